@@ -1,9 +1,17 @@
+use crate::config::{Config, load_config};
 use crate::determine_path;
 use clap_num::number_range;
 use std::env;
-use clap::Parser;
+use std::path::{Path, PathBuf};
+use clap::{Parser, Subcommand};
 use crate::Order;
-use crate::paths::is_valid_directory;
+use crate::bindings::Layout;
+use crate::grid_size::{GridSize, DEFAULT_CELL_SIZE, parse_grid};
+use crate::metadata::Format;
+use crate::paths::{is_valid_directory, Directories};
+use crate::selection::DEFAULT_SET;
+use crate::slideshow::{parse_dwell, parse_seconds};
+use crate::weighting::parse_weights;
 
 
 const DEFAULT_WIDTH: i32   = 1000;
@@ -11,17 +19,17 @@ const DEFAULT_HEIGHT: i32  = 1000;
 const WIDTH_ENV_VAR :&str  = "GALLSHWIDTH";
 const HEIGHT_ENV_VAR :&str = "GALLSHHEIGHT";
 
-fn less_than_11(s: &str) -> Result<usize, String> {
-    number_range(s,1,10)
+fn rank_stars(s: &str) -> Result<usize, String> {
+    number_range(s,0,3)
 }
 
 /// Gallery Show
 #[derive(Parser, Clone, Debug)]
-#[command(infer_subcommands = true, infer_long_args = true, author, version, about, long_about = None)]
+#[command(infer_long_args = true, author, version, about, long_about = None)]
 /// Pattern that displayed files must have
 pub struct Args {
-     /// Directory to search (default is set with variable GALLSHDIR)
-    pub directory: Option<String>,
+     /// Directory to search (default is set with variable GALLSHDIR, or in the config)
+    pub directory: Option<PathBuf>,
 
     /// Pattern (only files with names matching the regular expression will be displayed)
     #[arg(long)]
@@ -32,8 +40,8 @@ pub struct Args {
     pub maximized: bool,
 
     /// Ordered display (or random)
-    #[arg(short, long,value_name("order"), ignore_case(true), default_value_t = Order::Random)]
-    pub order: Order,
+    #[arg(short, long,value_name("order"), ignore_case(true))]
+    pub order: Option<Order>,
 
     /// Date ordered display
     #[arg(short, long, default_value_t = false)]
@@ -63,21 +71,49 @@ pub struct Args {
     #[arg(short, long, default_value_t = false)]
     pub label: bool,
 
-    /// Timer delay for next picture
+    /// Timer delay for next picture, in seconds (fractions allowed): starts a slideshow
+    #[arg(long, value_parser=parse_seconds)]
+    pub timer: Option<f64>,
+
+    /// Slideshow stops at the last page instead of starting over
+    #[arg(long, default_value_t = false)]
+    pub stop_at_end: bool,
+
+    /// Slideshow delay for pages with a given rank, as STARS=SECONDS (repeatable)
+    #[arg(long, value_parser=parse_dwell)]
+    pub dwell: Vec<(usize, f64)>,
+
+    /// Weighted random: R key, random order and random slideshow favour ranked pictures not shown recently
+    #[arg(long, default_value_t = false)]
+    pub weighted: bool,
+
+    /// Weights for 0, 1, 2 and 3 stars in weighted random mode
+    #[arg(long, value_parser=parse_weights)]
+    pub weights: Option<[f64; 4]>,
+
+    /// Keyboard layout of the key bindings preset (default is the bindings file preset, or bepo)
+    #[arg(long, ignore_case(true))]
+    pub layout: Option<Layout>,
+
+    /// Key bindings file (default is bindings.toml in the config directory)
     #[arg(long)]
-    pub timer: Option<u64>,
+    pub bindings: Option<String>,
 
     /// Reading List (only files in the list are displayed)
     #[arg(short, long)]
-    pub reading: Option<String>,
+    pub reading: Option<PathBuf>,
 
     /// Index of first image to read
     #[arg(short, long)]
     pub index: Option<usize>,
 
-    /// Grid Size
-    #[arg(short, long, value_parser=less_than_11)]
-    pub grid: Option<usize>,
+    /// Grid Size: 3 for 3×3, 5x3 for 5 columns and 3 rows, or auto to fit the window
+    #[arg(short, long, value_parser=parse_grid)]
+    pub grid: Option<GridSize>,
+
+    /// Cell size in pixels that the auto grid aims for
+    #[arg(long)]
+    pub cell_size: Option<i32>,
 
     /// From index number
     #[arg(long)]
@@ -89,7 +125,7 @@ pub struct Args {
 
     /// File to view
     #[arg(short, long)]
-    pub file: Option<String>,
+    pub file: Option<PathBuf>,
 
     /// Sample
     #[arg(long, default_value_t = false)]
@@ -103,13 +139,13 @@ pub struct Args {
     #[arg(short,long)]
     pub update_image_data: bool,
 
-    /// Copy selection to a target folder
-    #[arg(long)]
-    pub copy_selection: Option<String>,
+    /// Copy selection (default set or named set) to a target folder
+    #[arg(long,num_args=1..=2, value_names(&["DIRECTORY","SET"]))]
+    pub copy_selection: Option<Vec<String>>,
 
-    /// Move selection to a target folder
-    #[arg(long)]
-    pub move_selection: Option<String>,
+    /// Move selection (default set or named set) to a target folder
+    #[arg(long,num_args=1..=2, value_names(&["DIRECTORY","SET"]))]
+    pub move_selection: Option<Vec<String>>,
 
     /// Move entries with labels to target folder
     #[arg(short,long,value_delimiter=' ',num_args=2, value_names(&["LABEL","DIRECTORY"]))]
@@ -130,6 +166,130 @@ pub struct Args {
     /// Show palette extraction
     #[arg(short, long)]
     pub extraction: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Defaults from the user and gallery config files
+    #[arg(skip)]
+    pub config: Config,
+}
+
+/// Commands run on the entries without opening the window
+#[derive(Subcommand, Clone, Debug)]
+pub enum Command {
+    /// Export path, size, date, colors, rank, label, selection and palette of every entry
+    Export {
+        /// Output format
+        #[arg(long, ignore_case(true), default_value_t = Format::Csv)]
+        format: Format,
+
+        /// Output file (default is standard output)
+        #[arg(long)]
+        output: Option<String>,
+    },
+    /// Import ranks, labels and selection from an edited export file
+    Import {
+        /// File to import
+        file: String,
+
+        /// Input format (default is guessed from the file extension)
+        #[arg(long, ignore_case(true))]
+        format: Option<Format>,
+
+        /// Show the changes without saving them
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// List path, rank, label and selection of entries
+    List {
+        /// One JSON object per line
+        #[arg(long)]
+        json: bool,
+    },
+    /// Set the rank of entries (all entries, or only the given files)
+    Rank {
+        /// Number of stars
+        #[arg(value_parser=rank_stars)]
+        stars: usize,
+
+        /// Files to rank
+        files: Vec<String>,
+    },
+    /// Set the label of entries (all entries, or only the given files); an empty label removes it
+    Label {
+        /// Label
+        label: String,
+
+        /// Files to label
+        files: Vec<String>,
+    },
+    /// Select entries (all entries, or only the given files)
+    Select {
+        /// Unselect instead
+        #[arg(long)]
+        off: bool,
+
+        /// Selection set (default set if not given)
+        #[arg(long)]
+        set: Option<String>,
+
+        /// Files to select
+        files: Vec<String>,
+    },
+    /// Count entries and size by rank, label, selection, directory, file size and colors
+    Stats {
+        /// JSON output
+        #[arg(long)]
+        json: bool,
+    },
+    /// Write contact sheets (one png per page and a pdf) of the entries, using the grid size
+    ContactSheet {
+        /// Only the selected entries (default set or named set)
+        #[arg(long, num_args=0..=1, default_missing_value=DEFAULT_SET)]
+        selection: Option<String>,
+
+        /// Show palettes under the pictures
+        #[arg(long)]
+        palette: bool,
+
+        /// Output file name, without extension
+        #[arg(long, default_value_t = String::from("contact_sheet"))]
+        output: String,
+    },
+    /// Write a static html site (index pages, picture pages, pictures and thumbnails) of the entries
+    Html {
+        /// Target directory
+        directory: String,
+
+        /// Only the selected entries (default set or named set)
+        #[arg(long, num_args=0..=1, default_missing_value=DEFAULT_SET)]
+        selection: Option<String>,
+
+        /// Title of the index pages
+        #[arg(long, default_value_t = String::from("gallery"))]
+        title: String,
+
+        /// Pictures per index page (default: grid columns × grid rows)
+        #[arg(long)]
+        per_page: Option<usize>,
+    },
+    /// Create missing thumbnails and image data
+    Thumbnails,
+    /// Show the key bindings, from the layout preset and the bindings file
+    Keys {
+        /// Print them as a bindings file instead
+        #[arg(long)]
+        toml: bool,
+    },
+    /// Move entries with a label to a target folder
+    MoveLabel {
+        /// Label
+        label: String,
+
+        /// Target folder
+        directory: String,
+    },
 }
 
 impl Args {
@@ -145,7 +305,7 @@ impl Args {
                     }
                 },
                 _ => {
-                    DEFAULT_WIDTH
+                    self.config.width.unwrap_or(DEFAULT_WIDTH)
                 }
             }
         };
@@ -169,7 +329,7 @@ impl Args {
                     }
                 },
                 _ => {
-                    DEFAULT_HEIGHT
+                    self.config.height.unwrap_or(DEFAULT_HEIGHT)
                 }
             }
         };
@@ -182,42 +342,105 @@ impl Args {
     }
 
     pub fn copy_selection_target(&self) -> Result<Option<String>, String> {
-        selection_target(&self.copy_selection)
+        selection_target(&self.copy_selection.as_ref().map(|parameters| parameters[0].clone()).or(self.config.copy_target.clone()))
     }
 
     pub fn move_selection_target(&self) -> Result<Option<String>, String> {
-        selection_target(&self.move_selection)
+        selection_target(&self.move_selection.as_ref().map(|parameters| parameters[0].clone()).or(self.config.move_target.clone()))
+    }
+
+    pub fn copy_selection_set(&self) -> String {
+        selection_set(&self.copy_selection)
+    }
+
+    pub fn move_selection_set(&self) -> String {
+        selection_set(&self.move_selection)
     }
 
     pub fn all_label_move_target(&self) -> Result<Option<String>, String> {
-        selection_target(&self.all_label_move_target)
+        selection_target(&self.all_label_move_target.clone().or(self.config.label_target.clone()))
+    }
+
+    pub fn cell_size(&self) -> i32 {
+        self.cell_size.or(self.config.cell_size).filter(|size| *size > 0).unwrap_or(DEFAULT_CELL_SIZE)
     }
 
-    pub fn grid_size(&self) -> usize {
-        match self.grid {
-            Some(size) => if size > 0 && size <= 10 { size } else { if self.thumbnails { 10 } else { 1 } },
-            None => if self.thumbnails { 10 } else { 1 },
+    // columns and rows
+    pub fn grid_size(&self) -> (usize, usize) {
+        let config_grid = if self.thumbnails { None } else { self.config.grid() };
+        match self.grid.or(config_grid) {
+            Some(grid) => grid.dimensions(self.width(), self.height(), self.cell_size()),
+            None => if self.thumbnails { (10, 10) } else { (1, 1) },
         }
     }
 
+    // an order flag, or the order option, or the config order
     pub fn order(&self) -> Order {
-        Order::from_options(self.name, self.date, self.size, self.colors, self.value, self.palette, self.label)
+        if self.name || self.date || self.size || self.colors || self.value || self.palette || self.label {
+            Order::from_options(self.name, self.date, self.size, self.colors, self.value, self.palette, self.label)
+        } else {
+            self.order.or(self.config.order()).unwrap_or(Order::Random)
+        }
+    }
+
+    pub fn path(&self) -> PathBuf {
+        determine_path(self.directory.clone(), self.config.directory.clone())
+    }
+
+    pub fn maximized(&self) -> bool {
+        self.maximized || self.config.maximized.unwrap_or(false)
+    }
+
+    pub fn extraction(&self) -> bool {
+        self.extraction || self.config.extraction.unwrap_or(false)
+    }
+
+    // reads the config files; a config that can't be read is reported and ignored
+    pub fn with_config(mut self) -> Self {
+        self.config = match load_config(self.directory.clone()) {
+            Ok(config) => config,
+            Err(err) => {
+                eprintln!("{}", err);
+                Config::default()
+            },
+        };
+        self
     }
 
-    pub fn path(&self) -> String {
-        determine_path(self.directory.clone())
+    pub fn directories(&self) -> Directories {
+        self.config.directories()
     }
 
     pub fn sample(&self) -> bool {
         self.sample
     }
 
+    // options that used to be run by the gui are run as commands too
+    pub fn command(&self) -> Option<Command> {
+        if let Some(command) = &self.command {
+            Some(command.clone())
+        } else if self.update_image_data {
+            Some(Command::Thumbnails)
+        } else if let Some(parameters) = &self.move_label {
+            Some(Command::MoveLabel { label: parameters[0].clone(), directory: parameters[1].clone() })
+        } else {
+            None
+        }
+    }
+
+}
+
+pub fn selection_set(selection_arg: &Option<Vec<String>>) -> String {
+    match selection_arg.as_ref().and_then(|parameters| parameters.get(1)) {
+        Some(name) => name.to_string(),
+        None => String::from(DEFAULT_SET),
+    }
 }
 
 pub fn selection_target(target_arg: &Option<String>) -> Result<Option<String>, String> {
     match target_arg {
         Some(target) => {
-            if is_valid_directory(target) {
+            if is_valid_directory(Path::new(target)) {
                 Ok(Some(target.to_string()))
             } else {
                 Err(format!("path {} doesn't exist", target))
@@ -229,3 +452,17 @@ pub fn selection_target(target_arg: &Option<String>) -> Result<Option<String>, S
 
 
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_directory_named_like_the_start_of_a_subcommand_is_a_directory() {
+        let args = Args::try_parse_from(["gallsh", "st"]).unwrap();
+        assert_eq!(Some(PathBuf::from("st")), args.directory);
+        assert!(args.command.is_none());
+        let args = Args::try_parse_from(["gallsh", "stats"]).unwrap();
+        assert!(matches!(args.command, Some(Command::Stats { .. })));
+    }
+}
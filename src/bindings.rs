@@ -0,0 +1,513 @@
+use clap::builder::PossibleValue;
+use clap::ValueEnum;
+use crate::order::Order;
+use crate::paths::config_path;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs::read_to_string;
+use std::io::{Result, Error, ErrorKind};
+use std::path::PathBuf;
+
+pub const BINDINGS_FILE_NAME: &str = "bindings.toml";
+
+// what a key can be bound to
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+    NextPage, PrevPage, ForwardTenPages, BackwardTenPages, MoveFirst, MoveRandom, MoveToRegister,
+    Left, Right, Up, Down,
+    View, ScrollView, Filmstrip, CompareAdd, Compare, RealSize, ZoomIn, ZoomOut, CycleFit, KeepZoom, Palette, GridLimit, GridGrow, GridShrink,
+    RankNone, RankOne, RankTwo, RankThree,
+    ToggleSelect, StartGroup, CancelGroup, SelectPage, UnselectPage, UnselectAll, SaveSelection,
+    SelectionSet, PreviousSet, NextSet, SetUnion, SetIntersection, SetDifference,
+    LabelEdit, ApplyLastLabel, PointLabel, PointRemoveLabel, Search,
+    Order, DeleteRegisterDigit, ToggleDelete, CopyLabel, CopyTemp,
+    PageReadingList, ReadingList, ContactSheet,
+    Slideshow, Slower, Faster, Weighted,
+    Statistics, Info, CommandLine, Help, Quit, CopyMoveQuit,
+}
+
+const ACTIONS: [(Action, &str, &str); 65] = [
+    (Action::NextPage, "next-page", "move to next page"),
+    (Action::PrevPage, "prev-page", "move to previous page"),
+    (Action::ForwardTenPages, "forward-ten-pages", "move 10 pages forward"),
+    (Action::BackwardTenPages, "backward-ten-pages", "move 10 pages backward"),
+    (Action::MoveFirst, "move-first", "move to first picture"),
+    (Action::MoveRandom, "move-random", "move to a random picture"),
+    (Action::MoveToRegister, "move-to-register", "start typing a picture number, then move to it"),
+    (Action::Left, "left", "move left (scroll when zoomed)"),
+    (Action::Right, "right", "move right (scroll when zoomed)"),
+    (Action::Up, "up", "move up (scroll when zoomed)"),
+    (Action::Down, "down", "move down (scroll when zoomed)"),
+    (Action::View, "view", "view picture / back to the grid"),
+    (Action::ScrollView, "scroll-view", "scroll through all the thumbnails / back to the pages"),
+    (Action::Filmstrip, "filmstrip", "show or hide the previous and next pictures under the picture (view mode)"),
+    (Action::CompareAdd, "compare-add", "add the picture to the comparison, or remove it (4 pictures at most)"),
+    (Action::Compare, "compare", "compare the pictures side by side at the same zoom / back; arrows move the focus"),
+    (Action::RealSize, "real-size", "view real size (when not in grid mode)"),
+    (Action::ZoomIn, "zoom-in", "zoom in (view mode), also with the mouse wheel; drag to pan"),
+    (Action::ZoomOut, "zoom-out", "zoom out (view mode)"),
+    (Action::CycleFit, "cycle-fit", "cycle fit, fit width, fit height (view mode)"),
+    (Action::KeepZoom, "keep-zoom", "keep zoom when moving to another picture"),
+    (Action::Palette, "palette", "show palettes"),
+    (Action::GridLimit, "grid-limit", "keep moves inside the page"),
+    (Action::GridGrow, "grid-grow", "one more column and row in the grid"),
+    (Action::GridShrink, "grid-shrink", "one less column and row in the grid"),
+    (Action::RankNone, "rank-0", "remove rank"),
+    (Action::RankOne, "rank-1", "rank ☆"),
+    (Action::RankTwo, "rank-2", "rank ☆☆"),
+    (Action::RankThree, "rank-3", "rank ☆☆☆"),
+    (Action::ToggleSelect, "toggle-select", "toggle selection"),
+    (Action::StartGroup, "start-group", "start a selection/rank group"),
+    (Action::CancelGroup, "cancel-group", "cancel the selection/rank group"),
+    (Action::SelectPage, "select-page", "select the page"),
+    (Action::UnselectPage, "unselect-page", "unselect the page"),
+    (Action::UnselectAll, "unselect-all", "unselect all"),
+    (Action::SaveSelection, "save-selection", "save selected entries"),
+    (Action::SelectionSet, "selection-set", "switch to (or create) a named selection set"),
+    (Action::PreviousSet, "previous-set", "switch to previous selection set"),
+    (Action::NextSet, "next-set", "switch to next selection set"),
+    (Action::SetUnion, "set-union", "union with the previously active set"),
+    (Action::SetIntersection, "set-intersection", "intersection with the previously active set"),
+    (Action::SetDifference, "set-difference", "difference with the previously active set"),
+    (Action::LabelEdit, "label-edit", "enter label edit mode"),
+    (Action::ApplyLastLabel, "apply-last-label", "apply last label"),
+    (Action::PointLabel, "point-label", "label the selection/rank group"),
+    (Action::PointRemoveLabel, "point-remove-label", "remove the label of the selection/rank group"),
+    (Action::Search, "search", "search a picture by name"),
+    (Action::Order, "order", "change order (followed by an order key)"),
+    (Action::DeleteRegisterDigit, "delete-register-digit", "delete last digit of the picture number"),
+    (Action::ToggleDelete, "toggle-delete", "mark for deletion on quit"),
+    (Action::CopyLabel, "copy-label", "copy the label of the picture"),
+    (Action::CopyTemp, "copy-temp", "copy the picture to the temporary directory"),
+    (Action::PageReadingList, "page-reading-list", "save the current page as a reading list"),
+    (Action::ReadingList, "reading-list", "save all the pictures in current order as a reading list"),
+    (Action::ContactSheet, "contact-sheet", "save the current page as a contact sheet (png and pdf)"),
+    (Action::Slideshow, "slideshow", "start, pause or resume the slideshow"),
+    (Action::Slower, "slower", "slower slideshow"),
+    (Action::Faster, "faster", "faster slideshow"),
+    (Action::Weighted, "weighted", "toggle weighted random (favour ranked pictures not seen recently)"),
+    (Action::Statistics, "statistics", "show statistics"),
+    (Action::Info, "info", "show or hide the picture information panel"),
+    (Action::CommandLine, "command-line", "type a command (goto, rank, label, filter, sort…), TAB completes, ↑ ↓ browse history"),
+    (Action::Help, "help", "show or hide this help (ESC hides it too)"),
+    (Action::Quit, "quit", "quit"),
+    (Action::CopyMoveQuit, "copy-move-quit", "copy and move selections, delete marked pictures, then quit"),
+];
+
+impl Action {
+    pub fn name(&self) -> &'static str {
+        ACTIONS.iter().find(|(a, _, _)| a == self).map(|(_, n, _)| *n).unwrap()
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        ACTIONS.iter().find(|(_, n, _)| *n == name).map(|(a, _, _)| *a)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Layout {
+    Bepo, Azerty, Qwerty,
+}
+
+impl Layout {
+    pub fn from_name(name: &str) -> Option<Layout> {
+        match name {
+            "bepo" | "bépo" => Some(Layout::Bepo),
+            "azerty" => Some(Layout::Azerty),
+            "qwerty" => Some(Layout::Qwerty),
+            _ => None,
+        }
+    }
+}
+
+impl clap::ValueEnum for Layout {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[Layout::Bepo, Layout::Azerty, Layout::Qwerty]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            Layout::Bepo => PossibleValue::new("bepo").help("this is default"),
+            Layout::Azerty => PossibleValue::new("azerty"),
+            Layout::Qwerty => PossibleValue::new("qwerty"),
+        })
+    }
+}
+
+// keys bound the same way in every layout; key names are gdk key names
+const COMMON_KEYS: [(&str, Action); 60] = [
+    ("n", Action::NextPage),
+    ("space", Action::NextPage),
+    ("p", Action::PrevPage),
+    ("z", Action::MoveFirst),
+    ("R", Action::MoveRandom),
+    ("g", Action::MoveToRegister),
+    ("Left", Action::Left),
+    ("Right", Action::Right),
+    ("Up", Action::Up),
+    ("Down", Action::Down),
+    ("period", Action::View),
+    ("V", Action::ScrollView),
+    ("M", Action::Filmstrip),
+    ("A", Action::CompareAdd),
+    ("Z", Action::Compare),
+    ("f", Action::RealSize),
+    ("parenright", Action::ZoomIn),
+    ("parenleft", Action::ZoomOut),
+    ("F", Action::CycleFit),
+    ("K", Action::KeepZoom),
+    ("x", Action::Palette),
+    ("o", Action::GridLimit),
+    ("braceright", Action::GridGrow),
+    ("braceleft", Action::GridShrink),
+    ("comma", Action::ToggleSelect),
+    ("Return", Action::StartGroup),
+    ("Escape", Action::CancelGroup),
+    ("a", Action::SelectPage),
+    ("u", Action::UnselectPage),
+    ("U", Action::UnselectAll),
+    ("X", Action::SaveSelection),
+    ("numbersign", Action::SelectionSet),
+    ("bracketleft", Action::PreviousSet),
+    ("bracketright", Action::NextSet),
+    ("bar", Action::SetUnion),
+    ("asciitilde", Action::SetDifference),
+    ("slash", Action::LabelEdit),
+    ("asterisk", Action::ApplyLastLabel),
+    ("plus", Action::PointLabel),
+    ("minus", Action::PointRemoveLabel),
+    ("S", Action::Search),
+    ("equal", Action::Order),
+    ("BackSpace", Action::DeleteRegisterDigit),
+    ("D", Action::ToggleDelete),
+    ("c", Action::CopyLabel),
+    ("C", Action::CopyTemp),
+    ("w", Action::PageReadingList),
+    ("W", Action::ReadingList),
+    ("P", Action::ContactSheet),
+    ("T", Action::Slideshow),
+    ("percent", Action::Statistics),
+    ("colon", Action::CommandLine),
+    ("I", Action::Info),
+    ("less", Action::Slower),
+    ("greater", Action::Faster),
+    ("Y", Action::Weighted),
+    ("h", Action::Help),
+    ("q", Action::Quit),
+    ("Q", Action::CopyMoveQuit),
+    ("ampersand", Action::SetIntersection),
+];
+
+// bépo: t s r d as arrows on the home row, ranks on the top row
+const BEPO_KEYS: [(&str, Action); 13] = [
+    ("t", Action::Left),
+    ("r", Action::Right),
+    ("d", Action::Up),
+    ("s", Action::Down),
+    ("e", Action::NextPage),
+    ("i", Action::PrevPage),
+    ("j", Action::ForwardTenPages),
+    ("l", Action::BackwardTenPages),
+    ("k", Action::View),
+    ("dollar", Action::RankNone),
+    ("quotedbl", Action::RankOne),
+    ("guillemotleft", Action::RankTwo),
+    ("guillemotright", Action::RankThree),
+];
+
+// azerty: ranks on the unshifted top row, which takes & from set intersection
+const AZERTY_KEYS: [(&str, Action); 8] = [
+    ("agrave", Action::RankNone),
+    ("ampersand", Action::RankOne),
+    ("eacute", Action::RankTwo),
+    ("quotedbl", Action::RankThree),
+    ("ugrave", Action::SetIntersection),
+    ("N", Action::ForwardTenPages),
+    ("B", Action::BackwardTenPages),
+    ("v", Action::View),
+];
+
+// qwerty: vi-like h j k l arrows, which takes h from help
+const QWERTY_KEYS: [(&str, Action); 8] = [
+    ("h", Action::Left),
+    ("l", Action::Right),
+    ("k", Action::Up),
+    ("j", Action::Down),
+    ("question", Action::Help),
+    ("N", Action::ForwardTenPages),
+    ("B", Action::BackwardTenPages),
+    ("v", Action::View),
+];
+
+// keys that choose the order after the order key
+const ORDER_KEYS: [(&str, Order); 8] = [
+    ("c", Order::Colors),
+    ("d", Order::Date),
+    ("l", Order::Label),
+    ("n", Order::Name),
+    ("p", Order::Palette),
+    ("r", Order::Random),
+    ("s", Order::Size),
+    ("v", Order::Value),
+];
+
+#[derive(Deserialize, Debug, Default)]
+struct BindingsFile {
+    preset: Option<String>,
+    #[serde(default)]
+    keys: BTreeMap<String, String>,
+    #[serde(default)]
+    order: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bindings {
+    keys: BTreeMap<String, Action>,
+    orders: BTreeMap<String, Order>,
+}
+
+impl Bindings {
+    pub fn preset(layout: Layout) -> Self {
+        let mut keys: BTreeMap<String, Action> = BTreeMap::new();
+        let layout_keys: &[(&str, Action)] = match layout {
+            Layout::Bepo => &BEPO_KEYS,
+            Layout::Azerty => &AZERTY_KEYS,
+            Layout::Qwerty => &QWERTY_KEYS,
+        };
+        for (key, action) in COMMON_KEYS.iter().chain(layout_keys.iter()) {
+            keys.insert(key.to_string(), *action);
+        };
+        let orders: BTreeMap<String, Order> = ORDER_KEYS.iter().map(|(key, order)| (key.to_string(), *order)).collect();
+        Bindings { keys: keys, orders: orders }
+    }
+
+    pub fn action(&self, key_name: &str) -> Option<Action> {
+        self.keys.get(key_name).copied()
+    }
+
+    pub fn order(&self, key_name: &str) -> Option<Order> {
+        self.orders.get(key_name).copied()
+    }
+
+    // order names are the ones of the --order option
+    pub fn bind_order(&mut self, key_name: &str, order_name: &str) -> std::result::Result<(), String> {
+        if order_name == "none" {
+            self.orders.remove(key_name);
+            Ok(())
+        } else {
+            match Order::from_str(order_name, true) {
+                Ok(order) => {
+                    self.orders.insert(key_name.to_string(), order);
+                    Ok(())
+                },
+                Err(_) => Err(format!("unknown order {} for key {}", order_name, key_name)),
+            }
+        }
+    }
+
+    // binding a key to "none" removes it
+    pub fn bind(&mut self, key_name: &str, action_name: &str) -> std::result::Result<(), String> {
+        if action_name == "none" {
+            self.keys.remove(key_name);
+            Ok(())
+        } else {
+            match Action::from_name(action_name) {
+                Some(action) => {
+                    self.keys.insert(key_name.to_string(), action);
+                    Ok(())
+                },
+                None => Err(format!("unknown command {} for key {}", action_name, key_name)),
+            }
+        }
+    }
+
+    // a bindings file starts from a preset (or the given layout) and changes some keys;
+    // preset "none" starts with no key bound
+    //
+    //  preset = "qwerty"
+    //  [keys]
+    //  x = "next-page"
+    //  o = "none"
+    //  [order]
+    //  t = "date"
+    //
+    pub fn from_toml(content: &str, layout: Option<Layout>, errors: &mut Vec<String>) -> std::result::Result<Self, String> {
+        let file: BindingsFile = toml::from_str(content).map_err(|e| e.to_string())?;
+        let mut bindings = match (layout, file.preset.as_deref()) {
+            (Some(layout), _) => Bindings::preset(layout),
+            (None, Some("none")) => Bindings { keys: BTreeMap::new(), orders: BTreeMap::new() },
+            (None, Some(name)) => Bindings::preset(Layout::from_name(name).ok_or(format!("unknown preset {}", name))?),
+            (None, None) => Bindings::preset(Layout::Bepo),
+        };
+        for (key, action) in &file.keys {
+            if let Err(err) = bindings.bind(key, action) {
+                errors.push(err)
+            }
+        };
+        for (key, order) in &file.order {
+            if let Err(err) = bindings.bind_order(key, order) {
+                errors.push(err)
+            }
+        };
+        Ok(bindings)
+    }
+
+    pub fn keys_for(&self, action: Action) -> Vec<&str> {
+        self.keys.iter().filter(|(_, a)| **a == action).map(|(k, _)| k.as_str()).collect()
+    }
+
+    pub fn help(&self) -> String {
+        let mut lines: Vec<String> = vec![
+            String::from("commands:"),
+            String::new(),
+            String::from("0-3: rank, or picture number digits after the move to register key"),
+        ];
+        for (action, _, description) in ACTIONS.iter() {
+            let keys = self.keys_for(*action);
+            if !keys.is_empty() {
+                let keys: Vec<String> = keys.iter().map(|k| key_display(k)).collect();
+                lines.push(format!("{}: {}", keys.join(" "), description))
+            }
+        };
+        let orders: Vec<String> = self.orders.iter().map(|(key, order)| format!("{} {}", key_display(key), order_name(*order))).collect();
+        lines.push(format!("order keys: {}", orders.join(", ")));
+        lines.join("\n")
+    }
+
+    pub fn to_toml(&self) -> String {
+        let mut lines: Vec<String> = vec![String::from("preset = \"none\""), String::from("[keys]")];
+        for (key, action) in &self.keys {
+            lines.push(format!("{} = \"{}\"", toml_key(key), action.name()))
+        };
+        lines.push(String::from("[order]"));
+        for (key, order) in &self.orders {
+            lines.push(format!("{} = \"{}\"", toml_key(key), order_name(*order)))
+        };
+        lines.join("\n")
+    }
+}
+
+fn order_name(order: Order) -> String {
+    order.to_possible_value().map(|value| value.get_name().to_string()).unwrap_or(order.to_string())
+}
+
+fn toml_key(key: &str) -> String {
+    if key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        key.to_string()
+    } else {
+        format!("\"{}\"", key)
+    }
+}
+
+// the character for gdk key names that have one
+pub fn key_display(key_name: &str) -> String {
+    match key_name {
+        "ampersand" => "&", "asciitilde" => "~", "asterisk" => "*", "bar" => "|",
+        "bracketleft" => "[", "bracketright" => "]", "braceleft" => "{", "braceright" => "}",
+        "colon" => ":", "comma" => ",",
+        "dollar" => "$", "equal" => "=", "greater" => ">", "less" => "<",
+        "minus" => "-", "numbersign" => "#", "parenleft" => "(", "parenright" => ")",
+        "percent" => "%", "period" => ".", "plus" => "+", "question" => "?",
+        "quotedbl" => "\"", "slash" => "/", "guillemotleft" => "«", "guillemotright" => "»",
+        "agrave" => "à", "eacute" => "é", "ugrave" => "ù",
+        "Return" => "RET", "Escape" => "ESC", "BackSpace" => "BS", "space" => "SPC",
+        "Left" => "←", "Right" => "→", "Up" => "↑", "Down" => "↓",
+        other => other,
+    }.to_string()
+}
+
+pub fn bindings_file_path() -> PathBuf {
+    config_path().join(BINDINGS_FILE_NAME)
+}
+
+// the given file, or the user's bindings file if there's one, or the layout preset
+pub fn load_bindings(layout: Option<Layout>, file_name: Option<String>) -> Result<Bindings> {
+    let path = match file_name {
+        Some(name) => PathBuf::from(name),
+        None => {
+            let path = bindings_file_path();
+            if !path.exists() {
+                return Ok(Bindings::preset(layout.unwrap_or(Layout::Bepo)))
+            };
+            path
+        },
+    };
+    let content = read_to_string(&path)?;
+    let mut errors: Vec<String> = Vec::new();
+    let bindings = Bindings::from_toml(&content, layout, &mut errors)
+        .map_err(|err| Error::new(ErrorKind::Other, format!("can't read bindings {}: {}", path.display(), err)))?;
+    for error in errors {
+        eprintln!("{}: {}", path.display(), error)
+    };
+    Ok(bindings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_keep_their_layout_keys() {
+        let bepo = Bindings::preset(Layout::Bepo);
+        assert_eq!(Some(Action::Left), bepo.action("t"));
+        assert_eq!(Some(Action::RankThree), bepo.action("guillemotright"));
+        assert_eq!(Some(Action::Help), bepo.action("h"));
+        let qwerty = Bindings::preset(Layout::Qwerty);
+        assert_eq!(Some(Action::Left), qwerty.action("h"));
+        assert_eq!(Some(Action::Help), qwerty.action("question"));
+        let azerty = Bindings::preset(Layout::Azerty);
+        assert_eq!(Some(Action::RankOne), azerty.action("ampersand"));
+        assert_eq!(Some(Action::SetIntersection), azerty.action("ugrave"));
+    }
+
+    #[test]
+    fn every_action_has_a_unique_name() {
+        let names: std::collections::BTreeSet<&str> = ACTIONS.iter().map(|(_, n, _)| *n).collect();
+        assert_eq!(ACTIONS.len(), names.len());
+        for (action, name, _) in ACTIONS.iter() {
+            assert_eq!(Some(*action), Action::from_name(name))
+        }
+    }
+
+    #[test]
+    fn bindings_file_changes_a_preset() {
+        let mut errors = Vec::new();
+        let content = "preset = \"qwerty\"\n[keys]\nx = \"next-page\"\no = \"none\"\nm = \"fly\"\n";
+        let bindings = Bindings::from_toml(content, None, &mut errors).unwrap();
+        assert_eq!(Some(Action::NextPage), bindings.action("x"));
+        assert_eq!(None, bindings.action("o"));
+        assert_eq!(Some(Action::Left), bindings.action("h"));
+        assert_eq!(vec![String::from("unknown command fly for key m")], errors);
+        assert!(Bindings::from_toml("preset = \"dvorak\"", None, &mut errors).is_err());
+    }
+
+    #[test]
+    fn order_keys_can_be_rebound() {
+        let mut errors = Vec::new();
+        let content = "[order]\nt = \"date\"\nd = \"none\"\nk = \"shuffled\"\n";
+        let bindings = Bindings::from_toml(content, None, &mut errors).unwrap();
+        assert_eq!(Some(Order::Date), bindings.order("t"));
+        assert_eq!(None, bindings.order("d"));
+        assert_eq!(Some(Order::Colors), bindings.order("c"));
+        assert_eq!(vec![String::from("unknown order shuffled for key k")], errors);
+        assert!(bindings.help().contains("\norder keys: c colorsize, l label,"));
+    }
+
+    #[test]
+    fn help_lists_the_active_keys() {
+        let mut bindings = Bindings::preset(Layout::Bepo);
+        bindings.bind("y", "next-page").unwrap();
+        let help = bindings.help();
+        assert!(help.contains("\ne n SPC y: move to next page\n"));
+        assert!(help.contains("\n«: rank ☆☆\n"));
+    }
+
+    #[test]
+    fn bindings_written_as_toml_read_back_the_same() {
+        let bindings = Bindings::preset(Layout::Azerty);
+        let mut errors = Vec::new();
+        let read = Bindings::from_toml(&bindings.to_toml(), None, &mut errors).unwrap();
+        assert!(errors.is_empty());
+        assert_eq!(bindings, read);
+    }
+}
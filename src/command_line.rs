@@ -0,0 +1,262 @@
+use clap::ValueEnum;
+use crate::Order;
+use crate::rank::Rank;
+use std::fs::read_dir;
+use std::path::Path;
+
+// commands typed after : in the window
+#[derive(Clone, Debug, PartialEq)]
+pub enum LineCommand {
+    Goto(usize),
+    Rank(Rank),
+    Label(String),
+    Unlabel,
+    Filter(String),
+    Range(usize, usize),
+    All,
+    Sort(Order, bool),
+    CopySelection(String, Option<String>),
+    MoveSelection(String, Option<String>),
+    Info,
+    Reload,
+}
+
+// name, arguments, description
+pub const LINE_COMMANDS: [(&str, &str, &str); 12] = [
+    ("goto", "INDEX", "move to picture INDEX"),
+    ("rank", "STARS", "rank the picture (or the group) from 0 to 3 stars"),
+    ("label", "LABEL", "label the picture (or the group)"),
+    ("unlabel", "", "remove the label of the picture (or the group)"),
+    ("filter", "PATTERN", "only show pictures with paths matching the regular expression"),
+    ("range", "FROM TO", "only show pictures FROM to TO of the current list"),
+    ("all", "", "show all the pictures again"),
+    ("sort", "ORDER [desc]", "sort by colorsize, date, label, name, palette, random, size or value"),
+    ("copy-selection", "DIRECTORY [SET]", "copy the selection to DIRECTORY when quitting with Q"),
+    ("move-selection", "DIRECTORY [SET]", "move the selection to DIRECTORY when quitting with Q"),
+    ("info", "", "print picture information"),
+    ("reload", "", "read the pictures again and show what was added, removed or changed"),
+];
+
+const MAX_HISTORY: usize = 100;
+
+fn order_names() -> Vec<String> {
+    Order::value_variants().iter()
+        .filter_map(|o| o.to_possible_value())
+        .map(|v| v.get_name().to_string())
+        .collect()
+}
+
+fn index_arg(arg: Option<&str>, name: &str) -> Result<usize, String> {
+    arg.ok_or(format!("{} expected", name))?
+        .parse::<usize>()
+        .map_err(|_| format!("{} must be a number", name))
+}
+
+fn target_args(args: &[&str]) -> Result<(String, Option<String>), String> {
+    match args {
+        [directory] => Ok((directory.to_string(), None)),
+        [directory, set] => Ok((directory.to_string(), Some(set.to_string()))),
+        _ => Err(String::from("expected DIRECTORY [SET]")),
+    }
+}
+
+pub fn parse_line_command(line: &str) -> Result<LineCommand, String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let (name, args) = match words.split_first() {
+        Some((name, args)) => (*name, args),
+        None => return Err(String::from("no command")),
+    };
+    match name {
+        "goto" | "g" => Ok(LineCommand::Goto(index_arg(args.first().copied(), "INDEX")?)),
+        "rank" => {
+            let stars = index_arg(args.first().copied(), "STARS")?;
+            Rank::from_stars(stars).map(LineCommand::Rank).ok_or(String::from("STARS must be between 0 and 3"))
+        },
+        "label" => match args {
+            [] => Err(String::from("LABEL expected")),
+            _ => Ok(LineCommand::Label(args.join(" "))),
+        },
+        "unlabel" => Ok(LineCommand::Unlabel),
+        "filter" => match args {
+            [] => Ok(LineCommand::All),
+            _ => Ok(LineCommand::Filter(args.join(" "))),
+        },
+        "range" => {
+            let from = index_arg(args.first().copied(), "FROM")?;
+            let to = index_arg(args.get(1).copied(), "TO")?;
+            if from > to {
+                return Err(String::from("FROM must not be after TO"))
+            };
+            Ok(LineCommand::Range(from, to))
+        },
+        "all" => Ok(LineCommand::All),
+        "sort" => {
+            let order_name = args.first().ok_or(String::from("ORDER expected"))?;
+            let order = Order::from_str(order_name, true).map_err(|_| format!("unknown order {}", order_name))?;
+            match args.get(1).copied() {
+                None | Some("asc") => Ok(LineCommand::Sort(order, false)),
+                Some("desc") => Ok(LineCommand::Sort(order, true)),
+                Some(other) => Err(format!("expected asc or desc, not {}", other)),
+            }
+        },
+        "copy-selection" => target_args(args).map(|(directory, set)| LineCommand::CopySelection(directory, set)),
+        "move-selection" => target_args(args).map(|(directory, set)| LineCommand::MoveSelection(directory, set)),
+        "info" => Ok(LineCommand::Info),
+        "reload" => Ok(LineCommand::Reload),
+        other => Err(format!("unknown command {}", other)),
+    }
+}
+
+fn common_prefix(candidates: &[String]) -> Option<String> {
+    let first = candidates.first()?;
+    let mut prefix = first.clone();
+    for candidate in &candidates[1..] {
+        while !candidate.starts_with(&prefix) {
+            prefix.pop();
+        }
+    };
+    Some(prefix)
+}
+
+// directories starting with the partial path, with a trailing / so that completion can go on
+fn directory_candidates(partial: &str) -> Vec<String> {
+    let (parent, prefix) = match partial.rfind('/') {
+        Some(position) => (&partial[..position+1], &partial[position+1..]),
+        None => ("", partial),
+    };
+    let directory = if parent.is_empty() { Path::new(".") } else { Path::new(parent) };
+    match read_dir(directory) {
+        Ok(dir_entries) => {
+            let mut candidates: Vec<String> = dir_entries
+                .filter_map(|e| e.ok())
+                .filter(|e| e.path().is_dir())
+                .filter_map(|e| e.file_name().to_str().map(|name| name.to_string()))
+                .filter(|name| name.starts_with(prefix) && (prefix.starts_with('.') || !name.starts_with('.')))
+                .map(|name| format!("{}{}/", parent, name))
+                .collect();
+            candidates.sort();
+            candidates
+        },
+        Err(_) => vec![],
+    }
+}
+
+// completes the last word of the line: command names, then sort orders or directories;
+// also gives the candidates left when the completion is ambiguous
+pub fn complete_line(line: &str) -> (String, Vec<String>) {
+    let (head, last) = match line.rfind(' ') {
+        Some(position) => (&line[..position+1], &line[position+1..]),
+        None => ("", line),
+    };
+    let command = head.split_whitespace().next();
+    let candidates: Vec<String> = match (command, head.split_whitespace().count()) {
+        (None, _) => LINE_COMMANDS.iter().map(|(name, _, _)| name.to_string()).collect(),
+        (Some("sort"), 1) => order_names(),
+        (Some("sort"), 2) => vec![String::from("asc"), String::from("desc")],
+        (Some("copy-selection" | "move-selection"), 1) => directory_candidates(last),
+        _ => vec![],
+    };
+    let matching: Vec<String> = candidates.into_iter().filter(|c| c.starts_with(last)).collect();
+    match (matching.len(), common_prefix(&matching)) {
+        (1, _) if !matching[0].ends_with('/') => (format!("{}{} ", head, matching[0]), vec![]),
+        (_, Some(prefix)) if prefix.len() > last.len() => (format!("{}{}", head, prefix), vec![]),
+        _ if matching.len() > 1 => (line.to_string(), matching),
+        _ => (line.to_string(), vec![]),
+    }
+}
+
+pub fn line_commands_help() -> String {
+    LINE_COMMANDS.iter()
+        .map(|(name, args, description)| format!(":{} {}: {}", name, args, description))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+// lines entered, browsed from the latest with previous()
+#[derive(Clone, Debug, Default)]
+pub struct CommandHistory {
+    lines: Vec<String>,
+    position: Option<usize>,
+}
+
+impl CommandHistory {
+    pub fn push(&mut self, line: &str) {
+        self.position = None;
+        if line.trim().is_empty() || self.lines.last().map(|l| l == line).unwrap_or(false) {
+            return
+        };
+        if self.lines.len() >= MAX_HISTORY {
+            self.lines.remove(0);
+        };
+        self.lines.push(line.to_string())
+    }
+
+    pub fn reset(&mut self) {
+        self.position = None
+    }
+
+    pub fn previous(&mut self) -> Option<&str> {
+        let position = match self.position {
+            None => self.lines.len().checked_sub(1)?,
+            Some(0) => 0,
+            Some(position) => position - 1,
+        };
+        self.position = Some(position);
+        self.lines.get(position).map(|l| l.as_str())
+    }
+
+    // none when going past the latest line
+    pub fn next(&mut self) -> Option<&str> {
+        match self.position {
+            Some(position) if position + 1 < self.lines.len() => {
+                self.position = Some(position + 1);
+                self.lines.get(position + 1).map(|l| l.as_str())
+            },
+            _ => {
+                self.position = None;
+                None
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_are_parsed_with_their_arguments() {
+        assert_eq!(Ok(LineCommand::Goto(120)), parse_line_command("goto 120"));
+        assert_eq!(Ok(LineCommand::Rank(Rank::ThreeStars)), parse_line_command(" rank 3 "));
+        assert_eq!(Ok(LineCommand::Label(String::from("foo"))), parse_line_command("label foo"));
+        assert_eq!(Ok(LineCommand::Range(10, 20)), parse_line_command("range 10 20"));
+        assert_eq!(Ok(LineCommand::CopySelection(String::from("/tmp"), None)), parse_line_command("copy-selection /tmp"));
+        assert!(matches!(parse_line_command("sort date desc"), Ok(LineCommand::Sort(Order::Date, true))));
+        assert!(parse_line_command("rank 4").is_err());
+        assert!(parse_line_command("range 20 10").is_err());
+        assert!(parse_line_command("sort shoe").is_err());
+        assert!(parse_line_command("frobnicate").is_err());
+    }
+
+    #[test]
+    fn command_names_and_orders_are_completed() {
+        assert_eq!("goto ", complete_line("go").0);
+        assert_eq!("sort date ", complete_line("sort da").0);
+        assert_eq!("sort date desc ", complete_line("sort date d").0);
+        assert_eq!((String::from("ran"), vec![]), complete_line("ra"));
+        assert_eq!((String::from("ran"), vec![String::from("rank"), String::from("range")]), complete_line("ran"));
+    }
+
+    #[test]
+    fn history_is_browsed_from_the_latest_line() {
+        let mut history = CommandHistory::default();
+        history.push("goto 1");
+        history.push("rank 2");
+        history.push("rank 2");
+        assert_eq!(Some("rank 2"), history.previous());
+        assert_eq!(Some("goto 1"), history.previous());
+        assert_eq!(Some("goto 1"), history.previous());
+        assert_eq!(Some("rank 2"), history.next());
+        assert_eq!(None, history.next());
+    }
+}
@@ -0,0 +1,193 @@
+use crate::args::{Args, Command};
+use crate::bindings::load_bindings;
+use crate::contact_sheet::write_contact_sheets;
+use crate::entry::{Entry, EntryList};
+use crate::error::report_problems;
+use crate::html_export::{index_page_name, write_html_gallery};
+use crate::metadata::{Format, ImportSummary, apply_updates, export_entries, is_valid_label, parse_updates, record_for_entry};
+use crate::notices::Notices;
+use crate::picture_io::{ensure_thumbnails, entries_from_file, move_entries_with_label, read_entries, save_image_data};
+use crate::rank::Rank;
+use crate::repository::Repository;
+use crate::selection::DEFAULT_SET;
+use crate::statistics::Statistics;
+use std::fs::{File, read_to_string};
+use std::io::{Result, Error, ErrorKind, Write};
+use std::io;
+use std::path::Path;
+
+// run a subcommand without starting the gui; results go to stdout, messages to stderr
+pub fn run_command(args: &Args, command: &Command) -> Result<()> {
+    match command {
+        Command::Export { format, output } => export(&read_args_entries(args)?, *format, output),
+        Command::Import { file, format, dry_run } => import(read_args_entries(args)?, file, *format, *dry_run),
+        Command::List { json } => list(&read_args_entries(args)?, *json),
+        Command::Rank { stars, files } => {
+            let mut repository = headless_repository(args, files)?;
+            repository.rank_all(Rank::from_stars(*stars).unwrap());
+            print_paths(repository.entry_list());
+            Ok(())
+        },
+        Command::Label { label, files } => {
+            if !is_valid_label(label) {
+                return Err(Error::new(ErrorKind::Other, format!("invalid label: {}", label)))
+            };
+            let mut repository = headless_repository(args, files)?;
+            repository.label_all(label);
+            print_paths(repository.entry_list());
+            Ok(())
+        },
+        Command::Select { off, set, files } => {
+            let mut repository = headless_repository(args, files)?;
+            repository.switch_selection_set(set.as_deref().unwrap_or(DEFAULT_SET));
+            repository.select_all(!off);
+            print_paths(repository.entry_list());
+            Ok(())
+        },
+        Command::Stats { json } => stats(&read_args_entries(args)?, &args.path(), *json),
+        Command::ContactSheet { selection, palette, output } => {
+            let repository = selection_repository(args, selection)?;
+            let entries = selected_entries(&repository, selection);
+            let (cells_per_row, rows) = args.grid_size();
+            let files = write_contact_sheets(&entries, cells_per_row, rows, *palette, args.sample(), output)?;
+            print_paths_list(&files);
+            Ok(())
+        },
+        Command::Html { directory, selection, title, per_page } => {
+            let repository = selection_repository(args, selection)?;
+            let entries = selected_entries(&repository, selection);
+            let (cells_per_row, rows) = args.grid_size();
+            let per_page = per_page.unwrap_or(cells_per_row * rows);
+            let count = write_html_gallery(&entries, Path::new(directory), title, per_page)?;
+            println!("{} pictures exported to {}", count, Path::new(directory).join(index_page_name(0)).display());
+            Ok(())
+        },
+        Command::Thumbnails => {
+            let entry_list = read_args_entries(args)?;
+            ensure_thumbnails(&entry_list);
+            let total_size: u64 = entry_list.iter().map(|e| e.file_size).sum();
+            println!("{} entries, {} bytes", entry_list.len(), total_size);
+            Ok(())
+        },
+        Command::Keys { toml } => {
+            let bindings = load_bindings(args.layout, args.bindings.clone())?;
+            println!("{}", if *toml { bindings.to_toml() } else { bindings.help() });
+            Ok(())
+        },
+        Command::MoveLabel { label, directory } => Ok(move_entries_with_label(&read_args_entries(args)?, label, directory)?),
+    }
+}
+
+fn read_args_entries(args: &Args) -> Result<EntryList> {
+    Ok(read_entries(args.reading.clone(), args.file.clone(), args.path(), args.pattern.clone(), args.sample(), &args.directories())?)
+}
+
+// commands changing entries apply to the given files, or to all the entries
+fn headless_repository(args: &Args, files: &Vec<String>) -> Result<Repository> {
+    let entry_list = if files.is_empty() {
+        read_args_entries(args)?
+    } else {
+        let mut entry_list: EntryList = Vec::new();
+        let mut problems = Vec::new();
+        for file in files {
+            entry_list.extend(entries_from_file(Path::new(file), &args.directories(), &mut problems)?);
+        };
+        report_problems(&problems);
+        if entry_list.is_empty() {
+            return Err(Error::new(ErrorKind::Other, "no entries in the selection"))
+        };
+        entry_list
+    };
+    let mut repository = Repository::from_entries(entry_list, (1, 1), None, None, None, false);
+    repository.notices = Notices::headless();
+    repository.directories = args.directories();
+    repository.load_selection_sets(&args.path());
+    Ok(repository)
+}
+
+// the repository with the asked selection set active, if any
+fn selection_repository(args: &Args, selection: &Option<String>) -> Result<Repository> {
+    let mut repository = headless_repository(args, &vec![])?;
+    if let Some(name) = selection {
+        if !repository.has_selection_set(name) {
+            return Err(Error::new(ErrorKind::Other, format!("selection set {} doesn't exist", name)))
+        };
+        repository.switch_selection_set(name);
+    };
+    Ok(repository)
+}
+
+fn selected_entries<'a>(repository: &'a Repository, selection: &Option<String>) -> Vec<&'a Entry> {
+    repository.entry_list().iter().filter(|e| selection.is_none() || e.is_selected()).collect()
+}
+
+fn print_paths(entry_list: &EntryList) {
+    for entry in entry_list {
+        println!("{}", entry.original_file_path().display())
+    }
+}
+
+fn print_paths_list(paths: &Vec<String>) {
+    for path in paths {
+        println!("{}", path)
+    }
+}
+
+fn list(entry_list: &EntryList, json: bool) -> Result<()> {
+    let mut stdout = io::stdout().lock();
+    for entry in entry_list {
+        if json {
+            writeln!(stdout, "{}", serde_json::to_string(&record_for_entry(entry))?)?
+        } else {
+            writeln!(stdout, "{}\t{}\t{}\t{}",
+                entry.original_file_path().display(),
+                entry.image_data.rank.stars(),
+                entry.image_data.label,
+                entry.image_data.selected)?
+        }
+    };
+    stdout.flush()
+}
+
+fn stats(entry_list: &EntryList, gallery: &Path, json: bool) -> Result<()> {
+    let statistics = Statistics::from_entries(entry_list, gallery);
+    if json {
+        println!("{}", statistics.to_json()?)
+    } else {
+        println!("{}", statistics.to_text())
+    };
+    Ok(())
+}
+
+fn export(entry_list: &EntryList, format: Format, output: &Option<String>) -> Result<()> {
+    match output {
+        Some(file_name) => {
+            let mut file = File::create(file_name)?;
+            export_entries(entry_list, format, &mut file)?;
+            eprintln!("{} entries exported to {}", entry_list.len(), file_name);
+            Ok(())
+        },
+        None => {
+            let mut stdout = io::stdout().lock();
+            export_entries(entry_list, format, &mut stdout)?;
+            stdout.flush()
+        },
+    }
+}
+
+fn import(mut entry_list: EntryList, file_name: &str, format: Option<Format>, dry_run: bool) -> Result<()> {
+    let content = read_to_string(file_name)?;
+    let format = format.unwrap_or(Format::from_file_name(file_name));
+    let mut summary = ImportSummary::default();
+    let updates = parse_updates(&content, format, &mut summary.errors);
+    apply_updates(&mut entry_list, updates, &mut summary);
+    if !dry_run {
+        for &index in &summary.changed {
+            if let Err(err) = save_image_data(&entry_list[index]) {
+                summary.errors.push(format!("can't save image data {}: {}", entry_list[index].image_data_file_path().display(), err))
+            }
+        }
+    };
+    println!("{}{}", if dry_run { "dry run: " } else { "" }, summary.show());
+    Ok(())
+}
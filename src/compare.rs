@@ -0,0 +1,77 @@
+use std::path::{Path, PathBuf};
+
+pub const MAX_COMPARED: usize = 4;
+
+// pictures chosen for comparison, kept by file path so that they survive sorting and filtering
+#[derive(Clone, Debug, Default)]
+pub struct CompareList {
+    paths: Vec<PathBuf>,
+}
+
+impl CompareList {
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    // adds the picture, or removes it if it's already there; true when added
+    pub fn toggle(&mut self, path: impl AsRef<Path>) -> Result<bool, String> {
+        let path = path.as_ref();
+        if let Some(position) = self.paths.iter().position(|p| p == path) {
+            self.paths.remove(position);
+            Ok(false)
+        } else if self.paths.len() >= MAX_COMPARED {
+            Err(format!("can't compare more than {} pictures", MAX_COMPARED))
+        } else {
+            self.paths.push(path.to_path_buf());
+            Ok(true)
+        }
+    }
+}
+
+// columns and rows: side by side for 2 pictures, 2×2 for 3 or 4
+pub fn compare_layout(count: usize) -> (usize, usize) {
+    match count {
+        0 | 1 => (1, 1),
+        2 => (2, 1),
+        _ => (2, 2),
+    }
+}
+
+// the next (or previous) of the pictures compared, going round
+pub fn next_position(position: usize, count: usize, forward: bool) -> usize {
+    if count == 0 {
+        0
+    } else if forward {
+        (position + 1) % count
+    } else {
+        (position + count - 1) % count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pictures_are_added_then_removed_up_to_the_maximum() {
+        let mut list = CompareList::default();
+        assert_eq!(Ok(true), list.toggle("a.jpeg"));
+        assert_eq!(Ok(true), list.toggle("b.jpeg"));
+        assert_eq!(Ok(false), list.toggle("a.jpeg"));
+        assert_eq!(&[PathBuf::from("b.jpeg")], list.paths());
+        for path in ["c.jpeg", "d.jpeg", "e.jpeg"] {
+            list.toggle(path).unwrap();
+        };
+        assert!(list.toggle("f.jpeg").is_err());
+        assert_eq!(MAX_COMPARED, list.paths().len());
+    }
+
+    #[test]
+    fn focus_goes_round_the_compared_pictures() {
+        assert_eq!((2, 1), compare_layout(2));
+        assert_eq!((2, 2), compare_layout(3));
+        assert_eq!(0, next_position(3, 4, true));
+        assert_eq!(3, next_position(0, 4, false));
+        assert_eq!(1, next_position(0, 2, true));
+    }
+}
@@ -0,0 +1,140 @@
+use crate::compare::{MAX_COMPARED, compare_layout};
+use crate::gui::entry_opacity;
+use crate::picture_io::set_original_picture_file;
+use crate::repository::Repository;
+use crate::zoom::{Size, Zoom};
+use gtk::gio;
+use gtk::prelude::*;
+use gtk::{self, Align, Orientation};
+
+struct Pane {
+    vbox: gtk::Box,
+    scrolled_window: gtk::ScrolledWindow,
+    picture: gtk::Picture,
+    label: gtk::Label,
+}
+
+// the pictures compared, side by side or 2×2, at the same zoom; panning one pane pans them all
+pub struct CompareView {
+    pub grid: gtk::Grid,
+    panes: Vec<Pane>,
+}
+
+fn new_pane() -> Pane {
+    let picture = gtk::Picture::new();
+    picture.set_can_shrink(true);
+    picture.set_valign(Align::Center);
+    picture.set_halign(Align::Center);
+    let scrolled_window = gtk::ScrolledWindow::builder()
+        .hscrollbar_policy(gtk::PolicyType::Automatic)
+        .vscrollbar_policy(gtk::PolicyType::Automatic)
+        .build();
+    scrolled_window.set_hexpand(true);
+    scrolled_window.set_vexpand(true);
+    scrolled_window.set_child(Some(&picture));
+    let label = gtk::Label::new(None);
+    label.set_widget_name("picture_label");
+    let vbox = gtk::Box::new(Orientation::Vertical, 0);
+    vbox.append(&scrolled_window);
+    vbox.append(&label);
+    Pane { vbox: vbox, scrolled_window: scrolled_window, picture: picture, label: label }
+}
+
+fn intrinsic_size(picture: &gtk::Picture) -> Option<Size> {
+    picture.paintable()
+        .map(|paintable| (paintable.intrinsic_width() as f64, paintable.intrinsic_height() as f64))
+        .filter(|(width, height)| *width > 0.0 && *height > 0.0)
+}
+
+fn sync_adjustments(adjustments: Vec<gtk::Adjustment>) {
+    for adjustment in &adjustments {
+        let others: Vec<gtk::Adjustment> = adjustments.iter().filter(|a| *a != adjustment).cloned().collect();
+        adjustment.connect_value_changed(move |adjustment| {
+            for other in &others {
+                if (other.value() - adjustment.value()).abs() > 0.5 {
+                    other.set_value(adjustment.value())
+                }
+            }
+        });
+    }
+}
+
+impl CompareView {
+    pub fn new() -> Self {
+        let grid = gtk::Grid::new();
+        grid.set_row_homogeneous(true);
+        grid.set_column_homogeneous(true);
+        grid.set_hexpand(true);
+        grid.set_vexpand(true);
+        let panes: Vec<Pane> = (0..MAX_COMPARED).map(|_| new_pane()).collect();
+        for (position, pane) in panes.iter().enumerate() {
+            grid.attach(&pane.vbox, (position % 2) as i32, (position / 2) as i32, 1, 1)
+        };
+        sync_adjustments(panes.iter().map(|pane| pane.scrolled_window.hadjustment()).collect());
+        sync_adjustments(panes.iter().map(|pane| pane.scrolled_window.vadjustment()).collect());
+        CompareView { grid: grid, panes: panes }
+    }
+
+    // the callback gets the position of the pane clicked
+    pub fn connect_clicked<F: Fn(usize) + Clone + 'static>(&self, callback: F) {
+        for (position, pane) in self.panes.iter().enumerate() {
+            let gesture = gtk::GestureClick::new();
+            gesture.set_button(1);
+            let callback = callback.clone();
+            gesture.connect_pressed(move |_, _, _, _| callback(position));
+            pane.picture.add_controller(gesture)
+        }
+    }
+
+    // the panning follows the first pane
+    pub fn adjustments(&self) -> (gtk::Adjustment, gtk::Adjustment) {
+        (self.panes[0].scrolled_window.hadjustment(), self.panes[0].scrolled_window.vadjustment())
+    }
+
+    pub fn picture_size(&self) -> Option<Size> {
+        intrinsic_size(&self.panes[0].picture)
+    }
+
+    pub fn viewport_size(&self) -> Size {
+        (self.panes[0].scrolled_window.width() as f64, self.panes[0].scrolled_window.height() as f64)
+    }
+
+    pub fn apply_zoom(&self, zoom: Zoom) {
+        let viewport = self.viewport_size();
+        for pane in &self.panes {
+            match intrinsic_size(&pane.picture) {
+                Some(picture) if zoom != Zoom::Fit => {
+                    let (width, height) = zoom.size(picture, viewport);
+                    pane.picture.set_size_request(width as i32, height as i32)
+                },
+                _ => pane.picture.set_size_request(-1, -1),
+            }
+        }
+    }
+
+    pub fn refresh(&self, repository: &Repository) {
+        let indexes = repository.compared_indexes();
+        let (cols, rows) = compare_layout(indexes.len());
+        for (position, pane) in self.panes.iter().enumerate() {
+            let entry = indexes.get(position)
+                .filter(|_| position < cols * rows)
+                .and_then(|index| repository.entry_at_index(*index).map(|entry| (*index, entry)));
+            match entry {
+                Some((index, entry)) => {
+                    pane.vbox.set_visible(true);
+                    // a picture is only loaded again when the pane shows another one
+                    let shown = pane.picture.file().and_then(|file| file.path());
+                    if shown != gio::File::for_path(entry.original_file_path()).path() {
+                        if let Err(err) = set_original_picture_file(&pane.picture, entry) {
+                            eprintln!("{}", err)
+                        }
+                    };
+                    pane.picture.set_opacity(entry_opacity(entry));
+                    pane.label.set_text(&entry.label_display(index == repository.current_index(), repository.sample()))
+                },
+                None => pane.vbox.set_visible(false),
+            }
+        };
+        self.apply_zoom(repository.zoom())
+    }
+}
@@ -0,0 +1,156 @@
+use clap::ValueEnum;
+use crate::Order;
+use crate::grid_size::{GridSize, parse_grid};
+use crate::paths::{config_path, directory_setting, Directories};
+use serde::Deserialize;
+use std::fs::read_to_string;
+use std::io::{Result, Error, ErrorKind};
+use std::path::{Path, PathBuf};
+
+pub const CONFIG_FILE_NAME: &str = "config.toml";
+pub const GALLERY_CONFIG_FILE_NAME: &str = ".gallsh.toml";
+
+// defaults for the command line options; a value given on the command line or in an
+// environment variable wins over the gallery config, which wins over the user config
+//
+//  directory = "/home/me/photos"
+//  width = 1600
+//  height = 1000
+//  grid = "5x3"
+//  order = "date"
+//  copy_target = "/home/me/selected"
+//
+// grid = 3 or grid = "5x3" or grid = "auto"
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum GridSetting {
+    Side(usize),
+    Text(String),
+}
+
+impl GridSetting {
+    pub fn grid_size(&self) -> std::result::Result<GridSize, String> {
+        match self {
+            GridSetting::Side(side) => parse_grid(&side.to_string()),
+            GridSetting::Text(text) => parse_grid(text),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    pub directory: Option<String>,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub grid: Option<GridSetting>,
+    pub cell_size: Option<i32>,
+    pub order: Option<String>,
+    pub maximized: Option<bool>,
+    pub copy_target: Option<String>,
+    pub move_target: Option<String>,
+    pub label_target: Option<String>,
+    pub temporary: Option<String>,
+    pub thumbnails: Option<String>,
+    pub extraction: Option<bool>,
+}
+
+impl Config {
+    pub fn from_toml(content: &str) -> std::result::Result<Self, String> {
+        let config: Config = toml::from_str(content).map_err(|e| e.to_string())?;
+        if let Some(name) = &config.order {
+            Order::from_str(name, true).map_err(|_| format!("unknown order {}", name))?;
+        };
+        if let Some(grid) = &config.grid {
+            grid.grid_size()?;
+        };
+        if let Some(name) = &config.thumbnails {
+            if name.is_empty() || name.contains('/') || name.contains(std::path::MAIN_SEPARATOR) {
+                return Err(format!("thumbnails must be a directory name, not {}", name))
+            }
+        };
+        Ok(config)
+    }
+
+    // values not set here are taken from the other config
+    pub fn or(self, other: Config) -> Config {
+        Config {
+            directory: self.directory.or(other.directory),
+            width: self.width.or(other.width),
+            height: self.height.or(other.height),
+            grid: self.grid.or(other.grid),
+            cell_size: self.cell_size.or(other.cell_size),
+            order: self.order.or(other.order),
+            maximized: self.maximized.or(other.maximized),
+            copy_target: self.copy_target.or(other.copy_target),
+            move_target: self.move_target.or(other.move_target),
+            label_target: self.label_target.or(other.label_target),
+            temporary: self.temporary.or(other.temporary),
+            thumbnails: self.thumbnails.or(other.thumbnails),
+            extraction: self.extraction.or(other.extraction),
+        }
+    }
+
+    pub fn order(&self) -> Option<Order> {
+        self.order.as_ref().and_then(|name| Order::from_str(name, true).ok())
+    }
+
+    pub fn grid(&self) -> Option<GridSize> {
+        self.grid.as_ref().and_then(|grid| grid.grid_size().ok())
+    }
+
+    // the config only sets the directories their environment variable doesn't set
+    pub fn directories(&self) -> Directories {
+        Directories::new(self.temporary.as_deref(), self.thumbnails.as_deref())
+    }
+}
+
+pub fn config_file_path() -> PathBuf {
+    config_path().join(CONFIG_FILE_NAME)
+}
+
+fn read_config_file(path: &Path) -> Result<Config> {
+    if !path.exists() {
+        return Ok(Config::default())
+    };
+    let content = read_to_string(path)?;
+    Config::from_toml(&content)
+        .map_err(|err| Error::new(ErrorKind::Other, format!("can't read config {}: {}", path.display(), err)))
+}
+
+// the user config, then the config of the gallery directory (given on the command line,
+// with GALLSHDIR, or in the user config); a directory set in a gallery config is ignored
+pub fn load_config(directory: Option<PathBuf>) -> Result<Config> {
+    let user_config = read_config_file(&config_file_path())?;
+    let gallery_config = match directory_setting(directory, user_config.directory.clone()) {
+        Some(gallery) => Config { directory: None, ..read_config_file(&gallery.join(GALLERY_CONFIG_FILE_NAME))? },
+        None => Config::default(),
+    };
+    Ok(gallery_config.or(user_config))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_is_read_from_toml() {
+        let config = Config::from_toml("width = 1600\norder = \"Date\"\nthumbnails = \".thumbs\"").unwrap();
+        assert_eq!(Some(1600), config.width);
+        assert!(matches!(config.order(), Some(Order::Date)));
+        assert_eq!(None, config.grid());
+        assert!(Config::from_toml("order = \"shuffled\"").is_err());
+        assert!(Config::from_toml("grid = 12").is_err());
+        assert!(Config::from_toml("thumbnails = \"a/b\"").is_err());
+        assert!(Config::from_toml("colour = true").is_err());
+    }
+
+    #[test]
+    fn gallery_config_wins_over_user_config() {
+        let user = Config::from_toml("width = 1600\ngrid = 3").unwrap();
+        let gallery = Config::from_toml("grid = \"5x3\"").unwrap();
+        let config = gallery.or(user);
+        assert_eq!(Some(1600), config.width);
+        assert_eq!(Some(GridSize::Cells(5, 3)), config.grid());
+    }
+}
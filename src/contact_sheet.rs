@@ -0,0 +1,159 @@
+use crate::entry::Entry;
+use crate::picture_io::{PALETTE_HEIGHT, PALETTE_WIDTH, draw_palette};
+use gtk::cairo::{Context, Format, FontSlant, FontWeight, ImageSurface, PdfSurface};
+use gtk::gdk::prelude::GdkCairoContextExt;
+use gtk::gdk_pixbuf::Pixbuf;
+use std::fs::File;
+use std::io::{Result, Error, ErrorKind};
+
+// A4 landscape, in points for the pdf and in pixels for the png
+const PDF_WIDTH: f64 = 842.0;
+const PDF_HEIGHT: f64 = 595.0;
+const PNG_SCALE: f64 = 3.0;
+
+const MARGIN: f64 = 20.0;
+const CAPTION_LINES: f64 = 2.0;
+
+// same arrangement as the grid in the window: cells_per_row x rows cells,
+// each cell showing the picture, the palette if asked for, and the label
+#[derive(Clone, Debug, PartialEq)]
+pub struct SheetLayout {
+    pub width: f64,
+    pub height: f64,
+    pub cells_per_row: usize,
+    pub rows: usize,
+    pub with_palette: bool,
+}
+
+impl SheetLayout {
+    pub fn cells(&self) -> usize {
+        self.cells_per_row * self.rows
+    }
+
+    pub fn cell_size(&self) -> (f64, f64) {
+        ((self.width - 2.0 * MARGIN) / self.cells_per_row as f64, (self.height - 2.0 * MARGIN) / self.rows as f64)
+    }
+
+    pub fn cell_origin(&self, index: usize) -> (f64, f64) {
+        let (cell_width, cell_height) = self.cell_size();
+        let col = (index % self.cells_per_row) as f64;
+        let row = (index / self.cells_per_row) as f64;
+        (MARGIN + col * cell_width, MARGIN + row * cell_height)
+    }
+
+    pub fn font_size(&self) -> f64 {
+        (self.cell_size().1 / 16.0).clamp(4.0, 10.0)
+    }
+
+    pub fn palette_height(&self) -> f64 {
+        if self.with_palette { PALETTE_HEIGHT as f64 } else { 0.0 }
+    }
+
+    pub fn picture_size(&self) -> (f64, f64) {
+        let (cell_width, cell_height) = self.cell_size();
+        let padding = self.font_size() / 2.0;
+        let caption_height = CAPTION_LINES * self.font_size() * 1.2;
+        (cell_width - 2.0 * padding, cell_height - 2.0 * padding - caption_height - self.palette_height())
+    }
+}
+
+fn cairo_error(err: gtk::cairo::Error) -> Error {
+    Error::new(ErrorKind::Other, format!("cairo error: {}", err))
+}
+
+fn draw_cell(ctx: &Context, layout: &SheetLayout, index: usize, entry: &Entry, show_parent: bool) -> Result<()> {
+    let (x, y) = layout.cell_origin(index);
+    let (cell_width, _) = layout.cell_size();
+    let (picture_width, picture_height) = layout.picture_size();
+    let padding = layout.font_size() / 2.0;
+    if picture_width < 1.0 || picture_height < 1.0 {
+        return Ok(())
+    };
+    match Pixbuf::from_file_at_scale(entry.original_file_path(), picture_width as i32, picture_height as i32, true) {
+        Ok(pixbuf) => {
+            let picture_x = x + padding + (picture_width - pixbuf.width() as f64) / 2.0;
+            let picture_y = y + padding + (picture_height - pixbuf.height() as f64) / 2.0;
+            ctx.set_source_pixbuf(&pixbuf, picture_x, picture_y);
+            ctx.rectangle(picture_x, picture_y, pixbuf.width() as f64, pixbuf.height() as f64);
+            ctx.fill().map_err(cairo_error)?;
+        },
+        Err(err) => eprintln!("can't load {} for contact sheet: {}", entry.original_file_path().display(), err),
+    };
+    let mut bottom = y + padding + picture_height;
+    if layout.with_palette {
+        ctx.save().map_err(cairo_error)?;
+        ctx.translate(x + (cell_width - PALETTE_WIDTH as f64) / 2.0, bottom);
+        draw_palette(ctx, PALETTE_WIDTH, PALETTE_HEIGHT, &entry.image_data.palette);
+        ctx.restore().map_err(cairo_error)?;
+        bottom += layout.palette_height()
+    };
+    ctx.set_source_rgb(0.0, 0.0, 0.0);
+    ctx.set_font_size(layout.font_size());
+    for caption in [entry.original_file_name(), entry.label_display(false, show_parent)] {
+        bottom += layout.font_size() * 1.2;
+        ctx.move_to(x + padding, bottom);
+        ctx.show_text(&caption).map_err(cairo_error)?;
+    };
+    Ok(())
+}
+
+fn draw_page(ctx: &Context, layout: &SheetLayout, entries: &[&Entry], show_parent: bool) -> Result<()> {
+    ctx.set_source_rgb(1.0, 1.0, 1.0);
+    ctx.paint().map_err(cairo_error)?;
+    ctx.select_font_face("sans-serif", FontSlant::Normal, FontWeight::Normal);
+    for (index, entry) in entries.iter().enumerate() {
+        draw_cell(ctx, layout, index, entry, show_parent)?
+    };
+    Ok(())
+}
+
+// writes one png per page and a pdf with all the pages, returns the files written
+pub fn write_contact_sheets(entries: &[&Entry], cells_per_row: usize, rows: usize, with_palette: bool, show_parent: bool, base_name: &str) -> Result<Vec<String>> {
+    let mut files: Vec<String> = Vec::new();
+    let layout = SheetLayout { width: PDF_WIDTH, height: PDF_HEIGHT, cells_per_row: cells_per_row, rows: rows, with_palette: with_palette };
+    let pages: Vec<&[&Entry]> = entries.chunks(layout.cells()).collect();
+
+    let pdf_name = format!("{}.pdf", base_name);
+    let pdf_surface = PdfSurface::new(PDF_WIDTH, PDF_HEIGHT, &pdf_name).map_err(cairo_error)?;
+    let pdf_context = Context::new(&pdf_surface).map_err(cairo_error)?;
+    for page in &pages {
+        draw_page(&pdf_context, &layout, page, show_parent)?;
+        pdf_context.show_page().map_err(cairo_error)?;
+    };
+    pdf_surface.finish();
+    files.push(pdf_name);
+
+    for (number, page) in pages.iter().enumerate() {
+        let png_name = if pages.len() > 1 { format!("{}-{}.png", base_name, number + 1) } else { format!("{}.png", base_name) };
+        let surface = ImageSurface::create(Format::ARgb32, (PDF_WIDTH * PNG_SCALE) as i32, (PDF_HEIGHT * PNG_SCALE) as i32).map_err(cairo_error)?;
+        let context = Context::new(&surface).map_err(cairo_error)?;
+        context.scale(PNG_SCALE, PNG_SCALE);
+        draw_page(&context, &layout, page, show_parent)?;
+        let mut file = File::create(&png_name)?;
+        surface.write_to_png(&mut file).map_err(|err| Error::new(ErrorKind::Other, format!("can't write {}: {}", png_name, err)))?;
+        files.push(png_name);
+    };
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cells_are_laid_out_by_rows_like_the_grid() {
+        let layout = SheetLayout { width: 440.0, height: 240.0, cells_per_row: 2, rows: 2, with_palette: false };
+        assert_eq!(4, layout.cells());
+        assert_eq!((200.0, 100.0), layout.cell_size());
+        assert_eq!((20.0, 20.0), layout.cell_origin(0));
+        assert_eq!((220.0, 20.0), layout.cell_origin(1));
+        assert_eq!((20.0, 120.0), layout.cell_origin(2));
+    }
+
+    #[test]
+    fn palette_takes_room_from_the_picture() {
+        let without = SheetLayout { width: 440.0, height: 240.0, cells_per_row: 2, rows: 2, with_palette: false };
+        let with = SheetLayout { with_palette: true, ..without.clone() };
+        assert_eq!(without.picture_size().1 - PALETTE_HEIGHT as f64, with.picture_size().1);
+    }
+}
@@ -1,3 +1,4 @@
+use std::path::PathBuf;
 use std::rc::Rc;
 use std::time::SystemTime;
 use crate::rank::Rank;
@@ -8,28 +9,43 @@ pub type EntryList = Vec<Entry>;
 
 #[derive(PartialEq,Clone, Debug)]
 pub struct Entry {
-    file_path: Rc<String>,
+    file_path: Rc<PathBuf>,
     pub file_size: u64,
     pub modified_time: SystemTime,
     pub image_data: ImageData,
     pub delete: bool,
+    // the subdirectory holding the thumbnail, none when it's next to the picture
+    pub thumbnails: Option<String>,
 }
 
 
-pub fn make_entry(file_path:String, file_size:u64, colors:usize, modified_time:SystemTime, initial_rank: Rank) -> Entry {
+pub fn make_entry(file_path: impl Into<PathBuf>, file_size:u64, colors:usize, modified_time:SystemTime, initial_rank: Rank) -> Entry {
     return Entry { 
-        file_path: Rc::new(file_path),
+        file_path: Rc::new(file_path.into()),
         file_size: file_size,
         image_data: ImageData::new(colors, initial_rank),
         modified_time: modified_time,
         delete: false,
+        thumbnails: None,
     }
 }
 
+// the pictures the tests work on
+#[cfg(test)]
+pub fn example_entries() -> EntryList {
+    use chrono::DateTime;
+    let day = |text: &str| -> SystemTime { DateTime::parse_from_rfc2822(text).unwrap().into() };
+    vec!(
+        make_entry(String::from("photos/foo.jpeg"), 100, 5, day("Mon, 1 Jan 2024 11:52:37 GMT"), Rank::NoStar),
+        make_entry(String::from("photos/bar.jpeg"), 1000, 15, day("Sat, 1 Jul 2023 10:52:37 GMT"), Rank::ThreeStars),
+        make_entry(String::from("photos/qux.jpeg"), 10, 25, day("Mon, 1 Jan 2024 10:52:37 GMT"), Rank::TwoStars),
+        make_entry(String::from("photos/bub.jpeg"), 100, 25, day("Sun, 1 Jan 2023 10:52:37 GMT"), Rank::OneStar))
+}
+
 
 impl Entry {
-    pub fn image_data_file_path(&self) -> String {
-        image_data_file_path(&self.file_path)
+    pub fn image_data_file_path(&self) -> PathBuf {
+        image_data_file_path(&self.file_path, self.thumbnails.as_deref())
     }
 
     pub fn is_selected(&self) -> bool {
@@ -53,15 +69,20 @@ impl Entry {
         directory(&self.file_path)
     }
     pub fn original_file_name(&self) -> String {
-        original_file_name(&self.file_path)
+        original_file_name(&self.file_path, self.thumbnails.as_deref())
+    }
+
+    pub fn original_file_path(&self) -> PathBuf {
+        original_file_path(&self.file_path, self.thumbnails.as_deref())
     }
 
-    pub fn original_file_path(&self) -> String {
-        original_file_path(&self.file_path)
+    // the path as text, for display and for the lists that keep paths as text
+    pub fn path_text(&self) -> String {
+        self.original_file_path().to_string_lossy().to_string()
     }
 
-    pub fn thumbnail_file_path(&self) -> String {
-        thumbnail_file_path(&self.file_path)
+    pub fn thumbnail_file_path(&self) -> PathBuf {
+        thumbnail_file_path(&self.file_path, self.thumbnails.as_deref())
     }
 
     pub fn title_display(self) -> String {
@@ -117,7 +138,7 @@ mod tests {
     fn title_show_the_entry_information() {
         let mut entry = make_entry(String::from("photos/foo.jpeg"), 65636, 256, a_day(), Rank::ThreeStars);
         entry.image_data.selected = true;
-        assert_eq!("foo.jpeg △ [65636 256 ☆☆☆]", entry.title_display());
+        assert_eq!("foo.jpeg △ [65636 256 ☆☆☆] ", entry.title_display());
     }
 
     #[test]
@@ -125,11 +146,11 @@ mod tests {
         let mut entry = make_entry(String::from("photos/foo.jpeg"), 65636, 256, a_day(), Rank::ThreeStars);
         let without_focus = false;
         let with_focus = true;
-        assert_eq!("☆☆☆", entry.label_display(without_focus));
-        assert_eq!("▄☆☆☆", entry.label_display(with_focus));
+        assert_eq!("☆☆☆ ", entry.label_display(without_focus, false));
+        assert_eq!("▄☆☆☆ ", entry.label_display(with_focus, false));
         entry.image_data.selected = true;
-        assert_eq!("☆☆☆△", entry.label_display(without_focus));
-        assert_eq!("▄☆☆☆△", entry.label_display(with_focus));
+        assert_eq!("☆☆☆△ ", entry.label_display(without_focus, false));
+        assert_eq!("▄☆☆☆△ ", entry.label_display(with_focus, false));
     }
 
 }
@@ -0,0 +1,93 @@
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// what can go wrong with the pictures and the files kept beside them
+#[derive(Debug)]
+pub enum GalleryError {
+    Io(io::Error),
+    // a picture that can't be opened or decoded
+    Picture(PathBuf, String),
+    Thumbnail(PathBuf, String),
+    ImageData(PathBuf, String),
+    Pattern(String, String),
+    // a reading list hint that can't be applied to its picture
+    Hint(PathBuf, String),
+    NoEntries,
+    // arguments that don't fit the pictures read
+    Argument(String),
+}
+
+pub type Result<T> = std::result::Result<T, GalleryError>;
+
+impl GalleryError {
+    pub fn picture(path: &Path, reason: impl fmt::Display) -> Self {
+        GalleryError::Picture(path.to_path_buf(), reason.to_string())
+    }
+
+    pub fn thumbnail(path: &Path, reason: impl fmt::Display) -> Self {
+        GalleryError::Thumbnail(path.to_path_buf(), reason.to_string())
+    }
+
+    pub fn image_data(path: &Path, reason: impl fmt::Display) -> Self {
+        GalleryError::ImageData(path.to_path_buf(), reason.to_string())
+    }
+}
+
+impl fmt::Display for GalleryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GalleryError::Io(err) => write!(f, "{}", err),
+            GalleryError::Picture(path, reason) => write!(f, "can't read picture {}: {}", path.display(), reason),
+            GalleryError::Thumbnail(path, reason) => write!(f, "can't create thumbnail for {}: {}", path.display(), reason),
+            GalleryError::ImageData(path, reason) => write!(f, "can't find or create image data for {}: {}", path.display(), reason),
+            GalleryError::Pattern(pattern, reason) => write!(f, "can't parse regular expression {}: {}", pattern, reason),
+            GalleryError::Hint(path, reason) => write!(f, "ignoring reading list hint for {}: {}", path.display(), reason),
+            GalleryError::NoEntries => write!(f, "no entries in the selection"),
+            GalleryError::Argument(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for GalleryError {}
+
+impl From<io::Error> for GalleryError {
+    fn from(err: io::Error) -> Self {
+        GalleryError::Io(err)
+    }
+}
+
+// so that functions returning io results can still use ?
+impl From<GalleryError> for io::Error {
+    fn from(err: GalleryError) -> Self {
+        match err {
+            GalleryError::Io(err) => err,
+            other => io::Error::new(io::ErrorKind::Other, other),
+        }
+    }
+}
+
+// the files a scan had to leave out, printed once the scan is over
+pub fn report_problems(problems: &[GalleryError]) {
+    if !problems.is_empty() {
+        eprintln!("{} files left out:", problems.len());
+        for problem in problems {
+            eprintln!("  {}", problem)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_say_which_file_is_at_fault() {
+        let err = GalleryError::picture(Path::new("photos/foo.jpeg"), "corrupt data");
+        assert_eq!("can't read picture photos/foo.jpeg: corrupt data", err.to_string());
+        let err: io::Error = err.into();
+        assert_eq!("can't read picture photos/foo.jpeg: corrupt data", err.to_string());
+        let err: io::Error = GalleryError::from(io::Error::new(io::ErrorKind::NotFound, "gone")).into();
+        assert_eq!(io::ErrorKind::NotFound, err.kind());
+    }
+}
@@ -0,0 +1,78 @@
+use crate::gui::entry_opacity;
+use crate::picture_io::set_thumbnail_picture_file;
+use crate::repository::Repository;
+use gtk::prelude::*;
+use gtk::{self, Align, Orientation};
+
+// pictures shown on each side of the current one
+const FILMSTRIP_SIDE: usize = 4;
+const FILMSTRIP_HEIGHT: i32 = 100;
+
+// thumbnails of the pictures before and after the current one, in the current order;
+// cells are fixed, each showing the picture at its offset from the current one
+pub struct Filmstrip {
+    pub strip: gtk::Box,
+    cells: Vec<(gtk::Picture, gtk::Label)>,
+}
+
+fn offset(slot: usize) -> isize {
+    slot as isize - FILMSTRIP_SIDE as isize
+}
+
+impl Filmstrip {
+    pub fn new() -> Self {
+        let strip = gtk::Box::new(Orientation::Horizontal, 4);
+        strip.set_homogeneous(true);
+        strip.set_halign(Align::Center);
+        strip.set_visible(false);
+        let mut cells: Vec<(gtk::Picture, gtk::Label)> = Vec::new();
+        for _ in 0..2 * FILMSTRIP_SIDE + 1 {
+            let picture = gtk::Picture::new();
+            picture.set_size_request(FILMSTRIP_HEIGHT, FILMSTRIP_HEIGHT);
+            picture.set_can_shrink(true);
+            let label = gtk::Label::new(None);
+            label.set_widget_name("picture_label");
+            let vbox = gtk::Box::new(Orientation::Vertical, 0);
+            vbox.append(&picture);
+            vbox.append(&label);
+            strip.append(&vbox);
+            cells.push((picture, label))
+        };
+        Filmstrip { strip: strip, cells: cells }
+    }
+
+    // the callback gets the offset of the picture clicked from the current one
+    pub fn connect_clicked<F: Fn(isize) + Clone + 'static>(&self, callback: F) {
+        for (slot, (picture, _)) in self.cells.iter().enumerate() {
+            let gesture = gtk::GestureClick::new();
+            gesture.set_button(1);
+            let callback = callback.clone();
+            gesture.connect_pressed(move |_, _, _, _| callback(offset(slot)));
+            picture.add_controller(gesture)
+        }
+    }
+
+    pub fn refresh(&self, repository: &Repository) {
+        if repository.capacity() == 0 {
+            return
+        };
+        let current = repository.current_index() as isize;
+        for (slot, (picture, label)) in self.cells.iter().enumerate() {
+            let index = current + offset(slot);
+            let entry = if index >= 0 { repository.entry_at_index(index as usize) } else { None };
+            match entry {
+                Some(entry) => {
+                    picture.set_opacity(entry_opacity(entry));
+                    if let Err(err) = set_thumbnail_picture_file(picture, entry) {
+                        eprintln!("{}", err)
+                    };
+                    label.set_text(&entry.label_display(index == current, repository.sample()))
+                },
+                None => {
+                    picture.set_filename(None::<&str>);
+                    label.set_text("")
+                },
+            }
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use std::fmt;
+
+pub const MAX_GRID_SIDE: usize = 10;
+// target size of a cell in auto mode, in pixels
+pub const DEFAULT_CELL_SIZE: i32 = 250;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GridSize {
+    Cells(usize, usize), Auto,
+}
+
+impl GridSize {
+    // columns and rows; auto fits as many cells of about cell_size pixels as the window can take
+    pub fn dimensions(&self, width: i32, height: i32, cell_size: i32) -> (usize, usize) {
+        match self {
+            GridSize::Cells(cols, rows) => (*cols, *rows),
+            GridSize::Auto => {
+                let side = |length: i32| ((length / cell_size.max(1)).max(1) as usize).min(MAX_GRID_SIDE);
+                (side(width), side(height))
+            },
+        }
+    }
+}
+
+impl fmt::Display for GridSize {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GridSize::Cells(cols, rows) if cols == rows => write!(f, "{}", cols),
+            GridSize::Cells(cols, rows) => write!(f, "{}x{}", cols, rows),
+            GridSize::Auto => write!(f, "auto"),
+        }
+    }
+}
+
+fn grid_side(s: &str) -> Result<usize, String> {
+    match s.trim().parse::<usize>() {
+        Ok(n) if n >= 1 && n <= MAX_GRID_SIDE => Ok(n),
+        _ => Err(format!("grid sides must be between 1 and {}, not {}", MAX_GRID_SIDE, s)),
+    }
+}
+
+// N for a square grid, COLUMNSxROWS as in 5x3, or auto
+pub fn parse_grid(s: &str) -> Result<GridSize, String> {
+    if s == "auto" {
+        return Ok(GridSize::Auto)
+    };
+    match s.split_once(|c| c == 'x' || c == '×') {
+        Some((cols, rows)) => Ok(GridSize::Cells(grid_side(cols)?, grid_side(rows)?)),
+        None => {
+            let side = grid_side(s)?;
+            Ok(GridSize::Cells(side, side))
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grids_are_square_rectangular_or_auto() {
+        assert_eq!(Ok(GridSize::Cells(3, 3)), parse_grid("3"));
+        assert_eq!(Ok(GridSize::Cells(5, 3)), parse_grid("5x3"));
+        assert_eq!(Ok(GridSize::Auto), parse_grid("auto"));
+        assert!(parse_grid("11").is_err());
+        assert!(parse_grid("0x2").is_err());
+        assert_eq!("5x3", GridSize::Cells(5, 3).to_string());
+    }
+
+    #[test]
+    fn auto_grid_fits_the_window() {
+        assert_eq!((7, 4), GridSize::Auto.dimensions(1920, 1080, 250));
+        assert_eq!((1, 1), GridSize::Auto.dimensions(100, 100, 250));
+        assert_eq!((MAX_GRID_SIDE, 2), GridSize::Auto.dimensions(5000, 500, 250));
+        assert_eq!((5, 3), GridSize::Cells(5, 3).dimensions(100, 100, 250));
+    }
+}
@@ -3,7 +3,6 @@ use crate::timeout_add_local;
 use crate::Args;
 use std::time::Duration;
 use crate::Rank;
-use crate::Order;
 use gtk::gdk::Key;
 use gtk::Orientation;
 use std::cell::RefMut;
@@ -11,47 +10,109 @@ use gtk::Picture;
 use gtk::Label;
 use gtk::Grid;
 use gtk::CssProvider;
+use crate::bindings::{Action, Bindings, Layout, load_bindings};
+use crate::command_line::line_commands_help;
+use crate::compare_view::CompareView;
 use crate::direction::Direction;
 use crate::navigator::Coords;
-use crate::picture_io::{draw_palette, set_original_picture_file, set_thumbnail_picture_file};
+use crate::notices::Level;
+use crate::picture_io::{PALETTE_HEIGHT, PALETTE_WIDTH, draw_palette, prepare_picture, set_original_picture_file, set_thumbnail_picture_file};
 use crate::repository::Repository;
+use crate::scroll_view::{ScrollView, scroll_index};
+use crate::selection::SetOperation;
+use crate::slideshow::TICK_MILLIS;
+use crate::tiled_view::{TiledState, TilesMessage, draw_tiled_picture};
+use crate::watcher::{DirectoryWatcher, FileChange};
+use crate::zoom::{Size, Zoom, anchored_offset};
 use crate::entry::Entry;
+use crate::filmstrip::Filmstrip;
+use crate::info::{entry_info, info_text};
+use gtk::glib;
 use gtk::glib::clone;
 use gtk::glib::prelude::*;
 use gtk::prelude::*;
 use gtk::traits::WidgetExt;
 use gtk::{self, Align, ScrolledWindow};
-use std::cell::{RefCell};
+use std::cell::{Cell, RefCell};
+use std::path::PathBuf;
 use std::rc::Rc;
+use std::sync::mpsc;
+use std::thread;
 
 // build the main window
 // here's the deal:
 //
 //  window: ApplicationWindow
-//      stack: Stack
-//          grid_scrolled_window: ScrolledWindow
-//              panel: Grid
-//                  left_button: Label
-//                  grid: Grid
-//                      { cells_per_row x cells_per_row }
-//                      …
-//                      vbox: Box
-//                          image: Picture
-//                          label: Label
-//                      …
-//                  right_button: Label
-//          view_scrolled_window: ScrolledWindow
-//              view: Grid
-//                  image_view: Picture
+//      overlay: Overlay
+//        main_box: Box
+//          stack: Stack
+//              grid_scrolled_window: ScrolledWindow
+//                  panel: Grid
+//                      left_button: Label
+//                      grid: Grid
+//                          { cells_per_row x rows }
+//                          …
+//                          vbox: Box
+//                              image: Picture
+//                              label: Label
+//                          …
+//                      right_button: Label
+//              view_scrolled_window: ScrolledWindow
+//                  view: Grid
+//                      image_view: Picture
+//                      tiled_view: DrawingArea (large pictures, in place of image_view)
+//              scroll_view: ScrolledWindow
+//                  GridView (all the thumbnails)
+//              compare_view: Grid
+//                  { 2 or 2×2 panes: ScrolledWindow with a Picture, and a Label }
+//          filmstrip: Box (view mode)
+//          statistics_label: Label (overlay)
+//          info_panel: Box (overlay)
+//              info_label: Label
+//              info_palette: DrawingArea
+//          toast_label: Label (overlay)
+//          help_window: ScrolledWindow (overlay)
+//              help_label: Label
 //
 
+// toasts show the last notices for a while
+const TOAST_LINES: usize = 3;
+const TOAST_MILLIS: u64 = 3000;
+
+// pages with more cells than that show thumbnails, whatever the shape of the grid
+const MAX_ORIGINAL_CELLS: i32 = 25;
+
+// palette squares in the info panel
+const INFO_PALETTE_HEIGHT: i32 = 24;
+const INFO_PALETTE_WIDTH: i32 = INFO_PALETTE_HEIGHT * 9;
+
 pub struct Gui {
     pub application_window:   gtk::ApplicationWindow,
     pub stack:                gtk::Stack,
     pub grid_scrolled_window: gtk::ScrolledWindow,
     pub view_scrolled_window: gtk::ScrolledWindow,
+    pub scroll_view:          ScrollView,
+    pub scroll_on:            Rc<Cell<bool>>,
+    pub filmstrip:            Filmstrip,
+    pub compare_view:         CompareView,
+    pub filmstrip_on:         Rc<Cell<bool>>,
     pub picture_grid:       gtk::Grid,
+    pub left_button:        gtk::Label,
+    pub right_button:       gtk::Label,
     pub image_view:         gtk::Picture,
+    pub tiled_view:         gtk::DrawingArea,
+    pub tiled_state:        Rc<RefCell<TiledState>>,
+    pub tiles_sender:       glib::Sender<TilesMessage>,
+    pub statistics_label:   gtk::Label,
+    pub info_panel:         gtk::Box,
+    pub info_label:         gtk::Label,
+    pub info_palette:       gtk::DrawingArea,
+    pub info_colors:        Rc<Cell<[u32; 9]>>,
+    pub toast_label:        gtk::Label,
+    pub toast_generation:   Rc<Cell<u64>>,
+    pub help_window:        gtk::ScrolledWindow,
+    pub help_label:         gtk::Label,
+    pub bindings:           Bindings,
 }
 
 impl Gui {
@@ -59,14 +120,256 @@ impl Gui {
     pub fn view_mode(&self) -> bool {
         self.stack.visible_child().unwrap() == self.view_scrolled_window
     }
+
+    pub fn scroll_mode(&self) -> bool {
+        self.stack.visible_child().unwrap() == self.scroll_view.scrolled_window
+    }
+
+    pub fn compare_mode(&self) -> bool {
+        self.stack.visible_child().unwrap() == self.compare_view.grid
+    }
+
+    // the comparison needs at least 2 pictures in the list shown
+    pub fn toggle_compare(&self, repository: &Repository) {
+        if self.compare_mode() {
+            self.leave_view()
+        } else if repository.compared_indexes().len() >= 2 {
+            self.stack.set_visible_child(&self.compare_view.grid)
+        } else {
+            repository.notices.error(format!("choose at least 2 pictures to compare"))
+        }
+    }
+
+    // back from the view to the paged grid or to the scrolling thumbnails, whichever was shown
+    pub fn leave_view(&self) {
+        if self.scroll_on.get() {
+            self.stack.set_visible_child(&self.scroll_view.scrolled_window)
+        } else {
+            self.stack.set_visible_child(&self.grid_scrolled_window)
+        }
+    }
+
+    pub fn toggle_filmstrip(&self, repository: &Repository) {
+        self.filmstrip_on.set(!self.filmstrip_on.get());
+        self.filmstrip.strip.set_visible(self.filmstrip_on.get() && self.view_mode());
+        self.filmstrip.refresh(repository)
+    }
+
+    pub fn toggle_scroll(&self) {
+        self.scroll_on.set(!self.scroll_mode());
+        self.leave_view()
+    }
+
+    pub fn toggle_statistics(&self, repository: &Repository) {
+        if self.statistics_label.is_visible() {
+            self.statistics_label.set_visible(false)
+        } else {
+            self.statistics_label.set_text(&repository.statistics().to_text());
+            self.statistics_label.set_visible(true)
+        }
+    }
+
+    pub fn refresh_statistics(&self, repository: &Repository) {
+        if self.statistics_label.is_visible() {
+            self.statistics_label.set_text(&repository.statistics().to_text())
+        }
+    }
+
+    pub fn toggle_info(&self, repository: &Repository) {
+        if self.info_panel.is_visible() {
+            self.info_panel.set_visible(false)
+        } else {
+            self.info_panel.set_visible(true);
+            self.refresh_info(repository)
+        }
+    }
+
+    pub fn refresh_info(&self, repository: &Repository) {
+        if self.info_panel.is_visible() {
+            if let Some(entry) = repository.current_entry() {
+                self.info_label.set_text(&info_text(&entry_info(entry, &repository.current_sets())));
+                self.info_colors.set(entry.image_data.palette);
+                self.info_palette.queue_draw()
+            }
+        }
+    }
+
+    // shows the last notices gathered by the repository for a few seconds, errors in red
+    pub fn show_notices(&self, repository: &Repository) {
+        let notices = repository.notices.take();
+        if notices.is_empty() {
+            return
+        };
+        let lines: Vec<String> = notices.iter().skip(notices.len().saturating_sub(TOAST_LINES))
+            .map(|notice| {
+                let text = glib::markup_escape_text(&notice.text);
+                match notice.level {
+                    Level::Info => text.to_string(),
+                    Level::Error => format!("<span foreground=\"#ff6060\">{}</span>", text),
+                }
+            })
+            .collect();
+        self.toast_label.set_markup(&lines.join("\n"));
+        self.toast_label.set_visible(true);
+        let generation = self.toast_generation.get() + 1;
+        self.toast_generation.set(generation);
+        let toast_label = self.toast_label.clone();
+        let toast_generation = self.toast_generation.clone();
+        glib::timeout_add_local_once(Duration::from_millis(TOAST_MILLIS), move || {
+            // a later toast keeps the label shown
+            if toast_generation.get() == generation {
+                toast_label.set_visible(false)
+            }
+        });
+    }
+
+    pub fn toggle_help(&self) {
+        if self.help_window.is_visible() {
+            self.help_window.set_visible(false)
+        } else {
+            self.help_label.set_text(&format!("{}\n\ncommand line:\n\n{}", self.bindings.help(), line_commands_help()));
+            self.help_window.set_visible(true)
+        }
+    }
+
+    fn picture_size(&self) -> Option<Size> {
+        if self.tiled_view.is_visible() {
+            self.tiled_state.borrow().current.as_ref()
+                .map(|tiled| (tiled.pyramid.width as f64, tiled.pyramid.height as f64))
+        } else {
+            self.image_view.paintable()
+                .map(|paintable| (paintable.intrinsic_width() as f64, paintable.intrinsic_height() as f64))
+                .filter(|(width, height)| *width > 0.0 && *height > 0.0)
+        }
+    }
+
+    // large pictures are drawn from tiles, the others are loaded whole
+    pub fn setup_view(&self, repository_rc: &Rc<RefCell<Repository>>) {
+        if let Ok(repository) = repository_rc.try_borrow() {
+            if let Some(entry) = repository.current_entry() {
+                let tiled = self.tiled_state.borrow_mut().show(entry, &self.tiles_sender);
+                self.tiled_view.set_visible(tiled);
+                self.image_view.set_visible(!tiled);
+                if tiled {
+                    self.image_view.set_filename(None::<&str>);
+                    self.tiled_view.queue_draw();
+                    self.application_window.set_title(Some(&repository.title_display()));
+                    return
+                }
+            }
+        };
+        setup_image_view(&repository_rc, &self.image_view, &self.application_window)
+    }
+
+    fn viewport_size(&self) -> Size {
+        (self.view_scrolled_window.width() as f64, self.view_scrolled_window.height() as f64)
+    }
+
+    pub fn apply_zoom(&self, zoom: Zoom) {
+        if self.compare_mode() {
+            return self.compare_view.apply_zoom(zoom)
+        };
+        let widget: &gtk::Widget = if self.tiled_view.is_visible() { self.tiled_view.upcast_ref() } else { self.image_view.upcast_ref() };
+        match self.picture_size() {
+            Some(picture) if zoom != Zoom::Fit => {
+                let (width, height) = zoom.size(picture, self.viewport_size());
+                widget.set_size_request(width as i32, height as i32)
+            },
+            _ => widget.set_size_request(-1, -1),
+        }
+    }
+
+    // zooms in or out around the anchor (pointer position, or the centre of the view)
+    pub fn zoom_step(&self, repository: &mut Repository, zoom_in: bool, anchor: Option<(f64, f64)>) {
+        if self.compare_mode() {
+            if let Some(picture) = self.compare_view.picture_size() {
+                let viewport = self.compare_view.viewport_size();
+                let new_zoom = if zoom_in { repository.zoom().zoom_in(picture, viewport) } else { repository.zoom().zoom_out(picture, viewport) };
+                repository.set_zoom(new_zoom);
+                self.apply_zoom(new_zoom);
+                self.application_window.set_title(Some(&repository.title_display()))
+            };
+            return
+        };
+        if !self.view_mode() {
+            return
+        };
+        if let Some(picture) = self.picture_size() {
+            let viewport = self.viewport_size();
+            let old_zoom = repository.zoom();
+            let new_zoom = if zoom_in { old_zoom.zoom_in(picture, viewport) } else { old_zoom.zoom_out(picture, viewport) };
+            let (anchor_x, anchor_y) = anchor.unwrap_or((viewport.0 / 2.0, viewport.1 / 2.0));
+            let (old_scale, new_scale) = (old_zoom.scale(picture, viewport), new_zoom.scale(picture, viewport));
+            let hadjustment = self.view_scrolled_window.hadjustment();
+            let vadjustment = self.view_scrolled_window.vadjustment();
+            let x = anchored_offset(hadjustment.value(), anchor_x, old_scale, new_scale);
+            let y = anchored_offset(vadjustment.value(), anchor_y, old_scale, new_scale);
+            repository.set_zoom(new_zoom);
+            self.apply_zoom(new_zoom);
+            // the adjustments know the new size only after the next layout
+            glib::idle_add_local_once(move || {
+                hadjustment.set_value(x);
+                vadjustment.set_value(y)
+            });
+            self.application_window.set_title(Some(&repository.title_display()))
+        }
+    }
+
+    // the cells follow the grid size, which can change while running
+    fn fit_picture_grid(&self, repository_rc: &Rc<RefCell<Repository>>) {
+        let (cells_per_row, rows) = match repository_rc.try_borrow() {
+            Ok(repository) => (repository.cells_per_row(), repository.rows()),
+            Err(_) => return,
+        };
+        let fits = self.picture_grid.child_at(cells_per_row - 1, rows - 1).is_some()
+            && self.picture_grid.child_at(cells_per_row, 0).is_none()
+            && self.picture_grid.child_at(0, rows).is_none();
+        if !fits {
+            while let Some(child) = self.picture_grid.first_child() {
+                self.picture_grid.remove(&child)
+            };
+            attach_picture_cells(&self.picture_grid, &self.application_window, (cells_per_row, rows), repository_rc)
+        };
+        self.left_button.set_visible(cells_per_row * rows > 1);
+        self.right_button.set_visible(cells_per_row * rows > 1)
+    }
+
+    pub fn refresh(&self, repository_rc: &Rc<RefCell<Repository>>) {
+        self.fit_picture_grid(repository_rc);
+        if let Ok(repository) = repository_rc.try_borrow() {
+            self.refresh_statistics(&repository);
+            self.refresh_info(&repository)
+        };
+        if self.view_mode() {
+            self.setup_view(&repository_rc);
+            if let Ok(repository) = repository_rc.try_borrow() {
+                self.apply_zoom(repository.zoom());
+                if self.filmstrip.strip.is_visible() {
+                    self.filmstrip.refresh(&repository)
+                }
+            }
+        } else if self.compare_mode() {
+            if let Ok(mut repository) = repository_rc.try_borrow_mut() {
+                repository.keep_compare_focus();
+                self.compare_view.refresh(&repository);
+                self.application_window.set_title(Some(&repository.title_display()))
+            }
+        } else if self.scroll_mode() {
+            if let Ok(repository) = repository_rc.try_borrow() {
+                self.scroll_view.refresh(&repository);
+                self.application_window.set_title(Some(&repository.title_display()))
+            }
+        } else {
+            setup_picture_grid(&repository_rc, &self.picture_grid, &self.application_window)
+        }
+    }
 }
 
 
 pub fn setup_picture_grid(repository_rc: &Rc<RefCell<Repository>>, picture_grid: &gtk::Grid, window: &gtk::ApplicationWindow) {
     if let Ok(repository) = repository_rc.try_borrow() {
-        let cells_per_row = repository.cells_per_row();
-        for col in 0..cells_per_row {
-            for row in 0..cells_per_row {
+        for col in 0..repository.cells_per_row() {
+            for row in 0..repository.rows() {
                 let vbox = picture_grid.child_at(col,row).unwrap().downcast::<gtk::Box>().unwrap();
                 setup_picture_cell(window, &picture_grid, &vbox, (col, row), &repository_rc);
             }
@@ -80,7 +383,10 @@ pub fn setup_picture_grid(repository_rc: &Rc<RefCell<Repository>>, picture_grid:
 
 pub fn setup_image_view(repository_rc: &Rc<RefCell<Repository>>, picture_view: &gtk::Picture, window: &gtk::ApplicationWindow) {
     if let Ok(repository) = repository_rc.try_borrow() {
-        let entry = repository.current_entry().unwrap();
+        let entry = match repository.current_entry() {
+            Some(entry) => entry,
+            None => return,
+        };
         match set_original_picture_file(&picture_view, &entry) {
             Ok(_) => {
                 window.set_title(Some(&repository.title_display()))
@@ -93,38 +399,33 @@ pub fn setup_image_view(repository_rc: &Rc<RefCell<Repository>>, picture_view: &
     }
 }
 
-pub fn picture_hadjustment(window: &gtk::ApplicationWindow) -> gtk::Adjustment {
-    window
-        .child()
-        .and_then(|child| child.downcast::<gtk::Stack>().unwrap().visible_child())
+pub fn picture_hadjustment(stack: &gtk::Stack) -> gtk::Adjustment {
+    stack
+        .visible_child()
         .and_then(|child| child.downcast::<ScrolledWindow>().ok())
         .and_then(|sw| Some(sw.hadjustment()))
         .expect("Failed to get hadjustment").clone()
 }
-fn picture_vadjustment(window: &gtk::ApplicationWindow) -> gtk::Adjustment {
-    window
-        .child()
-        .and_then(|child| child.downcast::<gtk::Stack>().unwrap().visible_child())
+fn picture_vadjustment(stack: &gtk::Stack) -> gtk::Adjustment {
+    stack
+        .visible_child()
         .and_then(|child| child.downcast::<ScrolledWindow>().ok())
         .and_then(|sw| Some(sw.vadjustment()))
         .expect("Failed to get vadjustment").clone()
 }
 
+// none when the cell isn't there, e.g. while the grid is being rebuilt
 pub fn label_at_coords(grid: &gtk::Grid, coords: Coords) -> Option<gtk::Label> {
     let (col,row) = coords;
-    let vbox = grid.child_at(col as i32, row as i32).expect("can't find a child").downcast::<gtk::Box>().expect("can't downcast child to a Box");
-    let child = vbox.first_child().expect("can't access vbox first child").downcast::<gtk::Picture>().expect("can't downcast to Picture");
-    let next = child.next_sibling().expect("can't access vbox next child");
-    if next.widget_name() == "picture_label" {
-        Some(next.downcast::<gtk::Label>().unwrap())
-    } else {
-        let next_next = next.next_sibling().expect("can't access vbox next next child");
-        if next_next.widget_name() == "picture_label" {
-            Some(next_next.downcast::<gtk::Label>().unwrap())
-        } else {
-            panic!("can't find grid picture label");
-        }
-    }
+    let vbox = grid.child_at(col as i32, row as i32)?.downcast::<gtk::Box>().ok()?;
+    let mut child = vbox.first_child();
+    while let Some(widget) = child {
+        if widget.widget_name() == "picture_label" {
+            return widget.downcast::<gtk::Label>().ok()
+        };
+        child = widget.next_sibling()
+    };
+    None
 }
 
 pub fn set_label_text_at_coords(grid: &gtk::Grid, coords: Coords, text: String) {
@@ -134,10 +435,14 @@ pub fn set_label_text_at_coords(grid: &gtk::Grid, coords: Coords, text: String)
 }
 
 pub fn navigate(repository: &mut Repository, grid: &gtk::Grid, window: &gtk::ApplicationWindow, direction: Direction) {
+    let capacity = repository.capacity();
     let coords = repository.position();
-    let index = repository.index_from_position(coords).unwrap();
+    let index = match repository.index_from_position(coords) {
+        Some(index) if capacity > 0 => index,
+        _ => return,
+    };
     let cells_per_row = repository.cells_per_row() as usize;
-    let capacity = repository.capacity();
+    let rows = repository.rows() as usize;
     let column = coords.0 as usize;
     let row = coords.1 as usize;
     let next_index = if  !repository.grid_limit_on() {
@@ -152,7 +457,7 @@ pub fn navigate(repository: &mut Repository, grid: &gtk::Grid, window: &gtk::App
         match direction {
             Direction::Right => if column + 1 < cells_per_row { index + 1 } else { impossible },
             Direction::Left => if column > 0 { index - 1 } else { impossible },
-            Direction::Down => if row + 1 < cells_per_row { index + cells_per_row } else { impossible },
+            Direction::Down => if row + 1 < rows { index + cells_per_row } else { impossible },
             Direction::Up => if row > 0 { index - cells_per_row } else { impossible },
         }
     };
@@ -176,15 +481,18 @@ pub fn navigate(repository: &mut Repository, grid: &gtk::Grid, window: &gtk::App
     }
 }
 
+pub fn entry_opacity(entry: &Entry) -> f64 {
+    if entry.delete { 0.25 }
+    else if entry.image_data.selected { 0.50 } else { 1.0 }
+}
+
 pub fn picture_for_entry(entry: &Entry, repository: &Repository) -> gtk::Picture {
     let picture = gtk::Picture::new();
-    let opacity = if entry.delete { 0.25 }
-    else if entry.image_data.selected { 0.50 } else { 1.0 };
     picture.set_valign(Align::Center);
     picture.set_halign(Align::Center);
-    picture.set_opacity(opacity);
+    picture.set_opacity(entry_opacity(entry));
     picture.set_can_shrink(!repository.real_size());
-    let result = if repository.cells_per_row() < 10 {
+    let result = if repository.max_cells() <= MAX_ORIGINAL_CELLS {
         set_original_picture_file(&picture, &entry)
     } else {
         set_thumbnail_picture_file(&picture, &entry)
@@ -200,7 +508,7 @@ pub fn picture_for_entry(entry: &Entry, repository: &Repository) -> gtk::Picture
 }
 
 pub fn label_for_entry(entry: &Entry, index: usize, repository: &Repository) -> gtk::Label {
-    let is_current_entry = index == repository.current_index() && repository.cells_per_row() > 1;
+    let is_current_entry = index == repository.current_index() && repository.max_cells() > 1;
     let label = gtk::Label::new(Some(&entry.label_display(is_current_entry, repository.sample())));
     label.set_valign(Align::Center);
     label.set_halign(Align::Center);
@@ -223,12 +531,12 @@ pub fn drawing_area_for_entry(entry: &Entry) -> gtk::DrawingArea {
     drawing_area.set_valign(Align::Center);
     drawing_area.set_halign(Align::Center);
     let colors = entry.image_data.palette;
-    drawing_area.set_content_width(90);
-    drawing_area.set_content_height(10);
+    drawing_area.set_content_width(PALETTE_WIDTH);
+    drawing_area.set_content_height(PALETTE_HEIGHT);
     drawing_area.set_hexpand(true);
     drawing_area.set_vexpand(true);
     drawing_area.set_draw_func(move |_, ctx, _, _| {
-        draw_palette(ctx, 90, 10, &colors)
+        draw_palette(ctx, PALETTE_WIDTH, PALETTE_HEIGHT, &colors)
     });
     drawing_area
 }
@@ -241,7 +549,7 @@ pub fn set_label_text_at_current_position(grid: &gtk::Grid, repository: &Reposit
 }
 
 pub fn focus_on_cell_at_coords(coords: Coords, grid: &gtk::Grid, window: &gtk::ApplicationWindow, repository: &mut Repository, with_select: bool) {
-    if repository.cells_per_row() > 1 {
+    if repository.max_cells() > 1 {
         if repository.can_move_abs(coords) {
             set_label_text_at_current_position(&grid, &repository, false);
             repository.move_abs(coords);
@@ -255,7 +563,7 @@ pub fn focus_on_cell_at_coords(coords: Coords, grid: &gtk::Grid, window: &gtk::A
 }
 
 
-pub fn create_gui(application: &gtk::Application, width: i32, height: i32, grid_size: usize, repository_rc: &Rc<RefCell<Repository>>) -> Gui {
+pub fn create_gui(application: &gtk::Application, width: i32, height: i32, (cells_per_row, rows): (usize, usize), bindings: Bindings, repository_rc: &Rc<RefCell<Repository>>) -> Gui {
     let application_window = gtk::ApplicationWindow::builder()
         .application(application)
         .default_width(width)
@@ -296,24 +604,141 @@ pub fn create_gui(application: &gtk::Application, width: i32, height: i32, grid_
         let stack = gtk::Stack::new();
         stack.set_hexpand(true);
         stack.set_vexpand(true);
+        let scroll_view = ScrollView::new(&application_window, &repository_rc);
+        let scroll_on: Rc<Cell<bool>> = Rc::new(Cell::new(false));
         let _ = stack.add_child(&grid_scrolled_window);
         let _ = stack.add_child(&view_scrolled_window);
+        let _ = stack.add_child(&scroll_view.scrolled_window);
+        let compare_view = CompareView::new();
+        let _ = stack.add_child(&compare_view.grid);
         stack.set_visible_child(&view_scrolled_window);
         stack.set_visible_child(&grid_scrolled_window);
 
-        application_window.set_child(Some(&stack));
+        // the filmstrip is under the picture, only in view mode
+        let filmstrip = Filmstrip::new();
+        let filmstrip_on: Rc<Cell<bool>> = Rc::new(Cell::new(false));
+        let strip = filmstrip.strip.clone();
+        stack.connect_visible_child_notify(clone!(@strong strip, @strong filmstrip_on, @strong view_scrolled_window => move |stack| {
+            strip.set_visible(filmstrip_on.get() && stack.visible_child().map(|child| child == view_scrolled_window).unwrap_or(false))
+        }));
+        let main_box = gtk::Box::new(Orientation::Vertical, 0);
+        main_box.append(&stack);
+        main_box.append(&filmstrip.strip);
+
+        let overlay = gtk::Overlay::new();
+        overlay.set_child(Some(&main_box));
+        application_window.set_child(Some(&overlay));
+
+        let overlay_css_provider = CssProvider::new();
+        overlay_css_provider.load_from_data(
+            "
+            label {
+                background-color: rgba(0,0,0,0.8);
+                font-family: monospace;
+                padding: 1em;
+            }
+            ");
+        let statistics_label = Label::new(None);
+        statistics_label.set_halign(Align::Center);
+        statistics_label.set_valign(Align::Center);
+        statistics_label.style_context().add_provider(&overlay_css_provider, gtk::STYLE_PROVIDER_PRIORITY_APPLICATION);
+        statistics_label.set_visible(false);
+        overlay.add_overlay(&statistics_label);
+
+        let info_label = Label::new(None);
+        info_label.set_xalign(0.0);
+        info_label.style_context().add_provider(&overlay_css_provider, gtk::STYLE_PROVIDER_PRIORITY_APPLICATION);
+        let info_colors: Rc<Cell<[u32; 9]>> = Rc::new(Cell::new([0; 9]));
+        let info_palette = gtk::DrawingArea::new();
+        info_palette.set_content_width(INFO_PALETTE_WIDTH);
+        info_palette.set_content_height(INFO_PALETTE_HEIGHT);
+        info_palette.set_draw_func(clone!(@strong info_colors => move |_, ctx, _, _| {
+            draw_palette(ctx, INFO_PALETTE_WIDTH, INFO_PALETTE_HEIGHT, &info_colors.get())
+        }));
+        let info_panel = gtk::Box::new(Orientation::Vertical, 0);
+        info_panel.set_halign(Align::End);
+        info_panel.set_valign(Align::Start);
+        info_panel.append(&info_label);
+        info_panel.append(&info_palette);
+        info_panel.set_visible(false);
+        overlay.add_overlay(&info_panel);
+
+        let toast_label = Label::new(None);
+        toast_label.set_halign(Align::Center);
+        toast_label.set_valign(Align::End);
+        toast_label.set_margin_bottom(20);
+        toast_label.style_context().add_provider(&overlay_css_provider, gtk::STYLE_PROVIDER_PRIORITY_APPLICATION);
+        toast_label.set_visible(false);
+        overlay.add_overlay(&toast_label);
+
+        let help_label = Label::new(None);
+        help_label.set_xalign(0.0);
+        help_label.style_context().add_provider(&overlay_css_provider, gtk::STYLE_PROVIDER_PRIORITY_APPLICATION);
+        let help_window = ScrolledWindow::builder()
+            .hscrollbar_policy(gtk::PolicyType::Never)
+            .vscrollbar_policy(gtk::PolicyType::Automatic)
+            .propagate_natural_width(true)
+            .child(&help_label)
+            .build();
+        help_window.set_halign(Align::Center);
+        help_window.set_margin_top(20);
+        help_window.set_margin_bottom(20);
+        help_window.set_visible(false);
+        overlay.add_overlay(&help_window);
 
         let image_view = Picture::new();
         let view_gesture = gtk::GestureClick::new();
         view_gesture.set_button(0);
-        view_gesture.connect_pressed(clone!(@strong repository_rc, @strong stack, @strong grid_scrolled_window, @strong application_window => move |_,_, _, _| {
-            stack.set_visible_child(&grid_scrolled_window);
+        // released rather than pressed: a drag to pan cancels the click
+        let scroll_window = scroll_view.scrolled_window.clone();
+        view_gesture.connect_released(clone!(@strong repository_rc, @strong stack, @strong grid_scrolled_window, @strong scroll_window, @strong scroll_on, @strong application_window => move |_,_, _, _| {
+            if scroll_on.get() {
+                stack.set_visible_child(&scroll_window)
+            } else {
+                stack.set_visible_child(&grid_scrolled_window)
+            }
         }));
 
-        image_view.add_controller(view_gesture);
+        view.add_controller(view_gesture);
 
         view.attach(&image_view, 0, 0, 1, 1);
 
+        let tiled_state: Rc<RefCell<TiledState>> = Rc::new(RefCell::new(TiledState::default()));
+        let tiled_view = gtk::DrawingArea::new();
+        tiled_view.set_hexpand(true);
+        tiled_view.set_vexpand(true);
+        tiled_view.set_visible(false);
+        tiled_view.set_draw_func(clone!(@strong tiled_state, @strong view_scrolled_window => move |_, ctx, width, height| {
+            if let Some(tiled) = tiled_state.borrow_mut().current.as_mut() {
+                let hadjustment = view_scrolled_window.hadjustment();
+                let vadjustment = view_scrolled_window.vadjustment();
+                let visible = (hadjustment.value(), vadjustment.value(), hadjustment.page_size(), vadjustment.page_size());
+                draw_tiled_picture(ctx, tiled, (width as f64, height as f64), visible)
+            }
+        }));
+        for adjustment in [view_scrolled_window.hadjustment(), view_scrolled_window.vadjustment()] {
+            adjustment.connect_value_changed(clone!(@strong tiled_view => move |_| {
+                if tiled_view.is_visible() {
+                    tiled_view.queue_draw()
+                }
+            }));
+        };
+        // tiles are built in a thread, which tells the gui when a level is ready
+        let (tiles_sender, tiles_receiver) = glib::MainContext::channel::<TilesMessage>(glib::PRIORITY_DEFAULT);
+        tiles_receiver.attach(None, clone!(@strong tiled_state, @strong tiled_view => move |message| {
+            match message {
+                TilesMessage::Level => {},
+                TilesMessage::Done(directory) => tiled_state.borrow_mut().built(&directory),
+                TilesMessage::Failed(directory, err) => {
+                    eprintln!("{}", err);
+                    tiled_state.borrow_mut().built(&directory)
+                },
+            };
+            tiled_view.queue_draw();
+            Continue(true)
+        }));
+        view.attach(&tiled_view, 0, 0, 1, 1);
+
 
         let panel = Grid::new();
         panel.set_hexpand(true);
@@ -334,13 +759,12 @@ pub fn create_gui(application: &gtk::Application, width: i32, height: i32, grid_
         picture_grid.set_column_homogeneous(true);
         picture_grid.set_hexpand(true);
         picture_grid.set_vexpand(true);
-        if grid_size > 1 {
-            panel.attach(&left_button, 0, 0, 1, 1);
-            panel.attach(&picture_grid, 1, 0, 1, 1);
-            panel.attach(&right_button, 2, 0, 1, 1);
-        } else {
-            panel.attach(&picture_grid, 0, 0, 1, 1);
-        }
+        // hidden buttons take no room, so the single picture gets the whole panel
+        panel.attach(&left_button, 0, 0, 1, 1);
+        panel.attach(&picture_grid, 1, 0, 1, 1);
+        panel.attach(&right_button, 2, 0, 1, 1);
+        left_button.set_visible(cells_per_row * rows > 1);
+        right_button.set_visible(cells_per_row * rows > 1);
         left_gesture.set_button(1);
         left_gesture.connect_pressed(clone!(@strong repository_rc, @strong picture_grid, @strong picture_grid, @strong application_window => move |_,_,_,_| {
             {
@@ -360,18 +784,7 @@ pub fn create_gui(application: &gtk::Application, width: i32, height: i32, grid_
             setup_picture_grid(&repository_rc, &picture_grid, &application_window);
         }));
         right_button.add_controller(right_gesture);
-        for col in 0 .. grid_size as i32 {
-            for row in 0 .. grid_size as i32 {
-                let coords: Coords = (col,row);
-                let vbox = gtk::Box::new(Orientation::Vertical, 0);
-                vbox.set_valign(Align::Center);
-                vbox.set_halign(Align::Center);
-                vbox.set_hexpand(true);
-                vbox.set_vexpand(true);
-                setup_picture_cell(&application_window, &picture_grid, &vbox, coords, &repository_rc);
-                picture_grid.attach(&vbox, col as i32, row as i32, 1, 1);
-            }
-        }
+        attach_picture_cells(&picture_grid, &application_window, (cells_per_row as i32, rows as i32), &repository_rc);
         grid_scrolled_window.set_child(Some(&panel));
 
         let gui = Gui {
@@ -379,11 +792,46 @@ pub fn create_gui(application: &gtk::Application, width: i32, height: i32, grid_
             stack: stack,
             grid_scrolled_window: grid_scrolled_window,
             view_scrolled_window: view_scrolled_window,
+            scroll_view: scroll_view,
+            scroll_on: scroll_on,
+            filmstrip: filmstrip,
+            compare_view: compare_view,
+            filmstrip_on: filmstrip_on,
             picture_grid: picture_grid,
+            left_button: left_button,
+            right_button: right_button,
             image_view: image_view,
+            tiled_view: tiled_view,
+            tiled_state: tiled_state,
+            tiles_sender: tiles_sender,
+            statistics_label: statistics_label,
+            info_panel: info_panel,
+            info_label: info_label,
+            info_palette: info_palette,
+            info_colors: info_colors,
+            toast_label: toast_label,
+            toast_generation: Rc::new(Cell::new(0)),
+            help_window: help_window,
+            help_label: help_label,
+            bindings: bindings,
         };
         gui
 }
+fn attach_picture_cells(picture_grid: &gtk::Grid, window: &gtk::ApplicationWindow, (cells_per_row, rows): (i32, i32), repository_rc: &Rc<RefCell<Repository>>) {
+    for col in 0 .. cells_per_row {
+        for row in 0 .. rows {
+            let coords: Coords = (col,row);
+            let vbox = gtk::Box::new(Orientation::Vertical, 0);
+            vbox.set_valign(Align::Center);
+            vbox.set_halign(Align::Center);
+            vbox.set_hexpand(true);
+            vbox.set_vexpand(true);
+            setup_picture_cell(window, picture_grid, &vbox, coords, repository_rc);
+            picture_grid.attach(&vbox, col, row, 1, 1);
+        }
+    }
+}
+
 pub fn setup_picture_cell(window: &gtk::ApplicationWindow, grid: &gtk::Grid, vbox: &gtk::Box, coords: Coords, repository_rc: &Rc<RefCell<Repository>>) {
     if let Ok(repository) = repository_rc.try_borrow() {
         if let Some(index) = repository.index_from_position(coords) {
@@ -439,7 +887,21 @@ pub fn process_key(repository_rc: &Rc<RefCell<Repository>>, gui_rc: &Rc<RefCell<
     let mut refresh = true;
     if let Ok(mut repository) = repository_rc.try_borrow_mut() {
         if let Some(key_name) = key.name() {
-            if repository.label_edit_mode_on() {
+            if repository.command_edit_mode_on() {
+                match key_name.as_str() {
+                    "Return" => repository.confirm_command_edit(),
+                    "BackSpace" => repository.remove_edit_char(),
+                    "Escape" => repository.cancel_command_edit(),
+                    "Tab" => repository.complete_command_edit(),
+                    "Up" => repository.previous_command(),
+                    "Down" => repository.next_command(),
+                    _ => if let Some(ch) = key.to_unicode() {
+                        if !ch.is_control() {
+                            repository.add_edit_char(ch)
+                        }
+                    },
+                }
+            } else if repository.label_edit_mode_on() {
                 if key_name == "Return" {
                     repository.confirm_label_edit()
                 } else if key_name == "BackSpace" {
@@ -477,181 +939,370 @@ pub fn process_key(repository_rc: &Rc<RefCell<Repository>>, gui_rc: &Rc<RefCell<
                             }
                         }
                     }
-                } else {
-
-                    match key_name.as_str() {
-                        "0" | "1" | "2" | "3" | "4" | "5" | "6" | "7" | "8" | "9" => {
-                            let digit:usize = key_name.parse().unwrap();
-                            if repository.register_on() {
-                                repository.add_register_digit(digit)
-                            } else {
-                                match key_name.as_str() {
-                                    "0" => repository.point_rank(Rank::NoStar),
-                                    "1" => repository.point_rank(Rank::OneStar),
-                                    "2" => repository.point_rank(Rank::TwoStars),
-                                    "3" => repository.point_rank(Rank::ThreeStars),
-                                    other => println!("{}", other),
-                                }
+                } else if repository.selection_edit_mode_on() {
+                    if key_name == "Return" {
+                        repository.confirm_selection_edit()
+                    } else if key_name == "BackSpace" {
+                        repository.remove_edit_char()
+                    } else if key_name == "Escape" {
+                        repository.cancel_selection_edit()
+                    } else {
+                        if let Some(ch) = key.to_lower().to_unicode() {
+                            match ch {
+                                'a'..='z' => repository.add_edit_char(ch),
+                                '0'..='9' => repository.add_edit_char(ch),
+                                '-'|'_'   => repository.add_edit_char(ch),
+                                _ => {} ,
                             }
-                        },
-                        "BackSpace" => repository.delete_register_digit(),
-
-
-                        "Return" => repository.select_point(),
-                        "comma" => repository.point_select(),
-                        "Escape" => repository.cancel_point(),
-
-                        "equal" => repository.set_order_choice_on(),
-                        "c" if repository.order_choice_on() => repository.sort_by(Order::Colors),
-                        "d" if repository.order_choice_on() => repository.sort_by(Order::Date),
-                        "l" if repository.order_choice_on() => repository.sort_by(Order::Label),
-                        "n" if repository.order_choice_on() => repository.sort_by(Order::Name),
-                        "p" if repository.order_choice_on() => repository.sort_by(Order::Palette),
-                        "r" if repository.order_choice_on() => repository.sort_by(Order::Random),
-                        "s" if repository.order_choice_on() => repository.sort_by(Order::Size),
-                        "v" if repository.order_choice_on() => repository.sort_by(Order::Value),
-
-                        "g" => repository.move_to_register(),
-                        "z" => repository.move_to_index(0),
-                        "R" => repository.move_to_random_index(),
-
-                        "j" => repository.move_forward_ten_pages(),
-                        "l" => repository.move_backward_ten_pages(),
-                        "e" => repository.move_next_page(),
-                        "n" => repository.move_next_page(),
-                        "i" => repository.move_prev_page(),
-                        "p" => repository.move_prev_page(),
-
-
-                        "f" => repository.toggle_real_size(),
-                        "x" => repository.toggle_palette_extract(),
-                        "o" => repository.toggle_grid_limit(),
-
-
-                        "D" => repository.toggle_delete(),
-
-                        "c" => repository.copy_label(),
-                        "C" => repository.copy_temp(),
-                        "q" => { repository.quit(); refresh = false; gui.application_window.close() },
-                        "Q" => { repository.copy_move_and_quit(); refresh = false; gui.application_window.close() },
-                        "dollar" => repository.point_rank(Rank::NoStar),
-                        "quotedbl" => repository.point_rank(Rank::OneStar),
-                        "guillemotleft" => repository.point_rank(Rank::TwoStars),
-                        "guillemotright" => repository.point_rank(Rank::ThreeStars),
-
-                        "a" => repository.select_page(true),
-                        "u" => repository.select_page(false),
-                        "U" => repository.select_all(false),
-                        "X" => repository.save_select_entries(),
-
-                        "slash" => repository.begin_label_edit(),
-                        "S" => repository.begin_search_edit(),
-
-                        "asterisk" => repository.apply_last_label(),
-
-                        "plus" => repository.point_label(),
-                        "minus" => repository.point_remove_label(),
-
-                        "h" => repository.help(),
-                        "period"|"k" => {
-                            if gui.view_mode() {
-                                gui.stack.set_visible_child(&gui.grid_scrolled_window)
-                            } else {
-                                gui.stack.set_visible_child(&gui.view_scrolled_window);
-                                setup_image_view(&repository_rc, &gui.image_view, &gui.application_window)
-                            }
-                        },
-                        "colon" => {
-                            println!("{}", repository.title_display());
-                            println!("{}", repository.current_entry().expect("can't access current entry").original_file_path());
-                            println!("{:?}", repository.current_entry().expect("can't access current entry").image_data);
-
-                        },
-                        "space" => repository.move_next_page(),
-                        "Right"|"r" => {
-                            refresh = !repository.real_size();
-                            arrow_command(Direction::Right, &gui, &mut repository, &repository_rc)
-                        },
-                        "Left"|"t" => {
-                            refresh = !repository.real_size();
-                            arrow_command(Direction::Left, &gui, &mut repository, &repository_rc)
-                        },
-                        "Down"|"s" => {
-                            refresh = !repository.real_size();
-                            arrow_command(Direction::Down, &gui, &mut repository, &repository_rc)
-                        },
-                        "Up"|"d" => {
-                            refresh = !repository.real_size();
-                            arrow_command(Direction::Up, &gui, &mut repository, &repository_rc)
-                        },
-                        other => println!("{}", other),
+                        }
+                    }
+                } else {
+                    // after the order key or the move to register key, the key completes the command;
+                    // digits rank the pictures unless they are bound
+                    let digit: Option<usize> = key_name.parse().ok();
+                    if gui.help_window.is_visible() && key_name == "Escape" {
+                        gui.help_window.set_visible(false);
+                        refresh = false
+                    } else if let (Some(digit), true) = (digit, repository.register_on()) {
+                        repository.add_register_digit(digit)
+                    } else if let (true, Some(order)) = (repository.order_choice_on(), gui.bindings.order(&key_name)) {
+                        repository.sort_by(order)
+                    } else if let Some(action) = gui.bindings.action(&key_name) {
+                        refresh = run_action(action, &gui, &mut repository, &repository_rc)
+                    } else if let Some(rank) = digit.and_then(Rank::from_stars) {
+                        repository.point_rank(rank)
                     }
                 }
             };
         }
     }
     if refresh {
-        if gui.stack.visible_child().unwrap() == gui.grid_scrolled_window {
-            setup_picture_grid(&repository_rc, &gui.picture_grid, &gui.application_window)
-        } else {
-            setup_image_view(&repository_rc, &gui.image_view, &gui.application_window)
-        }
+        gui.refresh(&repository_rc)
     }
     gtk::Inhibit(false)
 }
+
+// runs the action bound to a key, returns false when the display doesn't need a refresh
+fn run_action(action: Action, gui: &Gui, repository: &mut Repository, repository_rc: &Rc<RefCell<Repository>>) -> bool {
+    // paging moves among the pictures compared, jumps would leave them
+    if gui.compare_mode() {
+        match action {
+            Action::NextPage => { repository.compare_move(true); return true },
+            Action::PrevPage => { repository.compare_move(false); return true },
+            Action::MoveFirst => { repository.compare_focus(0); return true },
+            Action::ForwardTenPages | Action::BackwardTenPages | Action::MoveRandom | Action::MoveToRegister => return false,
+            _ => {},
+        }
+    };
+    match action {
+        Action::NextPage => repository.move_next_page(),
+        Action::PrevPage => repository.move_prev_page(),
+        Action::ForwardTenPages => repository.move_forward_ten_pages(),
+        Action::BackwardTenPages => repository.move_backward_ten_pages(),
+        Action::MoveFirst => repository.move_to_index(0),
+        Action::MoveRandom => repository.move_to_random_index(),
+        Action::MoveToRegister => repository.move_to_register(),
+        Action::Left | Action::Right | Action::Up | Action::Down => {
+            let refresh = !is_scrolling(gui, repository);
+            let direction = match action {
+                Action::Left => Direction::Left,
+                Action::Right => Direction::Right,
+                Action::Up => Direction::Up,
+                _ => Direction::Down,
+            };
+            arrow_command(direction, gui, repository, repository_rc);
+            return refresh
+        },
+        Action::View => {
+            if gui.view_mode() {
+                gui.leave_view()
+            } else {
+                gui.stack.set_visible_child(&gui.view_scrolled_window);
+                gui.setup_view(repository_rc)
+            }
+        },
+        Action::ScrollView => gui.toggle_scroll(),
+        Action::Filmstrip => { gui.toggle_filmstrip(repository); return false },
+        Action::CompareAdd => repository.toggle_compare_entry(),
+        Action::Compare => gui.toggle_compare(repository),
+        Action::RealSize => repository.toggle_real_size(),
+        Action::ZoomIn => { gui.zoom_step(repository, true, None); return false },
+        Action::ZoomOut => { gui.zoom_step(repository, false, None); return false },
+        Action::CycleFit => {
+            repository.cycle_fit();
+            gui.apply_zoom(repository.zoom());
+            gui.application_window.set_title(Some(&repository.title_display()));
+            return false
+        },
+        Action::KeepZoom => repository.toggle_keep_zoom(),
+        Action::Palette => repository.toggle_palette_extract(),
+        Action::GridLimit => repository.toggle_grid_limit(),
+        Action::GridGrow => repository.resize_grid(true),
+        Action::GridShrink => repository.resize_grid(false),
+        Action::RankNone => repository.point_rank(Rank::NoStar),
+        Action::RankOne => repository.point_rank(Rank::OneStar),
+        Action::RankTwo => repository.point_rank(Rank::TwoStars),
+        Action::RankThree => repository.point_rank(Rank::ThreeStars),
+        Action::ToggleSelect => repository.point_select(),
+        Action::StartGroup => repository.select_point(),
+        Action::CancelGroup => repository.cancel_point(),
+        Action::SelectPage => repository.select_page(true),
+        Action::UnselectPage => repository.select_page(false),
+        Action::UnselectAll => repository.select_all(false),
+        Action::SaveSelection => repository.save_select_entries(),
+        Action::SelectionSet => repository.begin_selection_edit(),
+        Action::PreviousSet => repository.cycle_selection_set(false),
+        Action::NextSet => repository.cycle_selection_set(true),
+        Action::SetUnion => repository.combine_selection_set(SetOperation::Union),
+        Action::SetIntersection => repository.combine_selection_set(SetOperation::Intersection),
+        Action::SetDifference => repository.combine_selection_set(SetOperation::Difference),
+        Action::LabelEdit => repository.begin_label_edit(),
+        Action::ApplyLastLabel => repository.apply_last_label(),
+        Action::PointLabel => repository.point_label(),
+        Action::PointRemoveLabel => repository.point_remove_label(),
+        Action::Search => repository.begin_search_edit(),
+        Action::Order => repository.set_order_choice_on(),
+        Action::DeleteRegisterDigit => repository.delete_register_digit(),
+        Action::ToggleDelete => repository.toggle_delete(),
+        Action::CopyLabel => repository.copy_label(),
+        Action::CopyTemp => repository.copy_temp(),
+        Action::PageReadingList => repository.save_reading_list(true),
+        Action::ReadingList => repository.save_reading_list(false),
+        Action::ContactSheet => repository.save_contact_sheet(),
+        Action::Slideshow => repository.toggle_slideshow(),
+        Action::Slower => repository.slideshow_slower(),
+        Action::Faster => repository.slideshow_faster(),
+        Action::Weighted => repository.toggle_weighted(),
+        Action::Statistics => { gui.toggle_statistics(repository); return false },
+        Action::Info => { gui.toggle_info(repository); return false },
+        Action::CommandLine => repository.begin_command_edit(),
+        Action::Help => { gui.toggle_help(); return false },
+        Action::Quit => { repository.quit(); gui.application_window.close(); return false },
+        Action::CopyMoveQuit => { repository.copy_move_and_quit(); gui.application_window.close(); return false },
+    };
+    true
+}
+
+// arrows scroll the picture shown at real size or zoomed instead of moving
+fn is_scrolling(gui: &Gui, repository: &Repository) -> bool {
+    (repository.real_size() && !gui.scroll_mode() && !gui.compare_mode())
+        || ((gui.view_mode() || gui.compare_mode()) && repository.zoom() != Zoom::Fit)
+}
+
 pub fn arrow_command(direction: Direction, gui: &Gui, repository: &mut Repository, repository_rc: &Rc<RefCell<Repository>>) {
-    if repository.real_size() {
+    if is_scrolling(gui, repository) {
         let step: f64 = 100.0;
+        // the compare panes all follow the first one
+        let (hadjustment, vadjustment) = if gui.compare_mode() {
+            gui.compare_view.adjustments()
+        } else {
+            (picture_hadjustment(&gui.stack), picture_vadjustment(&gui.stack))
+        };
         let (picture_adjustment, step) = match direction {
-            Direction::Right => (picture_hadjustment(&gui.application_window), step),
-            Direction::Left  => (picture_hadjustment(&gui.application_window), -step),
-            Direction::Down  => (picture_vadjustment(&gui.application_window), step),
-            Direction::Up    => (picture_vadjustment(&gui.application_window), -step),
+            Direction::Right => (hadjustment, step),
+            Direction::Left  => (hadjustment, -step),
+            Direction::Down  => (vadjustment, step),
+            Direction::Up    => (vadjustment, -step),
         };
         picture_adjustment.set_value(picture_adjustment.value() + step)
+    } else if gui.compare_mode() {
+        repository.compare_move(matches!(direction, Direction::Right | Direction::Down))
+    } else if gui.scroll_mode() {
+        let columns = gui.scroll_view.columns() as usize;
+        if let Some(index) = scroll_index(repository.current_index(), columns, repository.capacity(), direction) {
+            repository.move_to_index(index)
+        }
     } else {
-        if repository.cells_per_row() == 1 {
+        if repository.max_cells() == 1 {
             repository.move_in_direction(direction)
         } else {
             navigate(repository, &gui.picture_grid, &gui.application_window, direction);
             if gui.stack.visible_child().unwrap() == gui.view_scrolled_window {
-                setup_image_view(&repository_rc, &gui.image_view, &gui.application_window)
+                gui.setup_view(&repository_rc)
             }
         }
     }
 }
 
 
+// every move sets the window title, even from the mouse, so the info panel follows the title;
+// the repository is still borrowed when the title is set, hence the refresh when idle
+fn setup_info_follow(repository_rc: &Rc<RefCell<Repository>>, gui_rc: &Rc<RefCell<Gui>>) {
+    let gui = gui_rc.try_borrow().unwrap();
+    gui.application_window.connect_title_notify(clone!(@strong repository_rc, @strong gui_rc => move |_| {
+        glib::idle_add_local_once(clone!(@strong repository_rc, @strong gui_rc => move || {
+            if let (Ok(gui), Ok(repository)) = (gui_rc.try_borrow(), repository_rc.try_borrow()) {
+                gui.refresh_info(&repository)
+            }
+        }));
+    }));
+}
+
+// a click on the filmstrip moves to that picture
+fn setup_filmstrip_clicks(repository_rc: &Rc<RefCell<Repository>>, gui_rc: &Rc<RefCell<Gui>>) {
+    let gui = gui_rc.try_borrow().unwrap();
+    gui.filmstrip.connect_clicked(clone!(@strong repository_rc, @strong gui_rc => move |offset| {
+        let moved = match repository_rc.try_borrow_mut() {
+            Ok(mut repository) => {
+                let index = repository.current_index() as isize + offset;
+                let moved = offset != 0 && index >= 0 && repository.can_move_to_index(index as usize);
+                if moved {
+                    repository.move_to_index(index as usize)
+                };
+                moved
+            },
+            Err(_) => false,
+        };
+        if moved {
+            if let Ok(gui) = gui_rc.try_borrow() {
+                gui.refresh(&repository_rc)
+            }
+        }
+    }));
+}
+
+// a click on a compared picture gives it the focus
+fn setup_compare_clicks(repository_rc: &Rc<RefCell<Repository>>, gui_rc: &Rc<RefCell<Gui>>) {
+    let gui = gui_rc.try_borrow().unwrap();
+    gui.compare_view.connect_clicked(clone!(@strong repository_rc, @strong gui_rc => move |position| {
+        if let Ok(mut repository) = repository_rc.try_borrow_mut() {
+            repository.compare_focus(position)
+        };
+        if let Ok(gui) = gui_rc.try_borrow() {
+            gui.refresh(&repository_rc)
+        }
+    }));
+}
+
+// mouse wheel zooms around the pointer, dragging pans the zoomed picture
+fn setup_zoom_controllers(repository_rc: &Rc<RefCell<Repository>>, gui_rc: &Rc<RefCell<Gui>>) {
+    let gui = gui_rc.try_borrow().unwrap();
+    let pointer: Rc<Cell<(f64, f64)>> = Rc::new(Cell::new((0.0, 0.0)));
+
+    let motion = gtk::EventControllerMotion::new();
+    motion.connect_motion(clone!(@strong pointer => move |_, x, y| {
+        pointer.set((x, y))
+    }));
+    gui.view_scrolled_window.add_controller(motion);
+
+    let scroll = gtk::EventControllerScroll::new(gtk::EventControllerScrollFlags::VERTICAL);
+    scroll.set_propagation_phase(gtk::PropagationPhase::Capture);
+    scroll.connect_scroll(clone!(@strong repository_rc, @strong gui_rc, @strong pointer => move |_, _, dy| {
+        if let (Ok(gui), Ok(mut repository)) = (gui_rc.try_borrow(), repository_rc.try_borrow_mut()) {
+            if gui.view_mode() {
+                gui.zoom_step(&mut repository, dy < 0.0, Some(pointer.get()));
+                return gtk::Inhibit(true)
+            }
+        };
+        gtk::Inhibit(false)
+    }));
+    gui.view_scrolled_window.add_controller(scroll);
+
+    let drag_start: Rc<Cell<(f64, f64)>> = Rc::new(Cell::new((0.0, 0.0)));
+    let drag = gtk::GestureDrag::new();
+    let view_scrolled_window = gui.view_scrolled_window.clone();
+    drag.connect_drag_begin(clone!(@strong drag_start, @strong view_scrolled_window => move |_, _, _| {
+        drag_start.set((view_scrolled_window.hadjustment().value(), view_scrolled_window.vadjustment().value()))
+    }));
+    drag.connect_drag_update(clone!(@strong drag_start, @strong view_scrolled_window => move |_, dx, dy| {
+        let (x, y) = drag_start.get();
+        view_scrolled_window.hadjustment().set_value(x - dx);
+        view_scrolled_window.vadjustment().set_value(y - dy)
+    }));
+    gui.view_scrolled_window.add_controller(drag);
+}
+
 pub fn build_gui(args: &Args, application: &gtk::Application) {
     let width = args.width();
     let height = args.height();
     let grid_size = args.grid_size();
+    let bindings = match load_bindings(args.layout, args.bindings.clone()) {
+        Ok(bindings) => bindings,
+        Err(err) => {
+            eprintln!("{}", err);
+            Bindings::preset(args.layout.unwrap_or(Layout::Bepo))
+        },
+    };
     match init_repository(args) {
         Ok(repository) => {
             let repository_rc = Rc::new(RefCell::new(repository));
-            let gui = create_gui(application, width, height, grid_size, &repository_rc);
+            let gui = create_gui(application, width, height, grid_size, bindings, &repository_rc);
             let gui_rc = Rc::new(RefCell::new(gui));
 
             let evk = gtk::EventControllerKey::new();
             evk.connect_key_pressed(clone!(@strong repository_rc, @strong gui_rc => move |_, key, _, _| {
                 process_key(&repository_rc, &gui_rc, key) 
             }));
+            setup_zoom_controllers(&repository_rc, &gui_rc);
+            setup_info_follow(&repository_rc, &gui_rc);
+            setup_filmstrip_clicks(&repository_rc, &gui_rc);
+            setup_compare_clicks(&repository_rc, &gui_rc);
             let gui = gui_rc.try_borrow().unwrap();
             let application_window = &gui.application_window;
             let picture_grid = &gui.picture_grid;
             gui.application_window.add_controller(evk);
-            if args.maximized { gui.application_window.fullscreen() };
-            // if a timer has been passed, set a timeout routine
-            if let Some(t) = args.timer {
-                timeout_add_local(Duration::new(t,0), clone!(@strong repository_rc, @strong picture_grid, @strong application_window => move | | {
-                    {
-                        let mut repository: RefMut<'_,Repository> = repository_rc.borrow_mut();
-                        repository.move_next_page();
-                    }
-                    setup_picture_grid(&repository_rc, &picture_grid, &application_window);
-                    Continue(true)
-                }));
+            if args.maximized() { gui.application_window.fullscreen() };
+            // pictures read from a directory follow the changes made to it
+            let mut watcher = if args.reading.is_none() && args.file.is_none() && !args.sample() {
+                match DirectoryWatcher::new(&args.path(), args.pattern.clone()) {
+                    Ok(watcher) => Some(watcher),
+                    Err(err) => {
+                        eprintln!("{}", err);
+                        None
+                    },
+                }
+            } else {
+                None
             };
+            // the palettes and thumbnails of the pictures added or changed are made in a thread, which hands the changes back once they are ready
+            let (work_sender, work_receiver) = mpsc::channel::<Vec<(PathBuf, FileChange)>>();
+            let (changes_sender, changes_receiver) = glib::MainContext::channel::<Vec<(PathBuf, FileChange)>>(glib::PRIORITY_DEFAULT);
+            let directories = args.directories();
+            thread::spawn(move || {
+                for changes in work_receiver {
+                    for (path, change) in &changes {
+                        if *change != FileChange::Removed {
+                            // errors are reported when the changes are applied
+                            let _ = prepare_picture(path, &directories);
+                        }
+                    };
+                    if changes_sender.send(changes).is_err() {
+                        break
+                    }
+                }
+            });
+            changes_receiver.attach(None, clone!(@strong repository_rc, @strong gui_rc => move |changes| {
+                if let Ok(mut repository) = repository_rc.try_borrow_mut() {
+                    repository.apply_file_changes(&changes)
+                };
+                if let Ok(gui) = gui_rc.try_borrow() {
+                    gui.refresh(&repository_rc)
+                };
+                Continue(true)
+            }));
+            // the slideshow, the watcher and the toasts are driven by a tick; the slideshow does nothing until started (--timer or T)
+            timeout_add_local(Duration::from_millis(TICK_MILLIS), clone!(@strong repository_rc, @strong gui_rc => move | | {
+                if let Some(watcher) = watcher.as_mut() {
+                    let changes = watcher.changes();
+                    if !changes.is_empty() {
+                        let _ = work_sender.send(changes);
+                    }
+                };
+                let changed = match repository_rc.try_borrow_mut() {
+                    Ok(mut repository) => repository.slideshow_tick(),
+                    Err(_) => false,
+                };
+                if let Ok(gui) = gui_rc.try_borrow() {
+                    if changed {
+                        gui.refresh(&repository_rc)
+                    };
+                    // notices from keys, the mouse and the slideshow alike show up here
+                    if let Ok(repository) = repository_rc.try_borrow() {
+                        gui.show_notices(&repository)
+                    }
+                };
+                Continue(true)
+            }));
             setup_picture_grid(&repository_rc, &picture_grid, &application_window);
             application_window.present();
         },
@@ -0,0 +1,241 @@
+use crate::entry::Entry;
+use crate::error::GalleryError;
+use crate::metadata::palette_hex;
+use crate::picture_io::ensure_thumbnail;
+use std::fs::{copy, create_dir_all, write};
+use std::io::Result;
+use std::path::Path;
+
+// layout of the exported site, everything linked with relative paths:
+//
+//  index.html, index_2.html …     thumbnails, per_page by page
+//  picture_0001.html …            one page per picture
+//  pictures/0001_foo.jpeg         originals
+//  thumbnails/0001_foo.jpeg       thumbnails
+//  style.css
+//
+const PICTURES_DIR: &str = "pictures";
+const THUMBNAILS_DIR: &str = "thumbnails";
+const STYLE_FILE_NAME: &str = "style.css";
+
+const STYLE: &str = "body { font-family: sans-serif; background: #222; color: #ddd; margin: 1em; }
+a { color: #8cf; }
+nav { margin: 1em 0; }
+.grid { display: flex; flex-wrap: wrap; gap: 1em; }
+.cell { width: 200px; text-align: center; }
+.cell img { max-width: 200px; max-height: 200px; }
+.picture img { max-width: 100%; max-height: 85vh; }
+.palette { display: flex; }
+.palette span { width: 20px; height: 10px; }
+";
+
+// what a page needs to know about an exported picture
+#[derive(Clone, Debug, PartialEq)]
+pub struct HtmlItem {
+    pub title: String,
+    pub file_name: String,
+    pub rank: String,
+    pub label: String,
+    pub palette: Vec<String>,
+}
+
+impl HtmlItem {
+    pub fn from_entry(index: usize, entry: &Entry) -> Self {
+        HtmlItem {
+            title: entry.original_file_name(),
+            file_name: format!("{:04}_{}", index + 1, entry.original_file_name()),
+            rank: entry.image_data.rank.show(),
+            label: entry.image_data.label.clone(),
+            palette: palette_hex(&entry.image_data.palette),
+        }
+    }
+}
+
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+// file names go in href and src attributes
+fn escape_path(text: &str) -> String {
+    escape(&text.replace('%', "%25").replace('#', "%23").replace('?', "%3F").replace(' ', "%20"))
+}
+
+pub fn index_page_name(page: usize) -> String {
+    if page == 0 { String::from("index.html") } else { format!("index_{}.html", page + 1) }
+}
+
+pub fn picture_page_name(index: usize) -> String {
+    format!("picture_{:04}.html", index + 1)
+}
+
+fn page_header(title: &str) -> String {
+    format!("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<link rel=\"stylesheet\" href=\"{}\">\n</head>\n<body>\n<h1>{}</h1>\n",
+        escape(title), STYLE_FILE_NAME, escape(title))
+}
+
+fn page_footer() -> String {
+    String::from("</body>\n</html>\n")
+}
+
+fn palette_swatches(palette: &[String]) -> String {
+    let swatches: Vec<String> = palette.iter()
+        .map(|color| format!("<span style=\"background: {}\" title=\"{}\"></span>", color, color))
+        .collect();
+    format!("<div class=\"palette\">{}</div>\n", swatches.join(""))
+}
+
+fn caption(item: &HtmlItem) -> String {
+    format!("{} {}", escape(&item.rank), escape(&item.label)).trim().to_string()
+}
+
+fn navigation(previous: Option<String>, up: Option<String>, next: Option<String>) -> String {
+    let mut links: Vec<String> = Vec::new();
+    if let Some(href) = previous {
+        links.push(format!("<a href=\"{}\">previous</a>", href))
+    };
+    if let Some(href) = up {
+        links.push(format!("<a href=\"{}\">index</a>", href))
+    };
+    if let Some(href) = next {
+        links.push(format!("<a href=\"{}\">next</a>", href))
+    };
+    format!("<nav>{}</nav>\n", links.join(" | "))
+}
+
+pub fn render_index(title: &str, items: &[HtmlItem], page: usize, per_page: usize) -> String {
+    let pages = (items.len() + per_page - 1) / per_page;
+    let start = page * per_page;
+    let end = std::cmp::min(start + per_page, items.len());
+    let mut html = page_header(&format!("{} ({}/{})", title, page + 1, pages));
+    let nav = navigation(
+        if page > 0 { Some(index_page_name(page - 1)) } else { None },
+        None,
+        if page + 1 < pages { Some(index_page_name(page + 1)) } else { None });
+    html.push_str(&nav);
+    html.push_str("<div class=\"grid\">\n");
+    for index in start..end {
+        let item = &items[index];
+        html.push_str(&format!("<div class=\"cell\"><a href=\"{}\"><img src=\"{}/{}\" alt=\"{}\"></a><br>{}</div>\n",
+            picture_page_name(index),
+            THUMBNAILS_DIR, escape_path(&item.file_name),
+            escape(&item.title),
+            caption(item)));
+    };
+    html.push_str("</div>\n");
+    html.push_str(&nav);
+    html.push_str(&page_footer());
+    html
+}
+
+pub fn render_picture(items: &[HtmlItem], index: usize, per_page: usize) -> String {
+    let item = &items[index];
+    let mut html = page_header(&item.title);
+    html.push_str(&navigation(
+        if index > 0 { Some(picture_page_name(index - 1)) } else { None },
+        Some(index_page_name(index / per_page)),
+        if index + 1 < items.len() { Some(picture_page_name(index + 1)) } else { None }));
+    html.push_str(&format!("<div class=\"picture\"><a href=\"{}/{}\"><img src=\"{}/{}\" alt=\"{}\"></a></div>\n",
+        PICTURES_DIR, escape_path(&item.file_name),
+        PICTURES_DIR, escape_path(&item.file_name),
+        escape(&item.title)));
+    html.push_str(&format!("<p>{}</p>\n", caption(item)));
+    html.push_str(&palette_swatches(&item.palette));
+    html.push_str(&page_footer());
+    html
+}
+
+// the thumbnail is created if missing; the original stands in for it if it can't be
+fn copy_pictures(entry: &Entry, item: &HtmlItem, target: &Path) -> Result<()> {
+    let original = entry.original_file_path();
+    copy(&original, target.join(PICTURES_DIR).join(&item.file_name))?;
+    let thumbnail = match ensure_thumbnail(entry) {
+        Ok(()) => entry.thumbnail_file_path(),
+        Err(err) => {
+            eprintln!("can't create thumbnail for {}: {}", original.display(), err);
+            original
+        },
+    };
+    copy(&thumbnail, target.join(THUMBNAILS_DIR).join(&item.file_name))?;
+    Ok(())
+}
+
+// writes the whole site in the target directory, returns the number of pictures exported
+pub fn write_html_gallery(entries: &[&Entry], target: &Path, title: &str, per_page: usize) -> Result<usize> {
+    if entries.is_empty() {
+        return Err(GalleryError::NoEntries.into())
+    };
+    let per_page = std::cmp::max(per_page, 1);
+    create_dir_all(target.join(PICTURES_DIR))?;
+    create_dir_all(target.join(THUMBNAILS_DIR))?;
+    write(target.join(STYLE_FILE_NAME), STYLE)?;
+    let items: Vec<HtmlItem> = entries.iter().enumerate().map(|(i, e)| HtmlItem::from_entry(i, e)).collect();
+    for (entry, item) in entries.iter().zip(items.iter()) {
+        copy_pictures(entry, item, target)?
+    };
+    let pages = (items.len() + per_page - 1) / per_page;
+    for page in 0..pages {
+        write(target.join(index_page_name(page)), render_index(title, &items, page, per_page))?
+    };
+    for index in 0..items.len() {
+        write(target.join(picture_page_name(index)), render_picture(&items, index, per_page))?
+    };
+    Ok(items.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::paths::test_directory;
+
+    fn item(name: &str) -> HtmlItem {
+        HtmlItem {
+            title: name.to_string(),
+            file_name: name.to_string(),
+            rank: String::from("☆☆"),
+            label: String::new(),
+            palette: vec![String::from("#ff0000")],
+        }
+    }
+
+    #[test]
+    fn text_is_escaped() {
+        assert_eq!("Tom &amp; Jerry &lt;3 &quot;cat&quot;", escape("Tom & Jerry <3 \"cat\""));
+    }
+
+    #[test]
+    fn index_pages_link_to_their_neighbours_and_pictures() {
+        let items = vec![item("a.jpeg"), item("b.jpeg"), item("c.jpeg")];
+        let first = render_index("gallery", &items, 0, 2);
+        assert!(first.contains("href=\"index_2.html\">next"));
+        assert!(first.contains("href=\"picture_0002.html\""));
+        assert!(!first.contains("picture_0003.html"));
+        let second = render_index("gallery", &items, 1, 2);
+        assert!(second.contains("href=\"index.html\">previous"));
+        assert!(second.contains("thumbnails/c.jpeg"));
+    }
+
+    #[test]
+    fn picture_pages_show_the_original_rank_and_palette_and_link_back_to_their_index() {
+        let items = vec![item("a.jpeg"), item("b b.jpeg"), item("c.jpeg")];
+        let html = render_picture(&items, 2, 2);
+        assert!(html.contains("href=\"index_2.html\">index"));
+        assert!(html.contains("href=\"picture_0002.html\">previous"));
+        assert!(!html.contains(">next"));
+        assert!(html.contains("<p>☆☆</p>"));
+        assert!(html.contains("background: #ff0000"));
+        assert!(render_picture(&items, 1, 2).contains("src=\"pictures/b%20b.jpeg\""));
+    }
+
+    #[test]
+    fn an_empty_selection_writes_no_gallery() {
+        let directory = test_directory("empty_html_gallery");
+        let target = directory.join("gallery");
+        assert!(write_html_gallery(&[], &target, "gallery", 4).is_err());
+        assert!(!target.exists());
+        let _ = std::fs::remove_dir_all(&directory);
+    }
+}
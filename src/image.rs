@@ -2,6 +2,7 @@ use image;
 use image::{Rgba, ImageResult, open};
 use image::GenericImageView;
 use std::collections::HashSet;
+use std::path::Path;
 
 
 
@@ -13,8 +14,8 @@ fn rgba_key(rgba: Rgba<u8>) -> u32 {
     };
     result 
 }
-pub fn get_image_color(file_path: &str) -> ImageResult<usize> {
-    println!("get image color of {}", file_path);
+pub fn get_image_color(file_path: &Path) -> ImageResult<usize> {
+    eprintln!("get image color of {}", file_path.display());
     match open(file_path) {
         Ok(dynamic_image) => {
             let iter: Vec<_>= dynamic_image.pixels().collect();
@@ -26,7 +27,7 @@ pub fn get_image_color(file_path: &str) -> ImageResult<usize> {
             Ok(colors.len())
         },
         Err(err) => {
-            println!("error getting image {} : {}", file_path, err);
+            eprintln!("error getting image {} : {}", file_path.display(), err);
             Err(err)
         },
     }
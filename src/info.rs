@@ -0,0 +1,87 @@
+use crate::entry::Entry;
+use chrono::{DateTime, Local};
+use exif::{In, Reader, Tag};
+use image::image_dimensions;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+// exif fields shown, when the picture has them
+const EXIF_TAGS: [Tag; 8] = [
+    Tag::Make, Tag::Model, Tag::LensModel, Tag::DateTimeOriginal,
+    Tag::ExposureTime, Tag::FNumber, Tag::PhotographicSensitivity, Tag::FocalLength,
+];
+
+pub fn hex_color(color: u32) -> String {
+    format!("#{:06x}", color & 0xffffff)
+}
+
+fn exif_fields(file_path: &Path) -> Vec<(String, String)> {
+    let file = match File::open(file_path) {
+        Ok(file) => file,
+        Err(_) => return vec![],
+    };
+    match Reader::new().read_from_container(&mut BufReader::new(file)) {
+        Ok(exif) => EXIF_TAGS.iter()
+            .filter_map(|tag| exif.get_field(*tag, In::PRIMARY)
+                .map(|field| (tag.to_string(), field.display_value().with_unit(&exif).to_string())))
+            .collect(),
+        Err(_) => vec![],
+    }
+}
+
+// what the info panel shows about a picture; sets are the selection sets it belongs to
+pub fn entry_info(entry: &Entry, sets: &[String]) -> Vec<(String, String)> {
+    let path = entry.original_file_path();
+    let mut rows: Vec<(String, String)> = vec![(String::from("path"), entry.path_text())];
+    if let Ok((width, height)) = image_dimensions(&path) {
+        rows.push((String::from("dimensions"), format!("{} × {}", width, height)))
+    };
+    rows.push((String::from("size"), format!("{} bytes", entry.file_size)));
+    let modified: DateTime<Local> = entry.modified_time.into();
+    rows.push((String::from("modified"), modified.format("%Y-%m-%d %H:%M:%S").to_string()));
+    rows.extend(exif_fields(&path));
+    rows.push((String::from("rank"), entry.image_data.rank.show()));
+    rows.push((String::from("label"), entry.image_data.label().unwrap_or_default()));
+    rows.push((String::from("selected"), if entry.is_selected() { String::from("yes") } else { String::from("no") }));
+    rows.push((String::from("sets"), sets.join(" ")));
+    if entry.delete {
+        rows.push((String::from("delete"), String::from("yes")))
+    };
+    rows.push((String::from("colors"), entry.image_data.colors.to_string()));
+    let palette: Vec<String> = entry.image_data.palette.iter().map(|c| hex_color(*c)).collect();
+    rows.push((String::from("palette"), palette[..5].join(" ")));
+    rows.push((String::new(), palette[5..].join(" ")));
+    rows
+}
+
+// one row per line, values aligned
+pub fn info_text(rows: &[(String, String)]) -> String {
+    let width = rows.iter().map(|(name, _)| name.chars().count()).max().unwrap_or(0);
+    rows.iter()
+        .map(|(name, value)| format!("{:width$}  {}", name, value, width = width))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entry::make_entry;
+    use crate::rank::Rank;
+    use std::time::SystemTime;
+
+    #[test]
+    fn info_shows_rank_label_and_palette_codes() {
+        let mut entry = make_entry(String::from("photos/foo.jpeg"), 100, 5, SystemTime::now(), Rank::TwoStars);
+        entry.set_label(&String::from("bar"));
+        entry.image_data.palette = [0xff0000, 0x00ff00, 0x0000ff, 0, 0, 0, 0, 0, 0xffffff];
+        let text = info_text(&entry_info(&entry, &[String::from("best")]));
+        assert!(text.starts_with("path      photos/foo.jpeg\n"));
+        assert!(text.contains("label     bar\n"));
+        assert!(text.contains("sets      best\n"));
+        assert!(text.contains("#ff0000 #00ff00 #0000ff #000000 #000000"));
+        assert!(text.ends_with("#000000 #000000 #000000 #ffffff"));
+        assert_eq!("#0a0b0c", hex_color(0x0a0b0c));
+    }
+}
@@ -1,10 +1,10 @@
 use clap::Parser;
 use crate::args::Args;
+use crate::commands::run_command;
 use crate::direction::Direction;
 use crate::entry::EntryList;
 use crate::gui::build_gui;
 use crate::paths::determine_path;
-use crate::picture_io::{ensure_thumbnails, read_entries};
 use crate::rank::Rank;
 use entry::Entry;
 use glib::clone;
@@ -15,21 +15,52 @@ use order::{Order};
 use paths::THUMB_SUFFIX;
 
 mod args;
+mod bindings;
+mod command_line;
+mod commands;
+mod compare;
+mod compare_view;
+mod config;
+mod contact_sheet;
 mod direction;
 mod entry;
+mod error;
+mod filmstrip;
+mod grid_size;
 mod gui;
+mod html_export;
 mod image;
 mod image_data;
+mod info;
+mod metadata;
 mod navigator;
+mod notices;
 mod order;
 mod paths;
 mod picture_io;
 mod rank;
+mod reading_list;
 mod repository;
+mod scroll_view;
+mod selection;
+mod slideshow;
+mod statistics;
+mod tiled_view;
+mod tiles;
+mod watcher;
+mod weighting;
+mod zoom;
 
 fn main() {
-    let args = Args::parse();
+    let args = Args::parse().with_config();
 
+    if let Some(command) = args.command() {
+        if let Err(err) = run_command(&args, &command) {
+            eprintln!("{}", err);
+            std::process::exit(1)
+        };
+        return
+    };
 
     let application = Application::builder()
         .application_id("org.example.gallsh")
@@ -0,0 +1,415 @@
+use chrono::{DateTime, Utc};
+use clap::builder::PossibleValue;
+use crate::entry::{Entry, EntryList};
+use crate::rank::Rank;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{Result, Write};
+
+const MAX_LABEL_LENGTH: usize = 16;
+const CSV_HEADER: [&str; 8] = ["path", "size", "modified", "colors", "rank", "label", "selected", "palette"];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Csv, Json,
+}
+
+impl Format {
+    pub fn from_file_name(file_name: &str) -> Self {
+        if file_name.ends_with(".jsonl") || file_name.ends_with(".json") {
+            Format::Json
+        } else {
+            Format::Csv
+        }
+    }
+}
+
+impl std::fmt::Display for Format {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            Format::Csv => "csv",
+            Format::Json => "json",
+        })
+    }
+}
+
+impl clap::ValueEnum for Format {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[Format::Csv, Format::Json]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            Format::Csv => PossibleValue::new("csv").help("this is default"),
+            Format::Json => PossibleValue::new("json").help("JSON Lines, one object per entry"),
+        })
+    }
+}
+
+// what gets exported for each entry
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Record {
+    pub path: String,
+    pub size: u64,
+    pub modified: String,
+    pub colors: usize,
+    pub rank: usize,
+    pub label: String,
+    pub selected: bool,
+    pub palette: Vec<String>,
+}
+
+// what can be imported back: only rank, label and selection are editable
+#[derive(Deserialize, Debug, PartialEq, Default)]
+pub struct Update {
+    pub path: String,
+    #[serde(default)]
+    pub rank: Option<usize>,
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(default)]
+    pub selected: Option<bool>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct ImportSummary {
+    pub records: usize,
+    pub changed: Vec<usize>,
+    pub ranks: usize,
+    pub labels: usize,
+    pub selections: usize,
+    pub unknown_paths: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+impl ImportSummary {
+    pub fn show(&self) -> String {
+        let mut result = format!("{} records read, {} entries changed (ranks: {}, labels: {}, selections: {})",
+            self.records,
+            self.changed.len(),
+            self.ranks,
+            self.labels,
+            self.selections);
+        for path in &self.unknown_paths {
+            result.push_str(&format!("\nunknown path: {}", path));
+        }
+        for error in &self.errors {
+            result.push_str(&format!("\n{}", error));
+        }
+        result
+    }
+}
+
+pub fn palette_hex(palette: &[u32;9]) -> Vec<String> {
+    palette.iter().map(|c| format!("#{:06x}", c)).collect()
+}
+
+pub fn record_for_entry(entry: &Entry) -> Record {
+    let modified: DateTime<Utc> = entry.modified_time.into();
+    Record {
+        path: entry.path_text(),
+        size: entry.file_size,
+        modified: modified.to_rfc3339(),
+        colors: entry.image_data.colors,
+        rank: entry.image_data.rank.stars(),
+        label: entry.image_data.label.clone(),
+        selected: entry.image_data.selected,
+        palette: palette_hex(&entry.image_data.palette),
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains(',') || field.contains('"') || field.contains('\n') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+pub fn csv_line(fields: &[String]) -> String {
+    fields.iter().map(|f| csv_field(f)).collect::<Vec<_>>().join(",")
+}
+
+const UNTERMINATED_FIELD: &str = "unterminated quoted field";
+
+pub fn parse_csv_line(line: &str) -> std::result::Result<Vec<String>, String> {
+    let mut fields: Vec<String> = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    // a closing quote ends the field, only a comma can follow it
+    let mut closed = false;
+    let mut chars = line.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if quoted {
+            match ch {
+                '"' if chars.peek() == Some(&'"') => { chars.next(); field.push('"') },
+                '"' => { quoted = false; closed = true },
+                _ => field.push(ch),
+            }
+        } else {
+            match ch {
+                ',' => { fields.push(std::mem::take(&mut field)); closed = false },
+                _ if closed => return Err(String::from("characters after a closing quote")),
+                '"' if field.is_empty() => quoted = true,
+                _ => field.push(ch),
+            }
+        }
+    }
+    if quoted {
+        return Err(String::from(UNTERMINATED_FIELD))
+    };
+    fields.push(field);
+    Ok(fields)
+}
+
+// the records of the content with the number of the line they start on; a quoted field
+// can hold line breaks, so a record goes on until its quotes are closed
+fn csv_records(content: &str) -> Vec<(usize, std::result::Result<Vec<String>, String>)> {
+    let mut records = Vec::new();
+    let mut pending: Option<(usize, String)> = None;
+    for (number, line) in content.lines().enumerate() {
+        let (start, text) = match pending.take() {
+            Some((start, text)) => (start, format!("{}\n{}", text, line)),
+            None if line.trim().is_empty() => continue,
+            None => (number+1, line.to_string()),
+        };
+        match parse_csv_line(&text) {
+            Err(err) if err == UNTERMINATED_FIELD => pending = Some((start, text)),
+            fields => records.push((start, fields)),
+        }
+    };
+    if let Some((start, text)) = pending {
+        records.push((start, parse_csv_line(&text)))
+    };
+    records
+}
+
+pub fn export_entries<W: Write>(entry_list: &EntryList, format: Format, writer: &mut W) -> Result<()> {
+    if format == Format::Csv {
+        writeln!(writer, "{}", CSV_HEADER.join(","))?;
+    };
+    for entry in entry_list {
+        let record = record_for_entry(entry);
+        match format {
+            Format::Csv => writeln!(writer, "{}", csv_line(&[
+                    record.path,
+                    record.size.to_string(),
+                    record.modified,
+                    record.colors.to_string(),
+                    record.rank.to_string(),
+                    record.label,
+                    record.selected.to_string(),
+                    record.palette.join(" ")]))?,
+            Format::Json => writeln!(writer, "{}", serde_json::to_string(&record)?)?,
+        }
+    };
+    Ok(())
+}
+
+fn parse_bool(value: &str) -> std::result::Result<bool, String> {
+    match value.trim().to_lowercase().as_str() {
+        "true" | "yes" | "1" => Ok(true),
+        "false" | "no" | "0" => Ok(false),
+        other => Err(format!("invalid selected value: {}", other)),
+    }
+}
+
+fn parse_csv_updates(content: &str, errors: &mut Vec<String>) -> Vec<Update> {
+    let mut records = csv_records(content).into_iter();
+    let header = match records.next() {
+        Some((_, Ok(fields))) => fields,
+        Some((number, Err(err))) => { errors.push(format!("line {}: {}", number, err)); return vec![] },
+        None => return vec![],
+    };
+    let column = |name: &str| header.iter().position(|h| h.trim() == name);
+    let (path_column, rank_column, label_column, selected_column) = (column("path"), column("rank"), column("label"), column("selected"));
+    if path_column.is_none() {
+        errors.push(String::from("line 1: missing path column"));
+        return vec![]
+    };
+    let mut updates: Vec<Update> = Vec::new();
+    for (number, record) in records {
+        let fields = match record {
+            Ok(fields) => fields,
+            Err(err) => { errors.push(format!("line {}: {}", number, err)); continue },
+        };
+        let field = |column: Option<usize>| column.and_then(|c| fields.get(c)).map(|f| f.to_string());
+        let rank = match field(rank_column).filter(|r| !r.trim().is_empty()).map(|r| r.trim().parse::<usize>()) {
+            Some(Ok(n)) => Some(n),
+            Some(Err(err)) => { errors.push(format!("line {}: invalid rank: {}", number, err)); continue },
+            None => None,
+        };
+        let selected = match field(selected_column).filter(|s| !s.trim().is_empty()).map(|s| parse_bool(&s)) {
+            Some(Ok(b)) => Some(b),
+            Some(Err(err)) => { errors.push(format!("line {}: {}", number, err)); continue },
+            None => None,
+        };
+        updates.push(Update {
+            path: field(path_column).unwrap_or_default(),
+            rank: rank,
+            label: field(label_column),
+            selected: selected,
+        })
+    };
+    updates
+}
+
+fn parse_json_updates(content: &str, errors: &mut Vec<String>) -> Vec<Update> {
+    let mut updates: Vec<Update> = Vec::new();
+    for (number, line) in content.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+        match serde_json::from_str::<Update>(line) {
+            Ok(update) => updates.push(update),
+            Err(err) => errors.push(format!("line {}: {}", number+1, err)),
+        }
+    };
+    updates
+}
+
+pub fn parse_updates(content: &str, format: Format, errors: &mut Vec<String>) -> Vec<Update> {
+    match format {
+        Format::Csv => parse_csv_updates(content, errors),
+        Format::Json => parse_json_updates(content, errors),
+    }
+}
+
+pub fn is_valid_label(label: &str) -> bool {
+    label.len() <= MAX_LABEL_LENGTH
+        && label.chars().all(|c| matches!(c, 'a'..='z' | '0'..='9' | '-' | '_'))
+}
+
+// apply the updates to the entries in memory; saving the changed entries is left to the caller
+pub fn apply_updates(entry_list: &mut EntryList, updates: Vec<Update>, summary: &mut ImportSummary) {
+    let positions: HashMap<String, usize> = entry_list.iter().enumerate().map(|(i, e)| (e.path_text(), i)).collect();
+    summary.records += updates.len();
+    for update in updates {
+        let index = match positions.get(&update.path) {
+            Some(&index) => index,
+            None => { summary.unknown_paths.push(update.path); continue },
+        };
+        let rank = match update.rank.map(|n| (n, Rank::from_stars(n))) {
+            Some((_, Some(rank))) => Some(rank),
+            Some((n, None)) => { summary.errors.push(format!("{}: invalid rank {}", update.path, n)); continue },
+            None => None,
+        };
+        if let Some(label) = &update.label {
+            if !is_valid_label(label) {
+                summary.errors.push(format!("{}: invalid label {}", update.path, label));
+                continue
+            }
+        };
+        let entry = &mut entry_list[index];
+        let mut changed = false;
+        if let Some(rank) = rank {
+            if entry.image_data.rank != rank {
+                entry.set_rank(rank);
+                summary.ranks += 1;
+                changed = true
+            }
+        };
+        if let Some(label) = &update.label {
+            if &entry.image_data.label != label {
+                entry.set_label(label);
+                summary.labels += 1;
+                changed = true
+            }
+        };
+        if let Some(selected) = update.selected {
+            if entry.image_data.selected != selected {
+                entry.set_select(selected);
+                summary.selections += 1;
+                changed = true
+            }
+        };
+        if changed && !summary.changed.contains(&index) {
+            summary.changed.push(index)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entry::example_entries;
+
+    #[test]
+    fn csv_fields_with_commas_or_quotes_are_quoted() {
+        let line = csv_line(&[String::from("photos/bar, baz.jpeg"), String::from("say \"hi\""), String::from("3")]);
+        assert_eq!("\"photos/bar, baz.jpeg\",\"say \"\"hi\"\"\",3", line);
+        assert_eq!(vec!["photos/bar, baz.jpeg", "say \"hi\"", "3"], parse_csv_line(&line).unwrap());
+    }
+
+    #[test]
+    fn export_to_csv_writes_a_header_and_a_line_per_entry() {
+        let mut output: Vec<u8> = Vec::new();
+        let mut entries = example_entries();
+        entries[1].image_data.label = String::from("bar, baz");
+        export_entries(&entries, Format::Csv, &mut output).unwrap();
+        let content = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(5, lines.len());
+        assert_eq!("path,size,modified,colors,rank,label,selected,palette", lines[0]);
+        assert!(lines[2].starts_with("photos/bar.jpeg,1000,2023-07-01T10:52:37+00:00,15,3,\"bar, baz\",false,#000000"));
+    }
+
+    #[test]
+    fn csv_import_only_needs_a_path_column() {
+        let mut errors = Vec::new();
+        let updates = parse_updates("label,path\nfoo,photos/foo.jpeg\n", Format::Csv, &mut errors);
+        assert!(errors.is_empty());
+        assert_eq!(vec![Update { path: String::from("photos/foo.jpeg"), rank: None, label: Some(String::from("foo")), selected: None }], updates);
+    }
+
+    #[test]
+    fn csv_fields_with_line_breaks_are_imported_back() {
+        let mut output: Vec<u8> = Vec::new();
+        let mut entries = example_entries();
+        entries[1].image_data.label = String::from("two,\nlines");
+        export_entries(&entries, Format::Csv, &mut output).unwrap();
+        let mut errors = Vec::new();
+        let updates = parse_updates(&String::from_utf8(output).unwrap(), Format::Csv, &mut errors);
+        assert!(errors.is_empty());
+        assert_eq!(4, updates.len());
+        assert_eq!("photos/bar.jpeg", updates[1].path);
+        assert_eq!(Some(String::from("two,\nlines")), updates[1].label);
+        assert_eq!("photos/qux.jpeg", updates[2].path);
+        let updates = parse_updates("path,rank\nphotos/foo.jpeg,1\n\"photos/bar.jpeg,2\n", Format::Csv, &mut errors);
+        assert_eq!(1, updates.len());
+        assert_eq!(vec![String::from("line 3: unterminated quoted field")], errors);
+    }
+
+    #[test]
+    fn csv_fields_with_characters_after_the_closing_quote_are_rejected() {
+        let mut errors = Vec::new();
+        let updates = parse_updates("path,label\n\"photos/foo.jpeg\"b,keep\nphotos/bar.jpeg,\"a\",\n", Format::Csv, &mut errors);
+        assert_eq!(1, updates.len());
+        assert_eq!("photos/bar.jpeg", updates[0].path);
+        assert_eq!(vec![String::from("line 2: characters after a closing quote")], errors);
+    }
+
+    #[test]
+    fn importing_updates_changes_rank_label_and_selection() {
+        let mut entries = example_entries();
+        let mut errors = Vec::new();
+        let updates = parse_updates("{\"path\":\"photos/foo.jpeg\",\"rank\":2,\"label\":\"keep\",\"selected\":true,\"size\":100}\n", Format::Json, &mut errors);
+        let mut summary = ImportSummary::default();
+        apply_updates(&mut entries, updates, &mut summary);
+        assert_eq!(vec![0], summary.changed);
+        assert_eq!((1, 1, 1), (summary.ranks, summary.labels, summary.selections));
+        assert_eq!(Rank::TwoStars, entries[0].image_data.rank);
+        assert_eq!("keep", entries[0].image_data.label);
+        assert_eq!(true, entries[0].image_data.selected);
+    }
+
+    #[test]
+    fn invalid_values_and_unknown_paths_are_reported_and_skipped() {
+        let mut entries = example_entries();
+        let mut errors = Vec::new();
+        let updates = parse_updates("path,rank,label\nphotos/foo.jpeg,7,\nphotos/foo.jpeg,1,Bad Label\nphotos/new.jpeg,1,\n", Format::Csv, &mut errors);
+        let mut summary = ImportSummary::default();
+        apply_updates(&mut entries, updates, &mut summary);
+        assert!(summary.changed.is_empty());
+        assert_eq!(2, summary.errors.len());
+        assert_eq!(vec![String::from("photos/new.jpeg")], summary.unknown_paths);
+        assert_eq!(Rank::NoStar, entries[0].image_data.rank);
+    }
+}
@@ -7,6 +7,7 @@ pub type Coords = (i32, i32);
 pub struct Navigator {
     capacity: i32,
     cells_per_row: i32,
+    rows: i32,
     max_cells: i32,
     start_cell_index: i32,
     position: Coords,
@@ -14,11 +15,18 @@ pub struct Navigator {
 }
 
 impl Navigator {
+    // square pages
+    #[cfg(test)]
     pub fn new(capacity: i32, cells_per_row: i32) -> Self {
+        Navigator::with_rows(capacity, cells_per_row, cells_per_row)
+    }
+
+    pub fn with_rows(capacity: i32, cells_per_row: i32, rows: i32) -> Self {
         Navigator {
             capacity: capacity,
             cells_per_row: cells_per_row,
-            max_cells: cells_per_row * cells_per_row,
+            rows: rows,
+            max_cells: cells_per_row * rows,
             start_cell_index: 0,
             position: (0,0),
             page_changed: true,
@@ -36,6 +44,10 @@ impl Navigator {
         self.cells_per_row
     }
 
+    pub fn rows(&self) -> i32 {
+        self.rows
+    }
+
     pub fn max_cells(&self) -> i32 {
         self.max_cells
     }
@@ -71,7 +83,7 @@ impl Navigator {
         position.0 >= 0
             && position.0 < self.cells_per_row
             && position.1 >= 0
-            && position.1 < self.cells_per_row
+            && position.1 < self.rows
             && self.index_from_position(position).is_some()
     }
 
@@ -143,13 +155,13 @@ mod tests {
     #[test]
     fn after_a_move_towards_index_has_changed() {
         let mut navigator = Navigator::new(10, 4);
-        navigator.move_towards(Direction::Right);
+        navigator.move_abs((1, 0));
         assert_eq!(1, navigator.index());
-        navigator.move_towards(Direction::Down);
+        navigator.move_abs((1, 1));
         assert_eq!(5, navigator.index());
-        navigator.move_towards(Direction::Left);
+        navigator.move_abs((0, 1));
         assert_eq!(4, navigator.index());
-        navigator.move_towards(Direction::Up);
+        navigator.move_abs((0, 0));
         assert_eq!(0, navigator.index());
     }
     #[test]
@@ -165,12 +177,10 @@ mod tests {
     fn after_next_page_or_prev_page_index_is_changed_and_aligned_to_a_page() {
         let mut navigator = Navigator::new(100, 4);
         assert_eq!(0, navigator.index());
-        navigator.move_towards(Direction::Right);
-        navigator.move_towards(Direction::Right);
+        navigator.move_abs((2, 0));
         navigator.move_next_page();
         assert_eq!(16, navigator.index());
-        navigator.move_towards(Direction::Down);
-        navigator.move_towards(Direction::Down);
+        navigator.move_abs((0, 2));
         assert_eq!(16+4+4, navigator.index());
         navigator.move_prev_page();
         assert_eq!(0, navigator.index());
@@ -198,20 +208,20 @@ mod tests {
     #[test]
     fn relative_move_can_be_checked() {
         let mut navigator = Navigator::new(10, 2);
-        assert_eq!(true, navigator.can_move_towards(Direction::Right));
-        assert_eq!(false, navigator.can_move_towards(Direction::Left));
-        assert_eq!(false, navigator.can_move_towards(Direction::Up));
-        navigator.move_towards(Direction::Right);
-        assert_eq!(true, navigator.can_move_towards(Direction::Left));
+        assert_eq!(true, navigator.can_move_abs((1, 0)));
+        assert_eq!(false, navigator.can_move_abs((-1, 0)));
+        assert_eq!(false, navigator.can_move_abs((0, -1)));
+        navigator.move_abs((1, 0));
+        assert_eq!(true, navigator.can_move_abs((0, 0)));
     }
 
     #[test]
     fn on_last_page_move_is_checked_against_capacity() {
         let mut navigator = Navigator::new(10, 2);
         navigator.move_next_page();
-        assert_eq!(true, navigator.can_move_towards(Direction::Right));
+        assert_eq!(true, navigator.can_move_abs((1, 0)));
         navigator.move_next_page();
-        assert_eq!(false, navigator.can_move_towards(Direction::Down)); // because that would move to index 10
+        assert_eq!(false, navigator.can_move_abs((0, 1))); // because that would move to index 10
     }
 
     #[test]
@@ -237,9 +247,9 @@ mod tests {
     }
     #[test]
     #[should_panic]
-    fn navigator_should_panic_if_move_towards_where_not_allowed() {
+    fn navigator_should_panic_if_move_abs_where_not_allowed() {
         let mut navigator = Navigator::new(10, 2);
-        navigator.move_towards(Direction::Left);
+        navigator.move_abs((-1, 0));
     }
 
     #[test]
@@ -251,4 +261,18 @@ mod tests {
         navigator.move_next_page();
         assert_eq!(None, navigator.index_from_position((1,1))); // because that would be 11
     }
+
+    #[test]
+    fn rectangular_pages_hold_columns_times_rows_cells() {
+        let mut navigator = Navigator::with_rows(40, 5, 3);
+        assert_eq!(15, navigator.max_cells());
+        assert_eq!(true, navigator.can_move_abs((4, 2)));
+        assert_eq!(false, navigator.can_move_abs((2, 3)));
+        navigator.move_to_index(23);
+        assert_eq!(15, navigator.start_cell_index());
+        assert_eq!((3, 1), navigator.position());
+        navigator.move_prev_page();
+        navigator.move_prev_page();
+        assert_eq!(30, navigator.index());
+    }
 }
@@ -0,0 +1,95 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+
+// notices not yet shown are dropped past this number, oldest first
+const MAX_PENDING: usize = 20;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Level {
+    Info, Error,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Notice {
+    pub level: Level,
+    pub text: String,
+}
+
+// messages for the user: printed to the terminal as before, and kept until the window shows them;
+// interior mutability lets methods that only read the repository report too
+#[derive(Debug, Default)]
+pub struct Notices {
+    pending: RefCell<VecDeque<Notice>>,
+    // without a window, notices only go to stderr, apart from the results on stdout
+    headless: bool,
+}
+
+impl Notices {
+    pub fn headless() -> Self {
+        Notices { pending: RefCell::default(), headless: true }
+    }
+
+    fn push(&self, level: Level, text: String) {
+        let mut pending = self.pending.borrow_mut();
+        if pending.len() >= MAX_PENDING {
+            pending.pop_front();
+        };
+        pending.push_back(Notice { level: level, text: text })
+    }
+
+    pub fn info(&self, text: String) {
+        if self.headless {
+            eprintln!("{}", text);
+            return
+        };
+        println!("{}", text);
+        self.push(Level::Info, text)
+    }
+
+    pub fn error(&self, text: String) {
+        eprintln!("{}", text);
+        if !self.headless {
+            self.push(Level::Error, text)
+        }
+    }
+
+    pub fn take(&self) -> Vec<Notice> {
+        self.pending.borrow_mut().drain(..).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notices_are_taken_once_in_order() {
+        let notices = Notices::default();
+        notices.info(String::from("sort pictures by Date"));
+        notices.error(String::from("can't move to picture #999"));
+        let taken = notices.take();
+        assert_eq!(2, taken.len());
+        assert_eq!(Level::Info, taken[0].level);
+        assert_eq!("can't move to picture #999", taken[1].text);
+        assert!(notices.take().is_empty());
+    }
+
+    #[test]
+    fn headless_notices_are_not_kept() {
+        let notices = Notices::headless();
+        notices.info(String::from("selection set best (2 selected)"));
+        notices.error(String::from("no other selection set to combine with"));
+        assert!(notices.take().is_empty());
+    }
+
+    #[test]
+    fn oldest_notices_are_dropped() {
+        let notices = Notices::default();
+        for i in 0..MAX_PENDING + 5 {
+            notices.info(i.to_string())
+        };
+        let taken = notices.take();
+        assert_eq!(MAX_PENDING, taken.len());
+        assert_eq!("5", taken[0].text);
+    }
+}
@@ -1,7 +1,7 @@
 
 use clap::builder::PossibleValue;
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Order {
     Colors, Date, Name, Random, Size, Value, Palette, Label,
 }
@@ -1,10 +1,10 @@
 use std::io;
 use std::path::Path;
-use std::path::Component;
+use std::ffi::{OsStr, OsString};
 use std::path::{PathBuf};
 use std::fs;
 use std::env;
-use std::io::{Result, Error, ErrorKind};
+use std::io::{Result, Error, ErrorKind, Write};
 
 pub const THUMB_SUFFIX: &str = "THUMB";
 pub const IMAGE_DATA: &str = "IMAGE_DATA";
@@ -12,9 +12,10 @@ pub const IMAGE_DATA: &str = "IMAGE_DATA";
 const DEFAULT_DIR :&str    = "images/";
 const DIR_ENV_VAR :&str    = "GALLSHDIR";
 const DIR_COPY :&str       = "GALLSHTMP";
+const DIR_THUMBNAILS :&str = "GALLSHTHUMBDIR";
+const APP_DIR_NAME :&str   = "gallsh";
 
-pub fn is_valid_directory(dir: &str) -> bool {
-    let path = PathBuf::from(dir);
+pub fn is_valid_directory(path: &Path) -> bool {
     if ! path.exists() {
        return false
     } else {
@@ -26,11 +27,11 @@ pub fn is_valid_directory(dir: &str) -> bool {
     }
 }
 
-pub fn check_path(dir: &str, confirm_create: bool) -> Result<PathBuf> {
-    let path = PathBuf::from(dir);
+pub fn check_path(dir: &Path, confirm_create: bool) -> Result<PathBuf> {
+    let path = dir.to_path_buf();
     if !path.exists() {
         if confirm_create {
-            println!("directory {} doesn't exist. Create ?", dir);
+            println!("directory {} doesn't exist. Create ?", dir.display());
             let mut response = String::new();
             let stdin = io::stdin();
             stdin.read_line(&mut response).expect("can't read from stdin");
@@ -48,23 +49,22 @@ pub fn check_path(dir: &str, confirm_create: bool) -> Result<PathBuf> {
                 None => Err(Error::new(ErrorKind::Other, "directory creation cancelled")),
             }
         } else {
-            Err(Error::new(ErrorKind::Other, format!("path {} doesn't exist", dir)))
+            Err(Error::new(ErrorKind::Other, format!("path {} doesn't exist", dir.display())))
         }
     } else {
         if is_valid_directory(dir) {
             Ok(path)
         } else {
-            Err(Error::new(ErrorKind::Other, format!("path {} is not a directory", dir)))
+            Err(Error::new(ErrorKind::Other, format!("path {} is not a directory", dir.display())))
         }
     }
 }
 
-pub fn check_label_path(dir: &str, label: &str) -> Result<PathBuf> {
-    let path = PathBuf::from(dir).join(label);
-    check_path(path.to_str().unwrap(),true)
+pub fn check_label_path(dir: &Path, label: &str) -> Result<PathBuf> {
+    check_path(&dir.join(label), true)
 }
 
-pub fn temporary_path() -> PathBuf {
+fn temporary_path() -> PathBuf {
     let gallshtmp = env::var(DIR_COPY);
     if let Ok(dir) = &gallshtmp {
         PathBuf::from(dir)
@@ -73,76 +73,174 @@ pub fn temporary_path() -> PathBuf {
     }
 }
 
-pub fn determine_path(directory: Option<String>) -> String {
-    let gallshdir = env::var(DIR_ENV_VAR);
-    if let Some(directory_arg) = directory {
-        String::from(directory_arg)
-    } else if let Ok(standard_dir) = &gallshdir {
-        String::from(standard_dir)
+// where pictures are copied to and where thumbnails are kept; the environment wins over the config
+#[derive(Clone, Debug, PartialEq)]
+pub struct Directories {
+    pub temporary: PathBuf,
+    pub thumbnails: Option<String>,
+}
+
+impl Default for Directories {
+    fn default() -> Self {
+        Directories { temporary: PathBuf::from("."), thumbnails: None }
+    }
+}
+
+impl Directories {
+    pub fn new(temporary: Option<&str>, thumbnails: Option<&str>) -> Self {
+        let temporary = match (env::var(DIR_COPY), temporary) {
+            (Err(_), Some(dir)) => PathBuf::from(dir),
+            _ => temporary_path(),
+        };
+        let thumbnails = env::var(DIR_THUMBNAILS).ok().or(thumbnails.map(String::from));
+        Directories {
+            temporary: temporary,
+            thumbnails: thumbnails.filter(|name| !name.is_empty()),
+        }
+    }
+}
+
+// an empty directory for a test, apart from the other tests and from other runs
+#[cfg(test)]
+pub fn test_directory(name: &str) -> PathBuf {
+    let directory = env::temp_dir().join(format!("gallsh_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(&directory).unwrap();
+    directory
+}
+
+// user configuration, such as key bindings
+pub fn config_path() -> PathBuf {
+    if let Ok(dir) = env::var("XDG_CONFIG_HOME") {
+        PathBuf::from(dir).join(APP_DIR_NAME)
+    } else if let Ok(home) = env::var("HOME") {
+        PathBuf::from(home).join(".config").join(APP_DIR_NAME)
     } else {
-        println!("GALLSHDIR variable not set. Using {} as default.", DEFAULT_DIR);
-        String::from(DEFAULT_DIR)
+        temporary_path().join(format!(".{}", APP_DIR_NAME))
     }
 }
-pub fn thumbnail_file_path(file_path: &str) -> String {
-    if file_path.contains(&THUMB_SUFFIX) {
-        file_path.to_string()
+
+// cache for generated files that can be rebuilt, such as picture tiles
+pub fn cache_path() -> PathBuf {
+    if let Ok(dir) = env::var("XDG_CACHE_HOME") {
+        PathBuf::from(dir).join(APP_DIR_NAME)
+    } else if let Ok(home) = env::var("HOME") {
+        PathBuf::from(home).join(".cache").join(APP_DIR_NAME)
     } else {
-        let path = PathBuf::from(file_path);
-        let parent = path.parent().unwrap();
-        let extension = path.extension().unwrap();
-        let file_stem = path.file_stem().unwrap();
-        let new_file_name = format!("{}{}.{}", file_stem.to_str().unwrap(), THUMB_SUFFIX, extension.to_str().unwrap());
-        let new_path = parent.join(new_file_name);
-        new_path.to_str().unwrap().to_string()
+        temporary_path().join(format!(".{}", APP_DIR_NAME))
+    }
+}
+
+// the gallery directory from the command line, or GALLSHDIR, or the config default
+pub fn directory_setting(directory: Option<PathBuf>, default: Option<String>) -> Option<PathBuf> {
+    directory.or(env::var_os(DIR_ENV_VAR).map(PathBuf::from)).or(default.map(PathBuf::from))
+}
+
+pub fn determine_path(directory: Option<PathBuf>, default: Option<String>) -> PathBuf {
+    match directory_setting(directory, default) {
+        Some(dir) => dir,
+        None => {
+            eprintln!("GALLSHDIR variable not set. Using {} as default.", DEFAULT_DIR);
+            PathBuf::from(DEFAULT_DIR)
+        },
     }
 }
 
-pub fn image_data_file_path(file_path: &str) -> String {
-    let image_file_path = original_file_path(file_path);
-    let path = PathBuf::from(image_file_path);
-    let parent = path.parent().unwrap();
-    let file_stem = path.file_stem().unwrap().to_str().unwrap();
-    let new_file_name = format!("{}{}.json", file_stem, IMAGE_DATA);
-    let new_path = parent.join(new_file_name);
-    new_path.to_str().unwrap().to_string()
+// dir_name is the subdirectory holding the thumbnails of the pictures of a directory,
+// none when thumbnails are next to their pictures
+pub fn thumbnail_directory(directory: &Path, dir_name: Option<&str>) -> PathBuf {
+    match dir_name {
+        Some(name) => directory.join(name),
+        None => directory.to_path_buf(),
+    }
 }
 
-pub fn directory(file_path: &str) -> String {
-    let path = PathBuf::from(file_path);
-    let parent = path.parent()
-        .expect(&format!("file path {} has no parent",file_path));
-    let directory = parent.components().next_back()
-        .expect(&format!("can't find directory of {}", file_path));
-    <Component<'_> as AsRef<Path>>::as_ref(&directory).to_str().unwrap().to_string()
+pub fn thumbnail_file_path(file_path: &Path, dir_name: Option<&str>) -> PathBuf {
+    if is_thumbnail(file_path) {
+        file_path.to_path_buf()
+    } else {
+        let parent = thumbnail_directory(file_path.parent().unwrap_or(Path::new("")), dir_name);
+        let mut new_file_name = file_path.file_stem().unwrap_or_default().to_os_string();
+        new_file_name.push(THUMB_SUFFIX);
+        if let Some(extension) = file_path.extension() {
+            new_file_name.push(".");
+            new_file_name.push(extension)
+        };
+        parent.join(new_file_name)
+    }
 }
 
-pub fn original_file_path(file_path: &str) -> String {
+pub fn image_data_file_path(file_path: &Path, dir_name: Option<&str>) -> PathBuf {
+    let path = original_file_path(file_path, dir_name);
+    let parent = path.parent().unwrap_or(Path::new(""));
+    let mut new_file_name = path.file_stem().unwrap_or_default().to_os_string();
+    new_file_name.push(format!("{}.json", IMAGE_DATA));
+    parent.join(new_file_name)
+}
+
+// the name of the directory holding the file, for display
+pub fn directory(file_path: &Path) -> String {
+    file_path.parent()
+        .and_then(|parent| parent.components().next_back())
+        .map(|directory| directory.as_os_str().to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+// a file name from the bytes it's written with; on unix any name comes back as it was
+#[cfg(unix)]
+pub fn os_string_from_bytes(bytes: &[u8]) -> OsString {
+    use std::os::unix::ffi::OsStrExt;
+    OsStr::from_bytes(bytes).to_os_string()
+}
+
+#[cfg(not(unix))]
+pub fn os_string_from_bytes(bytes: &[u8]) -> OsString {
+    OsString::from(String::from_utf8_lossy(bytes).into_owned())
+}
+
+// paths are written as they are, whatever their encoding, one per line
+pub fn write_path_line(writer: &mut impl Write, path: &Path) -> Result<()> {
+    writer.write_all(path.as_os_str().as_encoded_bytes())?;
+    writer.write_all(b"\n")
+}
+
+// the stem without the suffix
+fn strip_thumb_suffix(file_stem: &OsStr) -> OsString {
+    match file_stem.as_encoded_bytes().strip_suffix(THUMB_SUFFIX.as_bytes()) {
+        Some(bytes) => os_string_from_bytes(bytes),
+        None => file_stem.to_os_string(),
+    }
+}
+
+pub fn original_file_path(file_path: &Path, dir_name: Option<&str>) -> PathBuf {
     if !is_thumbnail(file_path) {
-        file_path.to_string()
+        file_path.to_path_buf()
     } else {
-        let path = PathBuf::from(file_path);
-        let parent = path.parent().unwrap();
-        let extension = path.extension().unwrap();
-        let file_stem = path.file_stem().unwrap().to_str().unwrap();
-        let new_file_stem = match file_stem.strip_suffix("THUMB") {
-            Some(s) => s,
-            None => &file_stem,
+        let mut parent = file_path.parent().unwrap_or(Path::new(""));
+        if let (Some(name), Some(grand_parent)) = (dir_name, parent.parent()) {
+            if parent.file_name().map(|n| n == name).unwrap_or(false) {
+                parent = grand_parent
+            }
+        };
+        let mut new_file_name = strip_thumb_suffix(file_path.file_stem().unwrap_or_default());
+        if let Some(extension) = file_path.extension() {
+            new_file_name.push(".");
+            new_file_name.push(extension)
         };
-        let new_file_name = format!("{}.{}", new_file_stem, extension.to_str().unwrap());
-        let new_path = parent.join(new_file_name);
-        new_path.to_str().unwrap().to_string()
+        parent.join(new_file_name)
     }
 }
 
-pub fn original_file_name(file_path: &str) -> String  {
-    let original = original_file_path(file_path);
-    let path = PathBuf::from(original);
-    path.file_name().unwrap().to_str().unwrap().to_string()
+// for display
+pub fn original_file_name(file_path: &Path, dir_name: Option<&str>) -> String  {
+    original_file_path(file_path, dir_name).file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
 }
 
-pub fn is_thumbnail(file_path: &str) -> bool {
-    file_path.contains(&THUMB_SUFFIX)
+// only the name counts, a directory named THUMB holds pictures like any other
+pub fn is_thumbnail(file_path: &Path) -> bool {
+    file_path.file_stem().map_or(false, |stem| stem.as_encoded_bytes().ends_with(THUMB_SUFFIX.as_bytes()))
 }
 
 #[cfg(test)]
@@ -151,21 +249,47 @@ mod tests {
 
     #[test]
     fn thumbnail_file_path_is_file_path_with_an_add_thumb_suffix() {
-        assert_eq!("photos/fooTHUMB.jpeg", thumbnail_file_path("photos/foo.jpeg"));
+        assert_eq!(Path::new("photos/fooTHUMB.jpeg"), thumbnail_file_path(Path::new("photos/foo.jpeg"), None));
     }
     #[test]
     fn original_file_name_is_rid_of_any_thumb_suffix_and_path() {
-        assert_eq!("foo.jpeg", original_file_name("photos/fooTHUMB.jpeg"));
+        assert_eq!("foo.jpeg", original_file_name(Path::new("photos/fooTHUMB.jpeg"), None));
     }
 
     #[test]
     fn thumbnail_file_path_is_added_the_thumb_suffix() {
-        assert_eq!("photos/fooTHUMB.jpeg", thumbnail_file_path("photos/foo.jpeg"));
+        assert_eq!(Path::new("photos/fooTHUMB.jpeg"), thumbnail_file_path(Path::new("photos/foo.jpeg"), None));
     }
 
     #[test]
     fn image_data_file_path_is_added_the_image_data_suffix_and_json_extension() {
-        assert_eq!("photos/fooIMAGE_DATA.json", image_data_file_path("photos/foo.jpeg"));
+        assert_eq!(Path::new("photos/fooIMAGE_DATA.json"), image_data_file_path(Path::new("photos/foo.jpeg"), None));
+    }
+
+    #[test]
+    fn thumbnails_are_told_by_the_end_of_their_name() {
+        assert!(is_thumbnail(Path::new("photos/fooTHUMB.jpeg")));
+        assert!(!is_thumbnail(Path::new("THUMBS/foo.jpeg")));
+        assert!(!is_thumbnail(Path::new("photos/THUMBnail.jpeg")));
+    }
+
+    #[test]
+    fn thumbnails_can_be_kept_in_a_subdirectory() {
+        assert_eq!(Path::new("photos/.thumbs/fooTHUMB.jpeg"), thumbnail_file_path(Path::new("photos/foo.jpeg"), Some(".thumbs")));
+        assert_eq!(Path::new("photos/foo.jpeg"), original_file_path(Path::new("photos/.thumbs/fooTHUMB.jpeg"), Some(".thumbs")));
+        assert_eq!(Path::new("photos/other/foo.jpeg"), original_file_path(Path::new("photos/other/fooTHUMB.jpeg"), Some(".thumbs")));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn sidecar_files_keep_names_that_are_not_utf8() {
+        use std::os::unix::ffi::OsStrExt;
+        let name = OsStr::from_bytes(b"photos/caf\xe9.jpeg");
+        let thumbnail = thumbnail_file_path(Path::new(name), None);
+        assert_eq!(OsStr::from_bytes(b"photos/caf\xe9THUMB.jpeg"), thumbnail.as_os_str());
+        assert_eq!(Path::new(name), original_file_path(&thumbnail, None));
+        assert_eq!(OsStr::from_bytes(b"photos/caf\xe9IMAGE_DATA.json"), image_data_file_path(Path::new(name), None).as_os_str());
+        assert_eq!("caf\u{FFFD}.jpeg", original_file_name(Path::new(name), None));
     }
 
 }
@@ -2,100 +2,93 @@ use crate::paths::check_label_path;
 use rand::thread_rng;
 use rand::prelude::SliceRandom;
 use crate::entry::entries_with_label;
+use crate::metadata::is_valid_label;
 use crate::Entry;
 use crate::EntryList;
-use crate::THUMB_SUFFIX;
 use crate::entry::make_entry;
+use crate::error::{GalleryError, Result, report_problems};
 use crate::image::get_image_color;
 use crate::image_data::ImageData;
-use crate::paths::{check_path, temporary_path};
+use crate::paths::{check_path, thumbnail_directory, write_path_line, Directories};
 use crate::paths::is_thumbnail;
+use crate::reading_list::parse_reading_list;
+use crate::selection::{SelectionSets, SELECTION_SETS_FILE_NAME};
 use crate::rank::Rank;
 use gtk::cairo::{Context, Format, ImageSurface};
 use palette_extract::{get_palette_rgb};
 use regex::Regex;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::ffi::OsStr;
 use std::fs::File;
+use std::fs::create_dir_all;
 use std::fs::OpenOptions;
 use std::fs::read_to_string;
 use std::fs::remove_file;
 use std::fs;
 use std::io::BufReader;
-use std::io::Write;
-use std::io::{Result,Error, ErrorKind};
 use std::path::Path;
 use std::path::PathBuf;
 use thumbnailer::ThumbnailSize;
 use thumbnailer::create_thumbnails;
-use thumbnailer::error::ThumbResult;
 use walkdir::WalkDir;
 
 const VALID_EXTENSIONS: [&'static str; 6] = ["jpg", "jpeg", "png", "JPG", "JPEG", "PNG"];
-const SELECTION_FILE_NAME: &str = "selections";
+pub const PALETTE_WIDTH: i32 = 90;
+pub const PALETTE_HEIGHT: i32 = 10;
 
 
 pub fn set_original_picture_file(picture: &gtk::Picture, entry: &Entry) -> Result<()> {
-    let original = entry.original_file_path();
-    let path = Path::new(&original);
+    let path = entry.original_file_path();
     if path.exists() {
-        picture.set_filename(Some(original));
+        picture.set_filename(Some(&path));
         Ok(())
     } else {
-        Err(Error::new(ErrorKind::Other, format!("file {} doesn't exist", original)))
+        Err(GalleryError::picture(&path, "file doesn't exist"))
     }
 }
 
-fn write_thumbnail<R: std::io::Seek + std::io::Read>(reader: BufReader<R>, extension: &str, mut output_file: File) -> ThumbResult<()> {
+fn write_thumbnail<R: std::io::Seek + std::io::Read>(reader: BufReader<R>, source: &Path, extension: &str, mut output_file: File) -> Result<()> {
     let mime = match extension {
         "jpg" | "jpeg" | "JPG" | "JPEG" => mime::IMAGE_JPEG,
         "png" | "PNG" => mime::IMAGE_PNG,
-        _ => panic!("wrong extension"),
-    };
-    let mut thumbnails = match create_thumbnails(reader, mime, [ThumbnailSize::Small]) {
-        Ok(tns) => tns,
-        Err(err) => {
-            println!("error while creating thumbnails:{:?}", err);
-            return Err(err)
-        },
+        _ => return Err(GalleryError::thumbnail(source, format!("wrong extension {}", extension))),
     };
-    let thumbnail = thumbnails.pop().unwrap();
-    let write_result = match extension {
-        "jpg" | "jpeg" | "JPG" | "JPEG" => thumbnail.write_jpeg(&mut output_file,255),
-        "png" | "PNG" => thumbnail.write_png(&mut output_file),
-        _ => panic!("wrong extension"),
+    let mut thumbnails = create_thumbnails(reader, mime.clone(), [ThumbnailSize::Small])
+        .map_err(|err| GalleryError::thumbnail(source, err))?;
+    let thumbnail = thumbnails.pop()
+        .ok_or(GalleryError::thumbnail(source, "no thumbnail created"))?;
+    let write_result = if mime == mime::IMAGE_PNG {
+        thumbnail.write_png(&mut output_file)
+    } else {
+        thumbnail.write_jpeg(&mut output_file,255)
     };
-    match write_result {
-        Err(err) => {
-            println!("error while writing ihunbnail:{}", err);
-            Err(err)
-        },
-        ok => ok,
-    }
+    write_result.map_err(|err| GalleryError::thumbnail(source, err))
 }
 
 fn create_thumbnail(entry: &Entry) -> Result<()> {
     let original = entry.original_file_path();
     let thumbnail = entry.thumbnail_file_path();
-    println!("creating thumbnail {}", thumbnail);
-    match File::open(original.clone()) {
-        Err(err) => Err(err),
+    eprintln!("creating thumbnail {}", thumbnail.display());
+    let source_path = original.as_path();
+    match File::open(source_path) {
+        Err(err) => Err(GalleryError::picture(source_path, err)),
         Ok(input_file) => {
-            let source_path = Path::new(&original);
             let extension = match source_path.extension()
                 .and_then(OsStr::to_str) { 
-                    None => return Err(Error::new(ErrorKind::Other, format!("source file has no extension"))),
+                    None => return Err(GalleryError::thumbnail(source_path, "source file has no extension")),
                     Some(ext) => ext,
                 };
             let reader = BufReader::new(input_file);
-            let output_file = match File::create(thumbnail) {
-                Err(err) => return Err(err),
-                Ok(file) => file,
+            if let Some(directory) = Path::new(&thumbnail).parent() {
+                create_dir_all(directory)?
             };
-            match write_thumbnail(reader, extension, output_file) {
-                Err(err) => Err(Error::new(ErrorKind::Other, err)),
-                Ok(_) => Ok (()),
-            }
+            let output_file = File::create(&thumbnail)?;
+            let result = write_thumbnail(reader, source_path, extension, output_file);
+            // a half written thumbnail would be taken for a good one next time
+            if result.is_err() {
+                let _ = remove_file(&thumbnail);
+            };
+            result
         },
     }
 }
@@ -115,16 +108,35 @@ pub fn ensure_thumbnail(entry: &Entry) -> Result<()> {
     }
 }
 
+// the picture changed, so its thumbnail is made again unless it is newer than the picture
+pub fn refresh_thumbnail(entry: &Entry) -> Result<()> {
+    let thumbnail = entry.thumbnail_file_path();
+    let is_current = fs::metadata(&thumbnail)
+        .and_then(|metadata| metadata.modified())
+        .map(|time| time >= entry.modified_time)
+        .unwrap_or(false);
+    if is_current {
+        return Ok(())
+    };
+    let _ = remove_file(&thumbnail);
+    create_thumbnail(entry)
+}
+
+// reads the image data and makes the thumbnail of a picture added or changed while the gallery runs
+pub fn prepare_picture(path: &Path, directories: &Directories) -> Result<()> {
+    let entry = entry_from_path(path, directories)?;
+    refresh_thumbnail(&entry)
+}
+
 pub fn set_thumbnail_picture_file(picture: &gtk::Picture, entry: &Entry) -> Result<()> {
     let thumbnail = entry.thumbnail_file_path();
-    let path = Path::new(&thumbnail);
-    if path.exists() {
-        picture.set_filename(Some(thumbnail));
+    if thumbnail.exists() {
+        picture.set_filename(Some(&thumbnail));
         Ok(())
     } else {
         match create_thumbnail(entry) {
             Ok(()) => {
-                picture.set_filename(Some(thumbnail));
+                picture.set_filename(Some(&thumbnail));
                 Ok(())
             },
             err => err,
@@ -132,14 +144,16 @@ pub fn set_thumbnail_picture_file(picture: &gtk::Picture, entry: &Entry) -> Resu
     }
 }
 
-fn set_palette(entry: &mut Entry) {
-    let image = image::open(entry.original_file_path()).expect("can't open image file for palette extraction");
+fn set_palette(entry: &mut Entry) -> Result<()> {
+    let original = entry.original_file_path();
+    let image = image::open(&original).map_err(|err| GalleryError::picture(&original, err))?;
     let pixels = image.as_bytes();
     let palette = get_palette_rgb(&pixels);
     palette.iter().enumerate().for_each(|(i,c)| {
         entry.image_data.palette[i] = (c.r as u32) << 16 | (c.g as u32) << 8 | c.b as u32;
     });
     entry.image_data.palette.sort();
+    Ok(())
 }
 
 pub fn set_image_data(entry: &mut Entry) -> Result<()> {
@@ -152,19 +166,20 @@ pub fn set_image_data(entry: &mut Entry) -> Result<()> {
                     entry.image_data = image_data;
                     Ok(())
                 },
-                Err(err) => Err(err.into()),
+                Err(err) => Err(GalleryError::image_data(path, err)),
             },
-            Err(err) => Err(err),
+            Err(err) => Err(GalleryError::image_data(path, err)),
         }
     } else {
-        match get_image_color(&entry.original_file_path()) {
+        let original = entry.original_file_path();
+        match get_image_color(&original) {
             Ok(colors) => {
                 entry.image_data = ImageData::new(colors, Rank::NoStar);
-                set_palette(entry);
+                set_palette(entry)?;
                 let _ = save_image_data(&entry);
                 Ok(())
             },
-            Err(err) => Err(Error::new(ErrorKind::Other,err)),
+            Err(err) => Err(GalleryError::picture(&original, err)),
         }
     }
 }
@@ -173,8 +188,20 @@ pub fn draw_palette(ctx: &Context, width: i32, height: i32, colors: &[u32;9]) {
     const COLOR_MAX: f64 = 9.0;
     let square_size: f64 = height as f64;
     let offset: f64 = (width as f64 - (COLOR_MAX as f64 * square_size)) / 2.0;
-    let surface = ImageSurface::create(Format::ARgb32, width, height).expect("can't create surface");
-    let context = Context::new(&surface).expect("can't create context");
+    let surface = match ImageSurface::create(Format::ARgb32, width, height) {
+        Ok(surface) => surface,
+        Err(err) => {
+            eprintln!("can't create surface: {}", err);
+            return
+        },
+    };
+    let context = match Context::new(&surface) {
+        Ok(context) => context,
+        Err(err) => {
+            eprintln!("can't create context: {}", err);
+            return
+        },
+    };
     for (i,w) in colors.iter().enumerate() {
         let r = ((w >> 16) & 255) as u8;
         let g = ((w >> 8) & 255) as u8;
@@ -182,109 +209,135 @@ pub fn draw_palette(ctx: &Context, width: i32, height: i32, colors: &[u32;9]) {
         context.set_source_rgb(r as f64 / 255.0, g as f64 / 255.0, b as f64 / 255.0);
         let x = i as f64 * square_size;
         context.rectangle(offset + x, 0.0, square_size, square_size);
-        context.fill().expect("can't fill rectangle");
+        if let Err(err) = context.fill() {
+            eprintln!("can't fill rectangle: {}", err)
+        }
     };
-    ctx.set_source_surface(&surface, 0.0, 0.0).expect("can't set source surface");
-    ctx.paint().expect("can't paint surface")
+    if let Err(err) = ctx.set_source_surface(&surface, 0.0, 0.0).and_then(|_| ctx.paint()) {
+        eprintln!("can't paint surface: {}", err)
+    }
 }
 
 pub fn save_image_data(entry: &Entry) -> Result<()> {
-    println!("saving image data {}", entry.image_data_file_path());
+    eprintln!("saving image data {}", entry.image_data_file_path().display());
     let image_data_file_path = entry.image_data_file_path();
     let path = Path::new(&image_data_file_path);
     match File::create(path) {
         Ok(file) => {
             match serde_json::to_writer(file, &entry.image_data) {
                 Ok(_) => Ok(()),
-                Err(err) => Err(err.into()),
+                Err(err) => Err(GalleryError::image_data(path, err)),
             }
         },
         Err(err) => {
-            println!("error saving image data {} : {}", path.display(), err);
-            Err(err)
+            eprintln!("error saving image data {} : {}", path.display(), err);
+            Err(GalleryError::image_data(path, err))
         },
     }
 }
 
-pub fn save_image_list(list: Vec<String>) {
+pub fn save_image_list(file_path: &Path, list: Vec<PathBuf>) {
     if let Ok(mut file) = OpenOptions::new()
         .write(true)
         .create(true)
         .truncate(true)
-        .open(SELECTION_FILE_NAME) {
+        .open(file_path) {
         for line in list {
-            writeln!(file, "{}", line).expect("can't write")
+            if let Err(err) = write_path_line(&mut file, &line) {
+                eprintln!("can't write {}: {}", file_path.display(), err);
+                return
+            }
         }
     }
 }
 
-pub fn delete_selection_file() {
-    let path = Path::new(SELECTION_FILE_NAME);
+pub fn delete_selection_file(file_path: &Path) {
+    if file_path.exists() {
+        let _ = remove_file(file_path);
+    }
+}
+
+pub fn read_selection_sets(gallery: &Path) -> SelectionSets {
+    let path = gallery.join(SELECTION_SETS_FILE_NAME);
     if path.exists() {
-        let _ = remove_file(path);
+        match read_to_string(&path).map(|content| serde_json::from_str(&content)) {
+            Ok(Ok(sets)) => sets,
+            Ok(Err(err)) => {
+                eprintln!("can't parse selection sets {}: {}", path.display(), err);
+                SelectionSets::default()
+            },
+            Err(err) => {
+                eprintln!("can't read selection sets {}: {}", path.display(), err);
+                SelectionSets::default()
+            },
+        }
+    } else {
+        SelectionSets::default()
     }
 }
 
-fn push_entry_from_path(path: &Path, pattern_opt: Option<String>, entry_list: &mut EntryList) -> Result<()> {
+pub fn save_selection_sets(gallery: &Path, sets: &SelectionSets) -> Result<()> {
+    let path = gallery.join(SELECTION_SETS_FILE_NAME);
+    let content = sets.to_json().map_err(std::io::Error::from)?;
+    Ok(fs::write(path, content)?)
+}
+
+// a picture the gallery shows, not one of its thumbnails
+pub fn is_picture_file(path: &Path) -> bool {
     let valid_extension = match path.extension() {
-        Some(extension) => VALID_EXTENSIONS.contains(&extension.to_str().unwrap()),
+        Some(extension) => VALID_EXTENSIONS.contains(&extension.to_str().unwrap_or("")),
         None => false,
     };
-    let matches_pattern = path.is_file() && match pattern_opt {
-        None => true,
-        Some(pattern) => {
-            match Regex::new(&pattern) {
-                Ok(reg_exp) => match reg_exp.captures(path.to_str().unwrap()) {
-                    Some(_) => true,
-                    None => false,
-                },
-                Err(err) => {
-                    println!("can't parse regular expression {}: {}", pattern, err);
-                    false
-                },
-            }
-        },
+    valid_extension && !is_thumbnail(path)
+}
+
+// the entry for a picture file, with its image data read or created
+pub fn entry_from_path(path: &Path, directories: &Directories) -> Result<Entry> {
+    let metadata = fs::metadata(&path).map_err(|err| GalleryError::picture(path, err))?;
+    let file_size = metadata.len();
+    if file_size == 0 {
+        eprintln!("file {} has a size of 0", path.display())
     };
-    let not_a_thumbnail = match path.to_str().map(|filename| filename.contains(THUMB_SUFFIX)) {
-        Some(false) => true,
-        _ => false,
+    let modified_time = metadata.modified()?;
+    let mut entry = make_entry(path.to_path_buf(), file_size, 0, modified_time, Rank::NoStar);
+    entry.thumbnails = directories.thumbnails.clone();
+    set_image_data(&mut entry)?;
+    Ok(entry)
+}
+
+fn compile_pattern(pattern_opt: Option<String>) -> Result<Option<Regex>> {
+    match pattern_opt {
+        Some(pattern) => Regex::new(&pattern)
+            .map(Some)
+            .map_err(|err| GalleryError::Pattern(pattern.clone(), err.to_string())),
+        None => Ok(None),
+    }
+}
+
+// a file that can't be read is added to the problems, and the scan goes on
+fn push_entry_from_path(path: &Path, pattern: Option<&Regex>, directories: &Directories, entry_list: &mut EntryList, problems: &mut Vec<GalleryError>) {
+    let matches_pattern = path.is_file() && match pattern {
+        None => true,
+        Some(reg_exp) => reg_exp.is_match(&path.to_string_lossy()),
     };
-    if valid_extension && not_a_thumbnail && matches_pattern {
-        if let Ok(metadata) = fs::metadata(&path) {
-            let file_size = metadata.len();
-            if file_size == 0 {
-                println!("file {} has a size of 0", path.display())
-            };
-            let modified_time = metadata.modified().unwrap();
-            let name = path.to_str().unwrap().to_string().to_owned();
-            let mut entry = make_entry(name, file_size, 0, modified_time, Rank::NoStar);
-            match set_image_data(&mut entry) {
-                Ok(_) => {
-                    entry_list.push(entry);
-                },
-                Err(_) => {
-                    println!("can't find or create image data for file {}", path.display())
-                },
-            }
-        } else {
-            println!("can't open: {}", path.display());
+    if is_picture_file(path) && matches_pattern {
+        match entry_from_path(path, directories) {
+            Ok(entry) => entry_list.push(entry),
+            Err(err) => problems.push(err),
         }
-    };
-    Ok(())
-}
-pub fn entries_from_directory(dir: &str, pattern_opt: Option<String>) -> Result<EntryList> {
-    match check_path(dir, false) {
-        Ok(directory) => {
-            let mut entry_list: EntryList = Vec::new();
-            for path in WalkDir::new(directory).into_iter().filter_map(|e| e.ok()).map(|e| e.into_path()) {
-                push_entry_from_path(&path, pattern_opt.clone(), &mut entry_list).unwrap();
-            };
-            Ok(entry_list.clone())
-        },
-        Err(e) => Err(e),
     }
 }
 
+pub fn entries_from_directory(dir: &Path, pattern_opt: Option<String>, directories: &Directories, problems: &mut Vec<GalleryError>) -> Result<EntryList> {
+    let pattern = compile_pattern(pattern_opt)?;
+    let directory = check_path(dir, false)?;
+    let mut entry_list: EntryList = Vec::new();
+    for path in WalkDir::new(directory).into_iter().filter_map(|e| e.ok()).map(|e| e.into_path()) {
+        push_entry_from_path(&path, pattern.as_ref(), directories, &mut entry_list, problems)
+    };
+    Ok(entry_list)
+}
+
 pub fn sample_entries(entry_list: &EntryList) -> EntryList {
     let mut entries = entry_list.clone();
     entries.shuffle(&mut thread_rng());
@@ -293,7 +346,7 @@ pub fn sample_entries(entry_list: &EntryList) -> EntryList {
     entries.into_iter().for_each( |entry| {
         let key = entry.directory();
         if !paths.contains(&key) {
-            println!("insert {}", key);
+            eprintln!("insert {}", key);
             paths.insert(key);
             sample.push(entry.clone());
         }
@@ -301,42 +354,60 @@ pub fn sample_entries(entry_list: &EntryList) -> EntryList {
     sample.clone()
 }
 
-pub fn entries_from_file(file: &str) -> Result<EntryList> {
+pub fn entries_from_file(file: &Path, directories: &Directories, problems: &mut Vec<GalleryError>) -> Result<EntryList> {
     let mut entry_list: EntryList = Vec::new();
-    let path = PathBuf::from(file);
-    push_entry_from_path(&path, None, &mut entry_list).unwrap();
-    Ok(entry_list.clone())
+    push_entry_from_path(file, None, directories, &mut entry_list, problems);
+    Ok(entry_list)
 }
 
-pub fn entries_from_reading_list(reading_list: &str, pattern_opt: Option<String>) -> Result<EntryList> {
-    match read_to_string(reading_list) {
+pub fn entries_from_reading_list(reading_list: &Path, pattern_opt: Option<String>, directories: &Directories, problems: &mut Vec<GalleryError>) -> Result<EntryList> {
+    let pattern = compile_pattern(pattern_opt)?;
+    match fs::read(reading_list) {
         Err(err) => {
-            Err(err)
+            Err(err.into())
         },
         Ok(content) => {
+            let base_dir = reading_list.parent().unwrap_or(Path::new("."));
             let mut entry_list: EntryList = Vec::new();
-            let mut file_paths_set: HashSet<String> = HashSet::new();
-            for path in content.lines().map(String::from).filter(|p| !is_thumbnail(p)).collect::<Vec<_>>().into_iter().map(|line| PathBuf::from(line)) {
-                let file_path = path.to_str().unwrap().to_string();
-                if ! file_paths_set.contains(&file_path) {
-                    file_paths_set.insert(file_path);
-                    push_entry_from_path(&path, pattern_opt.clone(), &mut entry_list).unwrap()
+            let mut file_paths_set: HashSet<PathBuf> = HashSet::new();
+            for item in parse_reading_list(&content, base_dir).into_iter().filter(|item| !is_thumbnail(&item.path)) {
+                if ! file_paths_set.contains(&item.path) {
+                    file_paths_set.insert(item.path.clone());
+                    let count = entry_list.len();
+                    push_entry_from_path(&item.path, pattern.as_ref(), directories, &mut entry_list, problems);
+                    // hints only fill in what the image data doesn't have yet
+                    if let Some(entry) = entry_list.get_mut(count) {
+                        if let Some(rank) = item.rank {
+                            if entry.image_data.rank == Rank::NoStar {
+                                entry.set_rank(rank)
+                            }
+                        };
+                        if let Some(label) = &item.label {
+                            if !is_valid_label(label) {
+                                problems.push(GalleryError::Hint(item.path.clone(), format!("invalid label: {}", label)))
+                            } else if entry.image_data.label().is_none() {
+                                entry.set_label(label)
+                            }
+                        }
+                    }
                 } else {
-                    println!("{} already in reading list", path.display());
+                    eprintln!("{} already in reading list", item.path.display());
                 }
             };
-            Ok(entry_list.clone())
+            Ok(entry_list)
         },
     }
 }
 
-pub fn read_entries(reading_list_opt: Option<String>, file_name_opt: Option<String>, path: String, pattern_opt: Option<String>, sample: bool) -> Result<EntryList> {  
-    if let Some(list_file_name) = reading_list_opt {
-        entries_from_reading_list(&list_file_name, pattern_opt.clone())
+// the entries read, and the files that had to be left out
+pub fn scan_entries(reading_list_opt: Option<PathBuf>, file_name_opt: Option<PathBuf>, path: PathBuf, pattern_opt: Option<String>, sample: bool, directories: &Directories) -> Result<(EntryList, Vec<GalleryError>)> {
+    let mut problems: Vec<GalleryError> = Vec::new();
+    let list = if let Some(list_file_name) = reading_list_opt {
+        entries_from_reading_list(&list_file_name, pattern_opt.clone(), directories, &mut problems)
     } else if let Some(file_name) = file_name_opt {
-        entries_from_file(&file_name)
+        entries_from_file(&file_name, directories, &mut problems)
     } else {
-        entries_from_directory(&path, pattern_opt.clone())
+        entries_from_directory(&path, pattern_opt.clone(), directories, &mut problems)
             .and_then(|entries| {
                 if sample {
                     Ok(sample_entries(&entries))
@@ -344,66 +415,77 @@ pub fn read_entries(reading_list_opt: Option<String>, file_name_opt: Option<Stri
                     Ok(entries)
                 }
             })
-    }.and_then( |list| {
-        if list.is_empty() {
-            Err(Error::new(ErrorKind::Other, "no entries in the selection"))
-        } else {
-            Ok(list)
-        }
-    })
+    }?;
+    if list.is_empty() {
+        report_problems(&problems);
+        Err(GalleryError::NoEntries)
+    } else {
+        Ok((list, problems))
+    }
+}
+
+// the paths of the entries no longer scanned, and the entries scanned that are new or whose file changed
+pub fn entry_changes<'a>(entries: impl Iterator<Item = &'a Entry>, scanned: EntryList) -> (Vec<PathBuf>, EntryList) {
+    let known: HashMap<PathBuf, &Entry> = entries.map(|e| (e.original_file_path(), e)).collect();
+    let scanned_paths: HashSet<PathBuf> = scanned.iter().map(|e| e.original_file_path()).collect();
+    let mut removed: Vec<PathBuf> = known.keys().filter(|path| !scanned_paths.contains(*path)).cloned().collect();
+    removed.sort();
+    let updated: EntryList = scanned.into_iter()
+        .filter(|e| match known.get(&e.original_file_path()) {
+            Some(known) => known.modified_time != e.modified_time || known.file_size != e.file_size,
+            None => true,
+        })
+        .collect();
+    (removed, updated)
+}
+
+pub fn read_entries(reading_list_opt: Option<PathBuf>, file_name_opt: Option<PathBuf>, path: PathBuf, pattern_opt: Option<String>, sample: bool, directories: &Directories) -> Result<EntryList> {  
+    let (list, problems) = scan_entries(reading_list_opt, file_name_opt, path, pattern_opt, sample, directories)?;
+    report_problems(&problems);
+    Ok(list)
 }
 
 pub fn move_entries_with_label(entry_list: &EntryList, label: &str, target: &str) -> Result<()> {
     let entries = entries_with_label(entry_list, &label);
-    check_path(target, false)
+    check_path(Path::new(target), false)
+        .map_err(GalleryError::from)
         .and_then(|path| {
             if entries.len() > 0 {
-                entries.iter().for_each( |entry| {
-                    copy_entry(entry, &path).unwrap();
+                for entry in entries {
+                    copy_entry(entry, &path)?;
                     delete_entry(entry)
-                });
+                };
                 Ok(())
             } else {
-                println!("no entries found with this label: {}", label);
+                eprintln!("no entries found with this label: {}", label);
                 Ok(())
             }
         })
 }
 
 pub fn move_entries_with_label_to_target(entry_list: &EntryList, target: &str) -> Result<()> {
-    let mut result = Ok(());
-    entry_list.into_iter().filter(|&entry| entry.image_data.label().is_some()).for_each( |entry| {
-        if result.is_ok() {
-            let label = entry.image_data.label().unwrap();
-            let check = match check_label_path(target, &label) {
-                Ok(path) => {
-                    let _ = copy_entry(&entry, &path).unwrap();
-                    let _ = delete_entry(&entry);
-                    Ok(())
-                },
-                Err(err) => {
-                    Err(err)
-                },
-            };
-            if check.is_err() {
-                result = check;
-            }
+    for entry in entry_list {
+        if let Some(label) = entry.image_data.label() {
+            let path = check_label_path(Path::new(target), &label)?;
+            copy_entry(&entry, &path)?;
+            delete_entry(&entry)
         }
-    });
-    result
+    };
+    Ok(())
 }
 
 fn copy_file_to_target_directory(file_path: &Path, target_directory: &Path) -> Result<u64> {
-    let file_name = file_path.file_name().unwrap();
+    let file_name = file_path.file_name()
+        .ok_or(GalleryError::picture(file_path, "no file name"))?;
     let target_file_path = target_directory.join(file_name);
-    println!("copy {} to {}", file_path.display(), target_file_path.display());
-    std::fs::copy(file_path, target_file_path)
+    eprintln!("copy {} to {}", file_path.display(), target_file_path.display());
+    Ok(std::fs::copy(file_path, target_file_path)?)
 }
 
-pub fn copy_entry_filename_to_current_dir(entry: &Entry) {
+pub fn copy_entry_filename_to_current_dir(entry: &Entry, temporary: &Path) {
     let s = entry.original_file_path();
     let file_path = Path::new(&s);
-    let _ = copy_file_to_target_directory(file_path, &temporary_path());
+    let _ = copy_file_to_target_directory(file_path, temporary);
 }
 
 pub fn copy_entry(entry: &Entry, target_path: &Path) -> Result<()> {
@@ -414,7 +496,9 @@ pub fn copy_entry(entry: &Entry, target_path: &Path) -> Result<()> {
     let thumbnail_path = Path::new(&thumbnail_name);
     let image_data_path = Path::new(&image_data_name);
     copy_file_to_target_directory(file_path, target_path)?;
-    copy_file_to_target_directory(thumbnail_path, target_path)?;
+    let thumbnail_target_path = thumbnail_directory(target_path, entry.thumbnails.as_deref());
+    create_dir_all(&thumbnail_target_path)?;
+    copy_file_to_target_directory(thumbnail_path, &thumbnail_target_path)?;
     copy_file_to_target_directory(image_data_path, target_path)?;
     Ok(())
 }
@@ -440,10 +524,13 @@ pub fn delete_entry(entry: &Entry) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::entry::example_entries;
+    use crate::paths::test_directory;
+    use std::time::SystemTime;
     
     #[test]
     fn can_read_entries_from_a_directory_without_reading_the_thumbnails() {
-        let entries = entries_from_directory("./testdata", None).unwrap();
+        let entries = entries_from_directory(Path::new("./testdata"), None, &Directories::default(), &mut vec![]).unwrap();
         assert_eq!(7, entries.len());
         let index = entries.iter().position(|e| e.original_file_name() == "UN_Fight_for_Freedom_Leslie_Ragan_1943_poster_-_restoration1.jpeg").unwrap();
         assert_eq!(56984, entries[index].image_data.colors);
@@ -452,13 +539,13 @@ mod tests {
 
     #[test]
     fn can_read_entries_from_a_directory_with_pattern() {
-        let entries = entries_from_directory("./testdata", Some(String::from("1.*4"))).unwrap();
+        let entries = entries_from_directory(Path::new("./testdata"), Some(String::from("1.*4")), &Directories::default(), &mut vec![]).unwrap();
         assert_eq!(3, entries.len());
     }
 
     #[test]
     fn can_read_entries_from_reading_list() {
-        let entries = entries_from_reading_list("./testdata/reading_list", None).unwrap();
+        let entries = entries_from_reading_list(Path::new("./testdata/reading_list"), None, &Directories::default(), &mut vec![]).unwrap();
         assert_eq!(4, entries.len());
         assert_eq!("020_African_blue_flycatcher_at_Kibale_forest_National_Park_Photo_by_Giles_Laurent.jpeg", entries[0].original_file_name());
         assert_eq!("Continental_I-1430_NASM.jpg", entries[1].original_file_name());
@@ -466,11 +553,75 @@ mod tests {
         assert_eq!("Johannes_Vermeer_-_Lady_at_the_Virginal_with_a_Gentleman,_'The_Music_Lesson'_-_Google_Art_Project.jpg", entries[3].original_file_name());
     }
 
+    #[test]
+    fn can_read_entries_from_an_extended_reading_list_with_paths_relative_to_the_list() {
+        let entries = entries_from_reading_list(Path::new("./testdata/reading_list.m3u"), None, &Directories::default(), &mut vec![]).unwrap();
+        assert_eq!(2, entries.len());
+        assert_eq!("Continental_I-1430_NASM.jpg", entries[0].original_file_name());
+        assert_eq!(Rank::TwoStars, entries[0].image_data.rank);
+        assert_eq!("engines", entries[0].image_data.label);
+        assert_eq!("DAN-13-Danzig-100_Mark_(1922).jpg", entries[1].original_file_name());
+    }
+
     #[test]
     fn can_read_entry_for_a_file() {
-        let entries = entries_from_file("./testdata/020_African_blue_flycatcher_at_Kibale_forest_National_Park_Photo_by_Giles_Laurent.jpeg").unwrap();
+        let entries = entries_from_file(Path::new("./testdata/020_African_blue_flycatcher_at_Kibale_forest_National_Park_Photo_by_Giles_Laurent.jpeg"), &Directories::default(), &mut vec![]).unwrap();
         assert_eq!(1, entries.len());
         assert_eq!("020_African_blue_flycatcher_at_Kibale_forest_National_Park_Photo_by_Giles_Laurent.jpeg", entries[0].original_file_name());
     }
-}
 
+    #[test]
+    fn a_corrupt_picture_is_left_out_as_a_problem() {
+        let directory = test_directory("corrupt");
+        fs::write(directory.join("broken.jpeg"), b"not a picture").unwrap();
+        let mut problems: Vec<GalleryError> = Vec::new();
+        let entries = entries_from_directory(&directory, None, &Directories::default(), &mut problems).unwrap();
+        let _ = fs::remove_dir_all(&directory);
+        assert!(entries.is_empty());
+        assert_eq!(1, problems.len());
+        assert!(matches!(problems[0], GalleryError::Picture(_, _)));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn can_read_entries_with_names_that_are_not_utf8() {
+        use std::os::unix::ffi::OsStrExt;
+        let directory = test_directory("names_not_utf8");
+        let path = directory.join(OsStr::from_bytes(b"caf\xe9.png"));
+        let picture = ::image::RgbImage::from_fn(16, 16, |x, y| ::image::Rgb([(x * 16) as u8, (y * 16) as u8, 128]));
+        picture.save_with_format(&path, ::image::ImageFormat::Png).unwrap();
+        let entries = entries_from_directory(&directory, None, &Directories::default(), &mut vec![]).unwrap();
+        let _ = fs::remove_dir_all(&directory);
+        assert_eq!(1, entries.len());
+        assert_eq!(path, entries[0].original_file_path());
+    }
+
+    #[test]
+    fn invalid_labels_in_reading_list_hints_are_problems() {
+        let directory = test_directory("invalid_label_hint");
+        let picture = ::image::RgbImage::from_fn(16, 16, |x, y| ::image::Rgb([(x * 16) as u8, (y * 16) as u8, 128]));
+        picture.save_with_format(directory.join("foo.png"), ::image::ImageFormat::Png).unwrap();
+        let list = directory.join("list.m3u");
+        fs::write(&list, "#EXTGALLSH:rank=2,label=Bad Label\nfoo.png\n").unwrap();
+        let mut problems: Vec<GalleryError> = Vec::new();
+        let entries = entries_from_reading_list(&list, None, &Directories::default(), &mut problems).unwrap();
+        let _ = fs::remove_dir_all(&directory);
+        assert_eq!(1, entries.len());
+        assert_eq!(Rank::TwoStars, entries[0].image_data.rank);
+        assert_eq!(None, entries[0].image_data.label());
+        assert_eq!(1, problems.len());
+        assert!(matches!(problems[0], GalleryError::Hint(_, _)));
+    }
+
+    #[test]
+    fn reloading_finds_the_pictures_removed_added_or_changed() {
+        let entries = example_entries();
+        let mut scanned = entries[1..].to_vec();
+        scanned[0].file_size = 2000;
+        scanned.push(make_entry(String::from("photos/new.jpeg"), 100, 5, SystemTime::now(), Rank::NoStar));
+        let (removed, updated) = entry_changes(entries.iter(), scanned);
+        assert_eq!(vec![PathBuf::from("photos/foo.jpeg")], removed);
+        let updated: Vec<String> = updated.iter().map(|e| e.original_file_name()).collect();
+        assert_eq!(vec![String::from("bar.jpeg"), String::from("new.jpeg")], updated);
+    }
+}
@@ -17,6 +17,20 @@ impl Rank {
         }
     }
 
+    pub fn stars(&self) -> usize {
+        3 - *self as usize
+    }
+
+    pub fn from_stars(stars: usize) -> Option<Self> {
+        match stars {
+            0 => Some(Rank::NoStar),
+            1 => Some(Rank::OneStar),
+            2 => Some(Rank::TwoStars),
+            3 => Some(Rank::ThreeStars),
+            _ => None,
+        }
+    }
+
 }
 
 impl fmt::Display for Rank {
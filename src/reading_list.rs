@@ -0,0 +1,144 @@
+use crate::entry::Entry;
+use crate::paths::{os_string_from_bytes, write_path_line};
+use crate::rank::Rank;
+use std::fs::File;
+use std::io::{Result, Write};
+use std::path::{Path, PathBuf};
+
+// reading list format:
+//
+//  #EXTM3U                            optional header
+//  # any other line starting with #   comment
+//  #EXTGALLSH:rank=3,label=foo        hints for the next picture
+//  pictures/foo.jpeg                  path, relative to the list file
+//
+pub const HEADER: &str = "#EXTM3U";
+pub const HINT_PREFIX: &str = "#EXTGALLSH:";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReadingItem {
+    pub path: PathBuf,
+    pub rank: Option<Rank>,
+    pub label: Option<String>,
+}
+
+fn parse_hints(hints: &str) -> (Option<Rank>, Option<String>) {
+    let mut rank = None;
+    let mut label = None;
+    for hint in hints.split(',') {
+        match hint.trim().split_once('=') {
+            Some(("rank", value)) => rank = value.trim().parse::<usize>().ok().and_then(Rank::from_stars),
+            Some(("label", value)) if !value.trim().is_empty() => label = Some(value.trim().to_string()),
+            _ => eprintln!("ignoring reading list hint: {}", hint),
+        }
+    };
+    (rank, label)
+}
+
+// a relative path is relative to the list file directory; former lists had paths relative to the current directory, so these still work
+fn resolve_path(path: PathBuf, base_dir: &Path) -> PathBuf {
+    if path.is_absolute() {
+        path
+    } else {
+        let relative_to_list = base_dir.join(&path);
+        if relative_to_list.exists() || !path.exists() {
+            relative_to_list
+        } else {
+            path
+        }
+    }
+}
+
+// the list is read as bytes, so that paths come back whatever their encoding
+pub fn parse_reading_list(content: &[u8], base_dir: &Path) -> Vec<ReadingItem> {
+    let mut items: Vec<ReadingItem> = Vec::new();
+    let mut hints: (Option<Rank>, Option<String>) = (None, None);
+    for line in content.split(|byte| *byte == b'\n').map(|l| l.trim_ascii()).filter(|l| !l.is_empty()) {
+        if let Some(rest) = line.strip_prefix(HINT_PREFIX.as_bytes()) {
+            hints = parse_hints(&String::from_utf8_lossy(rest))
+        } else if !line.starts_with(b"#") {
+            let (rank, label) = std::mem::take(&mut hints);
+            items.push(ReadingItem {
+                path: resolve_path(PathBuf::from(os_string_from_bytes(line)), base_dir),
+                rank: rank,
+                label: label,
+            })
+        }
+    };
+    items
+}
+
+pub fn hint_line(entry: &Entry) -> Option<String> {
+    let mut hints: Vec<String> = Vec::new();
+    if entry.image_data.rank != Rank::NoStar {
+        hints.push(format!("rank={}", entry.image_data.rank.stars()))
+    };
+    if let Some(label) = entry.image_data.label() {
+        hints.push(format!("label={}", label))
+    };
+    if hints.is_empty() {
+        None
+    } else {
+        Some(format!("{}{}", HINT_PREFIX, hints.join(",")))
+    }
+}
+
+pub fn write_reading_list(file_name: &Path, entries: &[&Entry]) -> Result<()> {
+    let mut file = File::create(file_name)?;
+    writeln!(file, "{}", HEADER)?;
+    for entry in entries {
+        if let Some(line) = hint_line(entry) {
+            writeln!(file, "{}", line)?;
+        };
+        let original = entry.original_file_path();
+        let absolute = original.canonicalize().unwrap_or(original);
+        write_path_line(&mut file, &absolute)?;
+    };
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::paths::test_directory;
+
+    #[test]
+    fn comments_and_blank_lines_are_ignored() {
+        let items = parse_reading_list(b"#EXTM3U\n# my list\n\nfoo.jpeg\n", Path::new("photos"));
+        assert_eq!(vec![ReadingItem { path: PathBuf::from("photos/foo.jpeg"), rank: None, label: None }], items);
+    }
+
+    #[test]
+    fn hints_apply_to_the_next_path_only() {
+        let items = parse_reading_list(b"#EXTGALLSH:rank=2,label=keep\nfoo.jpeg\nbar.jpeg\n", Path::new("photos"));
+        assert_eq!(Some(Rank::TwoStars), items[0].rank);
+        assert_eq!(Some(String::from("keep")), items[0].label);
+        assert_eq!(None, items[1].rank);
+        assert_eq!(None, items[1].label);
+    }
+
+    #[test]
+    fn absolute_paths_are_kept() {
+        let items = parse_reading_list(b"/pictures/foo.jpeg\n", Path::new("photos"));
+        assert_eq!(PathBuf::from("/pictures/foo.jpeg"), items[0].path);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn paths_that_are_not_utf8_are_read_back_as_written() {
+        use crate::entry::make_entry;
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+        use std::time::SystemTime;
+        let path = Path::new(OsStr::from_bytes(b"/pictures/caf\xe9.jpeg"));
+        let mut entry = make_entry(path, 100, 5, SystemTime::now(), Rank::TwoStars);
+        entry.set_label(&String::from("keep"));
+        let directory = test_directory("reading_list_not_utf8");
+        let file_name = directory.join("list.m3u");
+        write_reading_list(&file_name, &[&entry]).unwrap();
+        let content = std::fs::read(&file_name).unwrap();
+        let _ = std::fs::remove_dir_all(&directory);
+        let items = parse_reading_list(&content, Path::new("photos"));
+        assert_eq!(vec![ReadingItem { path: path.to_path_buf(), rank: Some(Rank::TwoStars), label: Some(String::from("keep")) }], items);
+    }
+}
@@ -2,25 +2,43 @@ use crate::Args;
 use crate::Direction;
 use crate::Entry;
 use crate::Order;
-use crate::ensure_thumbnails;
+use crate::command_line::{CommandHistory, LineCommand, complete_line, parse_line_command};
+use crate::compare::{CompareList, next_position};
+use crate::contact_sheet::write_contact_sheets;
 use crate::entry::{EntryList};
+use crate::error::{GalleryError, Result, report_problems};
+use crate::grid_size::{GridSize, MAX_GRID_SIDE};
 use crate::navigator::{Coords, Navigator};
-use crate::picture_io::{copy_entry, copy_entry_filename_to_current_dir, delete_entry, delete_selection_file, move_entries_with_label, move_entries_with_label_to_target, save_image_list};
+use crate::metadata::is_valid_label;
+use crate::notices::Notices;
+use crate::picture_io::{copy_entry, copy_entry_filename_to_current_dir, delete_entry, delete_selection_file, move_entries_with_label_to_target, read_selection_sets, save_image_list, save_selection_sets};
 use crate::picture_io;
+use crate::paths::{is_valid_directory, Directories};
 use crate::rank::Rank;
-use crate::read_entries;
+use crate::picture_io::{entry_changes, scan_entries};
+use crate::reading_list::write_reading_list;
+use crate::statistics::Statistics;
+use crate::watcher::FileChange;
+use crate::weighting::Weighting;
+use crate::zoom::Zoom;
+use crate::selection::{DEFAULT_SET, Member, SelectionSets, SetOperation, selection_file_name};
+use crate::slideshow::{DEFAULT_INTERVAL, Slideshow, Tick};
+use chrono::Local;
 use rand::prelude::SliceRandom;
 use rand::thread_rng;
-use std::cmp::Ordering::Equal;
-use std::cmp::min;
-use std::io::{Result,Error, ErrorKind};
-use std::path::Path;
+use regex::Regex;
+use std::cmp::Ordering::{self, Equal};
+use std::cmp::{max, min};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
 
 pub struct Repository {
     entry_list: EntryList,
     navigator: Navigator,
     select_start: Option<usize>,
     order: Option<Order>,
+    // the sorted order was reversed
+    reversed: bool,
     register: Option<usize>,
     real_size_on: bool,
     palette_extract_on: bool,
@@ -33,70 +51,117 @@ pub struct Repository {
     grid_limit_on: bool,
     search_edit_mode_on: bool,
     field: String,
+    gallery_path: PathBuf,
+    reading_list: Option<PathBuf>,
+    file_name: Option<PathBuf>,
+    pattern: Option<String>,
+    selection_sets: SelectionSets,
+    active_set: String,
+    previous_set: Option<String>,
+    selection_edit_mode_on: bool,
+    copy_selection_set: String,
+    move_selection_set: String,
+    slideshow: Option<Slideshow>,
+    weighting: Weighting,
+    weighted_on: bool,
+    zoom: Zoom,
+    keep_zoom_on: bool,
+    command_edit_mode_on: bool,
+    command_history: CommandHistory,
+    hidden: EntryList,
+    // the place of every picture while some are hidden, so that they're shown again in order
+    positions: HashMap<PathBuf, usize>,
+    // the pattern the shown pictures match, which new pictures must match too to be shown
+    filter: Option<Regex>,
+    compare_list: CompareList,
+    pub notices: Notices,
+    pub directories: Directories,
 }
 
 pub fn init_repository(args: &Args) -> Result<Repository> {
     let all_label_move_target = match args.all_label_move_target() {
         Ok(target) => target,
-        Err(err) => return Err(Error::new(ErrorKind::Other, err)),
+        Err(err) => return Err(GalleryError::Argument(err)),
     };
     let copy_selection_target = match args.copy_selection_target() {
         Ok(target) => target,
-        Err(err) => return Err(Error::new(ErrorKind::Other, err)),
+        Err(err) => return Err(GalleryError::Argument(err)),
     };
     let move_selection_target = match args.move_selection_target() {
         Ok(target) => target,
-        Err(err) => return Err(Error::new(ErrorKind::Other, err)),
+        Err(err) => return Err(GalleryError::Argument(err)),
     };
-    let entry_list = match read_entries(args.reading.clone(), args.file.clone(), args.path(), args.pattern.clone(), args.sample()) {
-        Ok(list) => list,
-        Err(err) => return Err(Error::new(ErrorKind::Other, err)),
-    };
-    if args.update_image_data {
-        ensure_thumbnails(&entry_list);
-        let nb_entries = &entry_list.len();
-        let total_size: u64 = entry_list.iter().map(|e| e.file_size).sum();
-        println!("{} entries, {} bytes", nb_entries, total_size);
-        return Err(Error::new(ErrorKind::Other, "updating thumbnails done"))
+    let directories = args.directories();
+    let (entry_list, problems) = scan_entries(args.reading.clone(), args.file.clone(), args.path(), args.pattern.clone(), args.sample(), &directories)?;
+    let mut repository = Repository::from_entries(entry_list, args.grid_size(), copy_selection_target.clone(), move_selection_target.clone(), all_label_move_target.clone(), args.sample());
+    // the files left out at start are told in the window too, as when reloading
+    if !problems.is_empty() {
+        repository.notices.error(format!("{} files left out", problems.len()));
+        report_problems(&problems)
     };
-
-    if let Some(parameters) = &args.move_label {
-        let label = parameters[0].clone();
-        let target = parameters[1].clone();
-        match move_entries_with_label(&entry_list, &label, &target) {
-            Ok(()) => return Err(Error::new(ErrorKind::Other, "move entries done")),
-            Err(err) => return Err(Error::new(ErrorKind::Other, err)),
+    repository.directories = directories;
+    repository.load_selection_sets(&args.path());
+    repository.reading_list = args.reading.clone();
+    repository.file_name = args.file.clone();
+    repository.pattern = args.pattern.clone();
+    repository.copy_selection_set = args.copy_selection_set();
+    repository.move_selection_set = args.move_selection_set();
+    for name in [&repository.copy_selection_set, &repository.move_selection_set] {
+        if !repository.selection_sets.names().contains(name) {
+            return Err(GalleryError::Argument(format!("selection set {} doesn't exist", name)))
         }
-    }
-
-    let mut repository = Repository::from_entries(entry_list, args.grid_size(), copy_selection_target.clone(), move_selection_target.clone(), all_label_move_target.clone(), args.sample());
-
+    };
 
+    if let Some(weights) = args.weights {
+        repository.weighting = Weighting::new(weights)
+    };
+    repository.weighted_on = args.weighted;
     repository.sort_by(args.order());
     repository.slice(args.from, args.to);
 
-    println!("{} entries", repository.capacity());
+    eprintln!("{} entries", repository.capacity());
 
     if let Some(index) = args.index {
         if repository.can_move_to_index(index) {
             repository.move_to_index(index)
         } else {
-            return Err(Error::new(ErrorKind::Other, "entry index out of range"))
+            return Err(GalleryError::Argument(String::from("entry index out of range")))
         }
     };
 
-    if args.extraction {
+    if args.extraction() {
         repository.toggle_palette_extract();
     };
+
+    if let Some(interval) = args.timer {
+        repository.slideshow = Some(Slideshow::new(interval, !args.stop_at_end, &args.dwell))
+    };
     Ok(repository)
 }
+
+// how two entries compare in a sorted order; random order doesn't compare them
+fn compare_entries(order: Order, a: &Entry, b: &Entry) -> Ordering {
+    let by_path = || a.original_file_path().cmp(&b.original_file_path());
+    match order {
+        Order::Label => a.image_data.cmp_label(&b.image_data).then_with(by_path),
+        Order::Colors => a.image_data.colors.cmp(&b.image_data.colors).then_with(by_path),
+        Order::Palette => a.image_data.palette.cmp(&b.image_data.palette),
+        Order::Date => a.modified_time.cmp(&b.modified_time),
+        Order::Name => by_path(),
+        Order::Size => a.file_size.cmp(&b.file_size),
+        Order::Value => (a.image_data.rank as usize).cmp(&(b.image_data.rank as usize)).then_with(by_path),
+        Order::Random => Equal,
+    }
+}
+
 impl Repository {
-    pub fn from_entries(entries: EntryList, cells_per_row: usize, copy_selection_target: Option<String>, move_selection_target: Option<String>, all_label_move_target: Option<String>, sample: bool) -> Self {
+    pub fn from_entries(entries: EntryList, (cells_per_row, rows): (usize, usize), copy_selection_target: Option<String>, move_selection_target: Option<String>, all_label_move_target: Option<String>, sample: bool) -> Self {
         Repository{
             entry_list: entries.clone(),
-            navigator: Navigator::new(entries.len() as i32, cells_per_row as i32),
+            navigator: Navigator::with_rows(entries.len() as i32, cells_per_row as i32, rows as i32),
             select_start: None,
             order: Some(Order::Random),
+            reversed: false,
             register: None,
             real_size_on: false,
             palette_extract_on: false,
@@ -109,11 +174,48 @@ impl Repository {
             grid_limit_on: true,
             search_edit_mode_on: false,
             field: String::from(""),
+            gallery_path: PathBuf::from("."),
+            reading_list: None,
+            file_name: None,
+            pattern: None,
+            selection_sets: SelectionSets::default(),
+            active_set: String::from(DEFAULT_SET),
+            previous_set: None,
+            selection_edit_mode_on: false,
+            copy_selection_set: String::from(DEFAULT_SET),
+            move_selection_set: String::from(DEFAULT_SET),
+            slideshow: None,
+            weighting: Weighting::default(),
+            weighted_on: false,
+            zoom: Zoom::Fit,
+            keep_zoom_on: false,
+            command_edit_mode_on: false,
+            command_history: CommandHistory::default(),
+            hidden: EntryList::new(),
+            positions: HashMap::new(),
+            filter: None,
+            compare_list: CompareList::default(),
+            notices: Notices::default(),
+            directories: Directories::default(),
         }
     }
 
+    pub fn load_selection_sets(&mut self, gallery_path: &Path) {
+        self.gallery_path = gallery_path.to_path_buf();
+        self.selection_sets = read_selection_sets(gallery_path);
+    }
+
+    pub fn statistics(&self) -> Statistics {
+        Statistics::from_entries(&self.entry_list, &self.gallery_path)
+    }
+
+    pub fn entry_list(&self) -> &EntryList {
+        &self.entry_list
+    }
+
     pub fn add_edit_char(&mut self, ch: char) {
-        if self.field.len() < 16 {
+        let max_length = if self.command_edit_mode_on { 256 } else { 16 };
+        if self.field.len() < max_length {
             self.field.push(ch);
         }
     }
@@ -143,6 +245,353 @@ impl Repository {
     }
 
 
+    pub fn selection_edit_mode_on(&self) -> bool {
+        self.selection_edit_mode_on
+    }
+
+    pub fn begin_selection_edit(&mut self) {
+        self.field = String::from("");
+        self.selection_edit_mode_on = true;
+    }
+
+    pub fn cancel_selection_edit(&mut self) {
+        self.selection_edit_mode_on = false;
+    }
+
+    pub fn confirm_selection_edit(&mut self) {
+        self.selection_edit_mode_on = false;
+        if !self.field.is_empty() {
+            let name = self.field.clone();
+            self.switch_selection_set(&name)
+        }
+    }
+
+    pub fn command_edit_mode_on(&self) -> bool {
+        self.command_edit_mode_on
+    }
+
+    pub fn begin_command_edit(&mut self) {
+        self.field = String::from("");
+        self.command_history.reset();
+        self.command_edit_mode_on = true;
+    }
+
+    pub fn cancel_command_edit(&mut self) {
+        self.command_edit_mode_on = false;
+    }
+
+    pub fn confirm_command_edit(&mut self) {
+        self.command_edit_mode_on = false;
+        let line = self.field.clone();
+        self.command_history.push(&line);
+        match parse_line_command(&line) {
+            Ok(command) => self.run_line_command(command),
+            Err(err) => self.notices.error(format!(":{}: {}", line, err)),
+        }
+    }
+
+    pub fn complete_command_edit(&mut self) {
+        let (line, candidates) = complete_line(&self.field);
+        self.field = line;
+        if !candidates.is_empty() {
+            self.notices.info(candidates.join(" "))
+        }
+    }
+
+    pub fn previous_command(&mut self) {
+        if let Some(line) = self.command_history.previous() {
+            self.field = line.to_string()
+        }
+    }
+
+    pub fn next_command(&mut self) {
+        self.field = self.command_history.next().unwrap_or("").to_string()
+    }
+
+    pub fn run_line_command(&mut self, command: LineCommand) {
+        match command {
+            LineCommand::Goto(index) => {
+                if self.can_move_to_index(index) {
+                    self.move_to_index(index)
+                } else {
+                    self.notices.error(format!("no picture #{}", index))
+                }
+            },
+            LineCommand::Rank(rank) => self.point_rank(rank),
+            LineCommand::Label(label) => {
+                if is_valid_label(&label) {
+                    self.field = label;
+                    self.point_label()
+                } else {
+                    self.notices.error(format!("invalid label: {}", label))
+                }
+            },
+            LineCommand::Unlabel => self.point_remove_label(),
+            LineCommand::Filter(pattern) => self.filter_entries(&pattern),
+            LineCommand::Range(from, to) => self.keep_range(from, to),
+            LineCommand::All => self.show_all_entries(),
+            LineCommand::Sort(order, descending) => {
+                self.sort_by(order);
+                if descending {
+                    self.reverse_order()
+                }
+            },
+            LineCommand::CopySelection(target, set) => {
+                if let Some((target, set)) = self.selection_target(target, set) {
+                    self.notices.info(format!("selection {} will be copied to {} when quitting with Q", set, target));
+                    self.copy_selection_target = Some(target);
+                    self.copy_selection_set = set
+                }
+            },
+            LineCommand::MoveSelection(target, set) => {
+                if let Some((target, set)) = self.selection_target(target, set) {
+                    self.notices.info(format!("selection {} will be moved to {} when quitting with Q", set, target));
+                    self.move_selection_target = Some(target);
+                    self.move_selection_set = set
+                }
+            },
+            LineCommand::Info => self.print_info(),
+            LineCommand::Reload => self.reload(),
+        }
+    }
+
+    fn selection_target(&self, target: String, set: Option<String>) -> Option<(String, String)> {
+        let set = set.unwrap_or(String::from(DEFAULT_SET));
+        if !is_valid_directory(Path::new(&target)) {
+            self.notices.error(format!("directory doesn't exist: {}", target));
+            None
+        } else if !self.selection_sets.names().contains(&set) {
+            self.notices.error(format!("selection set {} doesn't exist", set));
+            None
+        } else {
+            Some((target, set))
+        }
+    }
+
+    pub fn print_info(&self) {
+        if let Some(entry) = self.current_entry() {
+            println!("{}", self.title_display());
+            println!("{}", entry.original_file_path().display());
+            println!("{:?}", entry.image_data);
+        }
+    }
+
+    // shows some entries, hiding the others, and stays on the current picture if it's still shown
+    fn show_entries(&mut self, shown: EntryList, hidden: EntryList) {
+        let name = self.current_entry().map(|e| e.original_file_path());
+        if hidden.is_empty() {
+            self.positions.clear()
+        } else if self.hidden.is_empty() {
+            self.positions = self.entry_list.iter().enumerate().map(|(i, e)| (e.original_file_path(), i)).collect()
+        };
+        self.entry_list = shown;
+        self.hidden = hidden;
+        self.navigator = Navigator::with_rows(self.entry_list.len() as i32, self.navigator.cells_per_row(), self.navigator.rows());
+        self.select_start = None;
+        if let Some(name) = name {
+            self.jump_to_name(&name)
+        };
+        self.notices.info(format!("{} pictures shown, {} hidden", self.entry_list.len(), self.hidden.len()))
+    }
+
+    // the pattern is matched against the path of every picture, hidden or not
+    pub fn filter_entries(&mut self, pattern: &str) {
+        let reg_exp = match Regex::new(pattern) {
+            Ok(reg_exp) => reg_exp,
+            Err(err) => {
+                self.notices.error(format!("can't parse regular expression {}: {}", pattern, err));
+                return
+            },
+        };
+        let (shown, hidden): (EntryList, EntryList) = self.all_entries().into_iter().partition(|e| reg_exp.is_match(&e.path_text()));
+        if shown.is_empty() {
+            self.notices.error(format!("no picture matches {}", pattern));
+            return
+        };
+        self.show_entries(shown, hidden);
+        self.filter = Some(reg_exp)
+    }
+
+    pub fn keep_range(&mut self, from: usize, to: usize) {
+        if from >= self.entry_list.len() {
+            self.notices.error(format!("no picture #{}", from));
+            return
+        };
+        let to = min(to, self.entry_list.len() - 1);
+        self.filter = None;
+        let mut hidden = self.hidden.clone();
+        hidden.extend(self.entry_list[..from].iter().cloned());
+        hidden.extend(self.entry_list[to+1..].iter().cloned());
+        self.show_entries(self.entry_list[from..to+1].to_vec(), hidden)
+    }
+
+    pub fn show_all_entries(&mut self) {
+        if self.hidden.is_empty() {
+            self.notices.info(format!("all pictures are shown"));
+            return
+        };
+        self.filter = None;
+        self.show_entries(self.all_entries(), EntryList::new())
+    }
+
+    // the shown and hidden pictures, in the order they have when none is hidden
+    fn all_entries(&self) -> EntryList {
+        let mut all = self.entry_list.clone();
+        all.extend(self.hidden.iter().cloned());
+        all.sort_by_cached_key(|e| self.positions.get(&e.original_file_path()).copied().unwrap_or(usize::MAX));
+        all
+    }
+
+    // sorting applies to all the pictures, the hidden ones keep their new place for when they're shown again
+    fn set_all_entries_order(&mut self, all: EntryList) {
+        if self.hidden.is_empty() {
+            self.entry_list = all
+        } else {
+            self.positions = all.iter().enumerate().map(|(i, e)| (e.original_file_path(), i)).collect();
+            let positions = &self.positions;
+            self.entry_list.sort_by_cached_key(|e| positions.get(&e.original_file_path()).copied().unwrap_or(usize::MAX));
+            self.hidden.sort_by_cached_key(|e| positions.get(&e.original_file_path()).copied().unwrap_or(usize::MAX))
+        }
+    }
+
+    // pictures added, removed or changed in the directory
+    pub fn apply_file_changes(&mut self, changes: &[(PathBuf, FileChange)]) {
+        let mut removed: Vec<PathBuf> = Vec::new();
+        let mut updated = EntryList::new();
+        for (path, change) in changes {
+            match change {
+                FileChange::Removed => removed.push(path.clone()),
+                FileChange::Added | FileChange::Modified => match picture_io::entry_from_path(path, &self.directories) {
+                    Ok(entry) => updated.push(entry),
+                    Err(err) => self.notices.error(format!("{}", err)),
+                },
+            }
+        };
+        self.update_entries(&removed, updated)
+    }
+
+    // reads the pictures again, from the directory or the lists they were read from at start
+    pub fn reload(&mut self) {
+        if self.sample {
+            self.notices.error(format!("can't reload a sample"));
+            return
+        };
+        let scanned = match scan_entries(self.reading_list.clone(), self.file_name.clone(), self.gallery_path.clone(), self.pattern.clone(), false, &self.directories) {
+            Ok((entries, problems)) => {
+                if !problems.is_empty() {
+                    self.notices.error(format!("{} files left out", problems.len()));
+                    report_problems(&problems)
+                };
+                entries
+            },
+            Err(err) => {
+                self.notices.error(format!("can't reload: {}", err));
+                return
+            },
+        };
+        let (removed, updated) = entry_changes(self.entry_list.iter().chain(self.hidden.iter()), scanned);
+        if removed.is_empty() && updated.is_empty() {
+            self.notices.info(format!("no change found"));
+            return
+        };
+        self.update_entries(&removed, updated)
+    }
+
+    // drops the entries removed, replaces the ones updated and adds the new ones at the end (or at
+    // their place in the current order); the current picture stays current if it's still there,
+    // otherwise the one now at its place is
+    fn update_entries(&mut self, removed: &[PathBuf], updated: EntryList) {
+        let name = self.current_entry().map(|e| e.original_file_path());
+        let index = self.navigator.index();
+        let count = self.entry_list.len() + self.hidden.len();
+        // a directory removed takes the pictures under it
+        let is_removed = |e: &Entry| { let path = e.original_file_path(); removed.iter().any(|removed| path.starts_with(removed)) };
+        self.entry_list.retain(|e| !is_removed(e));
+        self.hidden.retain(|e| !is_removed(e));
+        let removed_count = count - self.entry_list.len() - self.hidden.len();
+        let mut modified = 0;
+        let mut added = EntryList::new();
+        for mut entry in updated {
+            let path = entry.original_file_path();
+            let existing = self.entry_list.iter_mut().chain(self.hidden.iter_mut())
+                .find(|e| e.original_file_path() == path);
+            match existing {
+                // only what comes from the file changes, the rank, label and selection stay as they are
+                Some(existing) => {
+                    existing.file_size = entry.file_size;
+                    existing.modified_time = entry.modified_time;
+                    existing.image_data.colors = entry.image_data.colors;
+                    existing.image_data.palette = entry.image_data.palette;
+                    if let Err(err) = picture_io::refresh_thumbnail(existing) {
+                        self.notices.error(format!("{}", err))
+                    };
+                    modified += 1
+                },
+                None => {
+                    if let Err(err) = picture_io::ensure_thumbnail(&entry) {
+                        self.notices.error(format!("{}", err))
+                    };
+                    if self.active_set != DEFAULT_SET {
+                        let selected = self.selection_sets.contains(&self.active_set, &self.member(&entry));
+                        entry.set_select(selected)
+                    };
+                    added.push(entry)
+                },
+            }
+        };
+        // new pictures find their place in the current order, the others keep theirs; while some
+        // pictures are hidden, the new ones are shown only if they match the filter
+        let added_count = added.len();
+        if added_count > 0 {
+            let mut all = self.all_entries();
+            for entry in added {
+                let position = self.insert_position(&all, &entry);
+                all.insert(position, entry.clone());
+                let shown = self.hidden.is_empty() || self.filter.as_ref().map_or(false, |filter| filter.is_match(&entry.path_text()));
+                if shown {
+                    self.entry_list.push(entry)
+                } else {
+                    self.hidden.push(entry)
+                }
+            };
+            self.set_all_entries_order(all)
+        };
+        if added_count + removed_count + modified == 0 {
+            return
+        };
+        self.update_max_selected();
+        self.navigator = Navigator::with_rows(self.entry_list.len() as i32, self.navigator.cells_per_row(), self.navigator.rows());
+        self.select_start = None;
+        match name.filter(|name| self.entry_list.iter().any(|e| &e.original_file_path() == name)) {
+            Some(name) => self.jump_to_name(&name),
+            None => if !self.entry_list.is_empty() {
+                self.navigator.move_to_index(min(index, self.entry_list.len() - 1))
+            },
+        };
+        self.notices.info(format!("{} pictures added, {} removed, {} changed", added_count, removed_count, modified))
+    }
+
+    // where a new picture goes in the current order, the end when it's random
+    fn insert_position(&self, entries: &EntryList, entry: &Entry) -> usize {
+        let after = if self.reversed { Ordering::Greater } else { Ordering::Less };
+        match self.order {
+            Some(order) if order != Order::Random => entries.iter()
+                .position(|e| compare_entries(order, entry, e) == after)
+                .unwrap_or(entries.len()),
+            _ => entries.len(),
+        }
+    }
+
+    pub fn reverse_order(&mut self) {
+        if let Some(name) = self.current_entry().map(|e| e.original_file_path()) {
+            let mut all = self.all_entries();
+            all.reverse();
+            self.set_all_entries_order(all);
+            self.reversed = !self.reversed;
+            self.jump_to_name(&name)
+        }
+    }
+
     pub fn label_edit_mode_on(&self) -> bool {
         self.label_edit_mode_on
     }
@@ -172,13 +621,13 @@ impl Repository {
     pub fn search(&mut self) {
         // let pattern = self.edit.iter().take_while(|&c| *c!='\0').collect::<String>();
         let pattern = self.field.clone();
-        println!("search {:?}", pattern);
+        self.notices.info(format!("search {:?}", pattern));
         if !pattern.is_empty() {
             if let Some(index) = self.entry_list.iter().position(|entry| entry.original_file_name().contains(&pattern)) {
-                println!("found {}", self.entry_list[index].original_file_name());
+                self.notices.info(format!("found {}", self.entry_list[index].original_file_name()));
                 self.move_to_index(index)
             } else {
-                println!("no picture found");
+                self.notices.info(format!("no picture found"));
             }
         }
 
@@ -193,7 +642,7 @@ impl Repository {
 
     pub fn toggle_grid_limit(&mut self) {
         self.grid_limit_on = !self.grid_limit_on;
-        println!("grid limit {}", if self.grid_limit_on { "on" } else { "off" })
+        self.notices.info(format!("grid limit {}", if self.grid_limit_on { "on" } else { "off" }))
     }
 
     pub fn apply_last_label(&mut self) {
@@ -216,6 +665,14 @@ impl Repository {
         self.navigator.cells_per_row()
     }
 
+    pub fn rows(&self) -> i32 {
+        self.navigator.rows()
+    }
+
+    pub fn max_cells(&self) -> i32 {
+        self.navigator.max_cells()
+    }
+
     pub fn index_from_position(&self, coords: Coords) -> Option<usize> {
         self.navigator.index_from_position(coords)
     }
@@ -231,11 +688,12 @@ impl Repository {
             return "".to_string()
         };
         let entry_title_display = &<Entry as Clone>::clone(&self.current_entry().unwrap()).title_display();
-        let result = format!("S:[{}] {} ordered by {} {}/{}  {} {} {} {} {}",
+        let result = format!("S:[{}{}] {} ordered by {} {}/{}  {} {} {}{} {} {} {} {} {}",
+            if self.active_set == DEFAULT_SET { String::from("") } else { format!("{} ", self.active_set) },
             self.max_selected,
             if self.select_start.is_some() { "…" } else { "" },
             if let Some(o) = self.order {
-                o.to_string()
+                format!("{}{}", o, if self.weighted_on { "≈" } else { "" })
             } else {
                 "??".to_string()
             },
@@ -244,8 +702,12 @@ impl Repository {
             entry_title_display,
             if self.register.is_none() { String::from("") } else { format!("{}", self.register.unwrap()) },
             if self.real_size_on { "*" } else { "" },
+            if self.zoom == Zoom::Fit { String::from("") } else { format!("{}{}", self.zoom, if self.keep_zoom_on { "!" } else { "" }) },
             if self.label_edit_mode_on { format!("Label:{}", self.field) } else { String::from("") },
-            if self.search_edit_mode_on { format!("Search:{}", self.field) } else { String::from("") }
+            if self.search_edit_mode_on { format!("Search:{}", self.field) } else { String::from("") },
+            if self.selection_edit_mode_on { format!("Selection:{}", self.field) } else { String::from("") },
+            if self.command_edit_mode_on { format!(":{}", self.field) } else { String::from("") },
+            if let Some(slideshow) = &self.slideshow { slideshow.to_string() } else { String::from("") }
             );
         result
     }
@@ -254,7 +716,32 @@ impl Repository {
         self.real_size_on
     }
 
-    fn jump_to_name(&mut self, name: &String) {
+    // moving to another picture leaves real size, and zoom unless it's kept
+    fn reset_view(&mut self) {
+        self.real_size_on = false;
+        if !self.keep_zoom_on {
+            self.zoom = Zoom::Fit
+        }
+    }
+
+    pub fn zoom(&self) -> Zoom {
+        self.zoom
+    }
+
+    pub fn set_zoom(&mut self, zoom: Zoom) {
+        self.zoom = zoom
+    }
+
+    pub fn cycle_fit(&mut self) {
+        self.zoom = self.zoom.next_fit()
+    }
+
+    pub fn toggle_keep_zoom(&mut self) {
+        self.keep_zoom_on = !self.keep_zoom_on;
+        self.notices.info(format!("keep zoom {}", if self.keep_zoom_on { "on" } else { "off" }))
+    }
+
+    fn jump_to_name(&mut self, name: &Path) {
         match self.entry_list.iter().position(|e| &e.original_file_path() == name) {
             Some(index) => { 
                 self.navigator.move_to_index(index);
@@ -288,11 +775,11 @@ impl Repository {
         if let Some(index) = self.register {
             self.navigator.move_to_index(index);
             self.register = None;
-            self.real_size_on = false;
-            println!("go to register index: {}", index)
+            self.reset_view();
+            self.notices.info(format!("go to register index: {}", index))
         } else {
             self.register = Some(0);
-            println!("start register index…")
+            self.notices.info(format!("start register index…"))
         }
     }
 
@@ -303,7 +790,7 @@ impl Repository {
                 if new_acc < self.navigator.capacity() { Some(new_acc) } else { Some(acc) }
             },
             None => {
-                println!("no register index");
+                self.notices.info(format!("no register index"));
                 None
             }
         }
@@ -312,7 +799,7 @@ impl Repository {
     pub fn delete_register_digit(&mut self) {
         self.register = self.register.map(|n| n / 10);
         if let Some(index) = self.register {
-            println!("register index: {}", index)
+            self.notices.info(format!("register index: {}", index))
         }
     }
 
@@ -321,43 +808,20 @@ impl Repository {
     }
 
     pub fn sort_by(&mut self, order: Order) {
-        println!("sort pictures by {}", order);
+        self.notices.info(format!("sort pictures by {}", order));
         if self.navigator.capacity() == 0 {
             return
         };
         let name = self.current_entry().unwrap().original_file_path();
+        let mut all = self.all_entries();
         match order {
-            Order::Label => self.entry_list.sort_by(|a, b| {
-                let cmp = a.image_data.cmp_label(&b.image_data);
-                if cmp == Equal {
-                    a.original_file_path().cmp(&b.original_file_path())
-                } else {
-                    cmp
-                }
-            }),
-            Order::Colors => self.entry_list.sort_by(|a, b| {
-                let cmp = (a.image_data.colors).cmp(&b.image_data.colors);
-                if cmp == Equal {
-                    a.original_file_path().cmp(&b.original_file_path())
-                } else {
-                    cmp
-                }
-            }),
-            Order::Palette => self.entry_list.sort_by(|a, b| { a.image_data.palette.cmp(&b.image_data.palette) }),
-            Order::Date => self.entry_list.sort_by(|a, b| { a.modified_time.cmp(&b.modified_time) }),
-            Order::Name => self.entry_list.sort_by(|a, b| { a.original_file_path().cmp(&b.original_file_path()) }),
-            Order::Size => self.entry_list.sort_by(|a, b| { a.file_size.cmp(&b.file_size) }),
-            Order::Value => self.entry_list.sort_by(|a,b| {
-                let cmp = (a.image_data.rank as usize).cmp(&(b.image_data.rank as usize));
-                if cmp == Equal {
-                    a.original_file_path().cmp(&b.original_file_path())
-                } else {
-                    cmp
-                }
-            }),
-            Order::Random => self.entry_list.shuffle(&mut thread_rng()),
+            Order::Random if self.weighted_on => self.weighting.shuffle(&mut all, &mut thread_rng()),
+            Order::Random => all.shuffle(&mut thread_rng()),
+            _ => all.sort_by(|a, b| compare_entries(order, a, b)),
         };
+        self.set_all_entries_order(all);
         self.order = Some(order);
+        self.reversed = false;
         self.jump_to_name(&name)
     }
 
@@ -371,26 +835,91 @@ impl Repository {
             Some(index) => index + 1,
         };
         self.entry_list = self.entry_list.clone()[start..end].to_vec();
-        self.navigator = Navigator::new(self.entry_list.len() as i32, self.navigator.cells_per_row());
+        self.navigator = Navigator::with_rows(self.entry_list.len() as i32, self.navigator.cells_per_row(), self.navigator.rows());
         self.select_start = None
     }
 
     pub fn current_entry(&self) -> Option<&Entry> {
-        Some(&self.entry_list[self.navigator.index()])
+        self.entry_list.get(self.navigator.index())
     }
 
     pub fn current_index(&self) -> usize {
         self.navigator.index()
     }
+    pub fn toggle_compare_entry(&mut self) {
+        let path = match self.current_entry() {
+            Some(entry) => entry.original_file_path(),
+            None => return,
+        };
+        match self.compare_list.toggle(&path) {
+            Ok(added) => self.notices.info(format!("{} {} comparison ({} pictures)", path.display(), if added { "added to" } else { "removed from" }, self.compare_list.paths().len())),
+            Err(err) => self.notices.error(err),
+        }
+    }
+
+    // indexes of the pictures compared that are in the list shown
+    pub fn compared_indexes(&self) -> Vec<usize> {
+        self.compare_list.paths().iter()
+            .filter_map(|path| self.entry_list.iter().position(|entry| entry.original_file_path() == *path))
+            .collect()
+    }
+
+    // moves the focus among the pictures compared, keeping zoom and pan
+    pub fn compare_move(&mut self, forward: bool) {
+        let indexes = self.compared_indexes();
+        if indexes.is_empty() {
+            return
+        };
+        let next = match indexes.iter().position(|index| *index == self.navigator.index()) {
+            Some(position) => next_position(position, indexes.len(), forward),
+            None => 0,
+        };
+        self.navigator.move_to_index(indexes[next])
+    }
+
+    // the focus goes back to a picture compared when something moved it away
+    pub fn keep_compare_focus(&mut self) {
+        let indexes = self.compared_indexes();
+        if !indexes.is_empty() && !indexes.contains(&self.navigator.index()) {
+            self.navigator.move_to_index(indexes[0])
+        }
+    }
+
+    pub fn compare_focus(&mut self, position: usize) {
+        if let Some(index) = self.compared_indexes().get(position) {
+            self.navigator.move_to_index(*index)
+        }
+    }
+
     pub fn toggle_real_size(&mut self) {
-        if self.navigator.cells_per_row() == 1 {
+        if self.navigator.max_cells() == 1 {
             self.real_size_on = !self.real_size_on;
-            println!("toggle real size")
+            self.notices.info(format!("toggle real size"))
         } else {
-            eprintln!("can't toggle real size in grid mode")
+            self.notices.error(format!("can't toggle real size in grid mode"))
         }
     }
 
+    // one more (or one less) column and row; the current picture stays current, on the page that holds it
+    pub fn resize_grid(&mut self, grow: bool) {
+        let (cells_per_row, rows) = (self.navigator.cells_per_row(), self.navigator.rows());
+        let resize = |side: i32| if grow { min(side + 1, MAX_GRID_SIDE as i32) } else { max(side - 1, 1) };
+        let (new_cells_per_row, new_rows) = (resize(cells_per_row), resize(rows));
+        if (new_cells_per_row, new_rows) == (cells_per_row, rows) {
+            self.notices.error(format!("can't {} the grid", if grow { "grow" } else { "shrink" }));
+            return
+        };
+        let index = if self.navigator.capacity() > 0 { Some(self.navigator.index()) } else { None };
+        self.navigator = Navigator::with_rows(self.entry_list.len() as i32, new_cells_per_row, new_rows);
+        if let Some(index) = index {
+            self.navigator.move_to_index(index)
+        };
+        if self.navigator.max_cells() > 1 {
+            self.real_size_on = false
+        };
+        self.notices.info(format!("grid {}", GridSize::Cells(new_cells_per_row as usize, new_rows as usize)))
+    }
+
     pub fn can_move_to_index(&self, index: usize) -> bool {
         self.navigator.can_move_to_index(index)
     }
@@ -398,25 +927,45 @@ impl Repository {
     pub fn move_to_index(&mut self, index: usize) {
         if self.navigator.can_move_to_index(index) {
             self.navigator.move_to_index(index);
-            self.real_size_on = false;
+            self.reset_view();
+            self.record_page_shown()
         } else {
-            eprintln!("can't move to picture #{}", index)
+            self.notices.error(format!("can't move to picture #{}", index))
         }
     }
 
     pub fn move_to_random_index(&mut self) {
-        self.navigator.move_to_random_index();
-        self.real_size_on = false;
+        if self.weighted_on {
+            if let Some(index) = self.weighting.pick(&self.entry_list, &mut thread_rng()) {
+                self.navigator.move_to_index(index)
+            }
+        } else {
+            self.navigator.move_to_random_index();
+        };
+        self.reset_view();
+        self.record_page_shown()
     }
 
     pub fn move_next_page(&mut self) {
         self.navigator.move_next_page();
-        self.real_size_on = false;
+        self.reset_view();
+        self.record_page_shown()
     }
 
     pub fn move_prev_page(&mut self) {
         self.navigator.move_prev_page();
-        self.real_size_on = false;
+        self.reset_view();
+        self.record_page_shown()
+    }
+
+    fn record_page_shown(&mut self) {
+        let (start, end) = self.page_range();
+        self.weighting.record_shown(&self.entry_list[start..end])
+    }
+
+    pub fn toggle_weighted(&mut self) {
+        self.weighted_on = !self.weighted_on;
+        self.notices.info(format!("weighted random {}", if self.weighted_on { "on" } else { "off" }))
     }
 
     pub fn move_in_direction(&mut self, direction: Direction) {
@@ -426,54 +975,86 @@ impl Repository {
         }
     }
 
+    fn on_last_page(&self) -> bool {
+        self.navigator.start_cell_index() + self.navigator.max_cells() as usize >= self.navigator.capacity()
+    }
+
+    // the best rank on the page sets how long the slideshow stays on it
+    fn page_rank(&self) -> Rank {
+        let (start, end) = self.page_range();
+        self.entry_list[start..end].iter()
+            .map(|e| e.image_data.rank)
+            .max_by_key(|rank| rank.stars())
+            .unwrap_or(Rank::NoStar)
+    }
+
+    // returns true when the display has to be refreshed
+    pub fn slideshow_tick(&mut self) -> bool {
+        if self.label_edit_mode_on || self.search_edit_mode_on || self.selection_edit_mode_on || self.command_edit_mode_on {
+            return false
+        };
+        let rank = self.page_rank();
+        let last_page = self.on_last_page();
+        let tick = match &mut self.slideshow {
+            Some(slideshow) => slideshow.tick(rank, last_page),
+            None => Tick::Wait,
+        };
+        match tick {
+            Tick::Wait => false,
+            Tick::Advance if self.weighted_on && matches!(self.order, Some(Order::Random)) => { self.move_to_random_index(); true },
+            Tick::Advance => { self.move_next_page(); true },
+            Tick::Stop => true,
+        }
+    }
+
+    // starts a slideshow if there's none yet
+    pub fn toggle_slideshow(&mut self) {
+        match &mut self.slideshow {
+            Some(slideshow) => slideshow.toggle_pause(),
+            None => self.slideshow = Some(Slideshow::new(DEFAULT_INTERVAL, true, &[])),
+        }
+    }
+
+    pub fn slideshow_faster(&mut self) {
+        if let Some(slideshow) = &mut self.slideshow {
+            slideshow.faster()
+        }
+    }
+
+    pub fn slideshow_slower(&mut self) {
+        if let Some(slideshow) = &mut self.slideshow {
+            slideshow.slower()
+        }
+    }
+
     pub fn page_changed(&self) -> bool {
         self.navigator.page_changed()
     }
 
     pub fn set_order_choice_on(&mut self) {
         self.order = None;
-        println!("order choice on…");
+        self.notices.info(format!("order choice on…"));
     }
 
     pub fn quit(&self) {
         println!("quit gallery show")
     }
 
-    pub fn help(&self) {
-        let content = "commands:\n\n\
-        n: move next page\n\
-        p: move prev page\n\
-        j: move 10 pages forward\n\
-        b: move 10 pages backward\n\
-        z: move to first picture\n\
-        r: move to a random picture\n\
-        =: change order (followed by c,d,l,n,r,v for colors, date, label, name, random, value)\n\
-        .: view picture (when in grid mode)\n\
-        f: view real size (when not in grid mode)\n\
-        ,: toggle selection\n\
-        RET: start a selection/rank group\n\
-        s: save selected entries\n\
-        /: enter label edit mode\n\
-        *: apply last label\n\
-        ";
-        println!("{}", &content)
-    }
     pub fn copy_move_and_quit(&self) {
         self.save_select_entries();
         if let Some(target_path) = &self.copy_selection_target {
-            println!("copy selection to target path");
-            self.copy_select_entries(&target_path)
+            println!("copy selection {} to target path", self.copy_selection_set);
+            self.copy_select_entries(&target_path, &self.copy_selection_set)
         };
         if let Some(target_path) = &self.move_selection_target {
-            println!("move selection to target path");
-            self.copy_select_entries(&target_path);
-            self.delete_select_entries();
-            delete_selection_file()
+            println!("move selection {} to target path", self.move_selection_set);
+            self.copy_select_entries(&target_path, &self.move_selection_set);
+            self.delete_select_entries(&self.move_selection_set);
         };
         if let Some(target_path) = &self.all_label_move_target {
             match move_entries_with_label_to_target(&self.entry_list, target_path) {
                 Ok(()) => {},
-                Err(err) => eprintln!("{}", err),
+                Err(err) => self.notices.error(format!("{}", err)),
             }
         }
         self.delete_entries();
@@ -482,7 +1063,7 @@ impl Repository {
 
     pub fn copy_temp(&self) {
         if let Some(entry) = self.current_entry() {
-            copy_entry_filename_to_current_dir(&entry);
+            copy_entry_filename_to_current_dir(&entry, &self.directories.temporary);
         }
     }
 
@@ -491,7 +1072,9 @@ impl Repository {
     }
 
     pub fn toggle_select(&mut self) {
-        assert!(self.entry_list.len() > 0);
+        if self.entry_list.is_empty() {
+            return
+        };
         let index = self.navigator.index();
         let entry = &mut self.entry_list[index];
         entry.toggle_select();
@@ -500,15 +1083,16 @@ impl Repository {
         } else { 
            self.max_selected -= 1
         }; 
-        if picture_io::save_image_data(&self.entry_list[index]).is_err() {
-            eprintln!("can't save image data {}", &self.entry_list[index].image_data_file_path())
-        };
+        self.save_entry(index);
+        self.save_selection();
         self.navigator.refresh()
 
     }
 
     pub fn toggle_delete(&mut self) {
-        assert!(self.entry_list.len() > 0);
+        if self.entry_list.is_empty() {
+            return
+        };
         let index = self.navigator.index();
         let entry = &mut self.entry_list[index];
         entry.delete = ! entry.delete;
@@ -516,25 +1100,23 @@ impl Repository {
     }
 
     pub fn set_rank(&mut self, rank: Rank) {
-        assert!(self.entry_list.len() > 0);
-        let index = self.navigator.index();
-        let entry = &mut self.entry_list[index];
-        entry.set_rank(rank);
-        if picture_io::save_image_data(&entry).is_err() {
-            eprintln!("can't save image data {}", &entry.image_data_file_path())
+        if self.entry_list.is_empty() {
+            return
         };
+        let index = self.navigator.index();
+        self.entry_list[index].set_rank(rank);
+        self.save_entry(index);
         self.navigator.refresh()
     }
 
     pub fn record_label(&mut self) {
-        assert!(self.entry_list.len() > 0);
-        let index = self.navigator.index();
-        let entry = &mut self.entry_list[index];
-        entry.set_label(&self.field);
-        println!("recording label {}", entry.image_data.label);
-        if picture_io::save_image_data(&entry).is_err() {
-            eprintln!("can't save image data {}", &entry.image_data_file_path())
+        if self.entry_list.is_empty() {
+            return
         };
+        let index = self.navigator.index();
+        self.entry_list[index].set_label(&self.field);
+        self.notices.info(format!("recording label {}", self.entry_list[index].image_data.label));
+        self.save_entry(index);
         self.navigator.refresh()
     }
 
@@ -545,11 +1127,10 @@ impl Repository {
             None => self.record_label(),
             Some(other) => {
                 let (start,end) = if other <= index { (other,index) } else { (index,other) };
-                println!("label: {}…{}", start, end);
+                self.notices.info(format!("label: {}…{}", start, end));
                 for i in start..end+1 {
-                    let entry: &mut Entry = &mut self.entry_list[i];
-                    entry.set_label(&self.field);
-                    let _=  picture_io::save_image_data(entry);
+                    self.entry_list[i].set_label(&self.field);
+                    self.save_entry(i);
                 }
                 self.select_start = None
             },
@@ -557,17 +1138,20 @@ impl Repository {
         self.navigator.refresh()
     }
 
-    pub fn select_page(&mut self, value: bool) {
+    fn page_range(&self) -> (usize, usize) {
         let start = self.navigator.start_cell_index();
-        let end = min(start + self.navigator.max_cells() as usize, self.navigator.capacity());
+        (start, min(start + self.navigator.max_cells() as usize, self.navigator.capacity()))
+    }
+
+    pub fn select_page(&mut self, value: bool) {
+        let (start, end) = self.page_range();
         for i in start..end {
             let entry = &mut self.entry_list[i];
             entry.set_select(value);
-            if picture_io::save_image_data(&self.entry_list[i]).is_err() {
-                eprintln!("can't save image data {}", &self.entry_list[i].image_data_file_path())
-            };
+            self.save_entry(i);
             self.update_max_selected()
         };
+        self.save_selection();
         self.navigator.refresh()
     }
 
@@ -577,14 +1161,29 @@ impl Repository {
         for i in start..end {
             let entry = &mut self.entry_list[i];
             entry.set_select(value);
-            if picture_io::save_image_data(&self.entry_list[i]).is_err() {
-                eprintln!("can't save image data {}", &self.entry_list[i].image_data_file_path())
-            };
+            self.save_entry(i);
             self.update_max_selected()
         };
+        self.save_selection();
         self.navigator.refresh()
     }
     
+    pub fn rank_all(&mut self, rank: Rank) {
+        for i in 0..self.entry_list.len() {
+            self.entry_list[i].set_rank(rank);
+            self.save_entry(i)
+        };
+        self.navigator.refresh()
+    }
+
+    pub fn label_all(&mut self, label: &str) {
+        for i in 0..self.entry_list.len() {
+            self.entry_list[i].set_label(&label.to_string());
+            self.save_entry(i)
+        };
+        self.navigator.refresh()
+    }
+
     fn update_max_selected(&mut self) {
         self.max_selected = self.entry_list.iter().filter(|e| e.is_selected()).count()
     }
@@ -596,11 +1195,10 @@ impl Repository {
                 None => self.apply_last_label(),
                 Some(other) => {
                     let (start,end) = if other <= index { (other,index) } else { (index,other) };
-                    println!("label: {}…{}", start, end);
+                    self.notices.info(format!("label: {}…{}", start, end));
                     for i in start..end+1 {
-                        let entry = &mut self.entry_list[i];
-                        entry.set_label(&self.field);
-                        let _=  picture_io::save_image_data(entry);
+                        self.entry_list[i].set_label(&self.field);
+                        self.save_entry(i);
                     }
                     self.select_start = None
                 },
@@ -611,7 +1209,7 @@ impl Repository {
 
     pub fn select_point(&mut self) {
         let index = self.navigator.index();
-        println!("select: {}…", index);
+        self.notices.info(format!("select: {}…", index));
         self.select_start = Some(index)
     }
 
@@ -620,17 +1218,17 @@ impl Repository {
         match self.select_start {
             None => {
                 self.toggle_select();
-                println!("picture #{} {}", index, if self.entry_list[index].image_data.selected { "selected" } else { "unselected" })
+                self.notices.info(format!("picture #{} {}", index, if self.entry_list[index].image_data.selected { "selected" } else { "unselected" }))
             },
             Some(other) => {
                 let (start,end) = if other <= index { (other,index) } else { (index,other) };
-                println!("select: {}…{}", start, end);
+                self.notices.info(format!("select: {}…{}", start, end));
                 for i in start..end+1 {
-                    let entry = &mut self.entry_list[i];
-                    entry.image_data.selected = true;
-                    let _=  picture_io::save_image_data(entry);
+                    self.entry_list[i].image_data.selected = true;
+                    self.save_entry(i);
                 }
-                self.select_start = None
+                self.select_start = None;
+                self.save_selection()
             },
         };
         self.navigator.refresh();
@@ -638,7 +1236,7 @@ impl Repository {
     }
 
     pub fn cancel_point(&mut self) {
-        println!("point cancelled");
+        self.notices.info(format!("point cancelled"));
         self.select_start = None
     }
 
@@ -647,11 +1245,11 @@ impl Repository {
         match self.select_start {
             None => {
                 self.set_rank(rank);
-                println!("picture #{} rank {}", index, rank)
+                self.notices.info(format!("picture #{} rank {}", index, rank))
             },
             Some(other) => {
                 let (start,end) = if other <= index { (other,index) } else { (index,other) };
-                println!("rank {}: {}…{}", rank, start, end);
+                self.notices.info(format!("rank {}: {}…{}", rank, start, end));
                 for i in start..end+1 {
                     self.entry_list[i].image_data.rank = rank
                 }
@@ -662,37 +1260,182 @@ impl Repository {
     }
 
     pub fn save_select_entries(&self) {
-        let mut list: Vec<String> = Vec::new();
-        let selection: Vec<&Entry> = self.entry_list.iter().filter(|e| e.image_data.selected).collect();
+        let mut list: Vec<PathBuf> = Vec::new();
+        let selection: Vec<&Entry> = self.entries_in_set(&self.active_set);
         for entry in selection.iter() {
             list.push(entry.original_file_path());
             list.push(entry.thumbnail_file_path());
             list.push(entry.image_data_file_path());
         };
-        save_image_list(list);
+        save_image_list(&self.selection_file_path(&self.active_set), list);
+    }
+
+    fn selection_file_path(&self, name: &str) -> PathBuf {
+        self.gallery_path.join(selection_file_name(name))
+    }
+
+    // names of the selection sets the current picture belongs to
+    pub fn current_sets(&self) -> Vec<String> {
+        match self.current_entry() {
+            Some(entry) => {
+                let member = self.member(entry);
+                self.selection_sets.names().into_iter().filter(|name| self.set_members(name).contains(&member)).collect()
+            },
+            None => vec![],
+        }
+    }
+
+    // how a picture is known in the selection sets
+    fn member(&self, entry: &Entry) -> Member {
+        Member::new(&entry.original_file_path(), &self.gallery_path)
+    }
+
+    fn set_members(&self, name: &str) -> BTreeSet<Member> {
+        if name == self.active_set {
+            self.entry_list.iter().chain(self.hidden.iter()).filter(|e| e.is_selected()).map(|e| self.member(e)).collect()
+        } else {
+            self.selection_sets.members(name)
+        }
+    }
+
+    fn entries_in_set(&self, name: &str) -> Vec<&Entry> {
+        let members = self.set_members(name);
+        self.entry_list.iter().chain(self.hidden.iter()).filter(|e| members.contains(&self.member(e))).collect()
+    }
+
+    fn save_entry(&self, index: usize) {
+        self.save_image_data(&self.entry_list[index])
+    }
+
+    // when another set is active, the image data keeps the default set selection
+    fn save_image_data(&self, entry: &Entry) {
+        let result = if self.active_set == DEFAULT_SET {
+            picture_io::save_image_data(entry)
+        } else {
+            let mut default_entry = entry.clone();
+            default_entry.set_select(self.selection_sets.contains(DEFAULT_SET, &self.member(entry)));
+            picture_io::save_image_data(&default_entry)
+        };
+        if result.is_err() {
+            self.notices.error(format!("can't save image data {}", entry.image_data_file_path().display()))
+        }
+    }
+
+    fn save_selection(&mut self) {
+        if self.active_set != DEFAULT_SET {
+            self.selection_sets.record(&self.active_set, self.entry_list.iter().chain(self.hidden.iter()), &self.gallery_path);
+            if let Err(err) = save_selection_sets(&self.gallery_path, &self.selection_sets) {
+                self.notices.error(format!("can't save selection sets: {}", err))
+            }
+        }
+    }
+
+    pub fn has_selection_set(&self, name: &str) -> bool {
+        self.selection_sets.names().iter().any(|n| n == name)
+    }
+
+    pub fn switch_selection_set(&mut self, name: &str) {
+        if name == self.active_set {
+            return
+        };
+        // a set only gets created once something is selected in it
+        if self.has_selection_set(&self.active_set) {
+            self.selection_sets.record(&self.active_set, self.entry_list.iter().chain(self.hidden.iter()), &self.gallery_path);
+        };
+        // the hidden pictures follow the set too, so that they're right once shown again
+        let members = self.selection_sets.members(name);
+        let gallery = self.gallery_path.clone();
+        for entry in self.entry_list.iter_mut().chain(self.hidden.iter_mut()) {
+            let selected = members.contains(&Member::new(&entry.original_file_path(), &gallery));
+            entry.set_select(selected)
+        };
+        self.previous_set = Some(self.active_set.clone());
+        self.active_set = name.to_string();
+        self.update_max_selected();
+        self.notices.info(format!("selection set {} ({} selected)", self.active_set, self.max_selected));
+        self.navigator.refresh()
+    }
+
+    pub fn cycle_selection_set(&mut self, forward: bool) {
+        let name = self.selection_sets.next_name(&self.active_set, forward);
+        self.switch_selection_set(&name)
+    }
+
+    // combine the active set with the previously active one
+    pub fn combine_selection_set(&mut self, operation: SetOperation) {
+        let other = match &self.previous_set {
+            Some(name) => name.clone(),
+            None => {
+                self.notices.error(format!("no other selection set to combine with"));
+                return
+            },
+        };
+        let members = operation.apply(&self.set_members(&self.active_set), &self.set_members(&other));
+        let mut changed = EntryList::new();
+        let gallery = self.gallery_path.clone();
+        for entry in self.entry_list.iter_mut().chain(self.hidden.iter_mut()) {
+            let selected = members.contains(&Member::new(&entry.original_file_path(), &gallery));
+            if entry.is_selected() != selected {
+                entry.set_select(selected);
+                changed.push(entry.clone())
+            }
+        };
+        for entry in &changed {
+            self.save_image_data(entry)
+        };
+        self.save_selection();
+        self.update_max_selected();
+        self.notices.info(format!("selection set {} := {} {} {} ({} selected)", self.active_set, self.active_set, operation, other, self.max_selected));
+        self.navigator.refresh()
+    }
+
+    pub fn save_contact_sheet(&self) {
+        let (start, end) = self.page_range();
+        let entries: Vec<&Entry> = self.entry_list[start..end].iter().collect();
+        let file_name = format!("contact_sheet_{}", Local::now().format("%Y%m%d_%H%M%S"));
+        let base_name = self.directories.temporary.join(file_name);
+        match write_contact_sheets(&entries, self.cells_per_row() as usize, self.rows() as usize, self.palette_extract_on, self.sample, &base_name.to_string_lossy()) {
+            Ok(files) => self.notices.info(format!("contact sheet written to {}", files.join(", "))),
+            Err(err) => self.notices.error(format!("can't write contact sheet: {}", err)),
+        }
+    }
+
+    pub fn save_reading_list(&self, page_only: bool) {
+        let (start, end) = if page_only {
+            self.page_range()
+        } else {
+            (0, self.navigator.capacity())
+        };
+        let entries: Vec<&Entry> = self.entry_list[start..end].iter().collect();
+        let file_name = format!("reading_list_{}.m3u", Local::now().format("%Y%m%d_%H%M%S"));
+        let path = self.directories.temporary.join(file_name);
+        match write_reading_list(&path, &entries) {
+            Ok(()) => self.notices.info(format!("{} entries written to reading list {}", entries.len(), path.display())),
+            Err(err) => self.notices.error(format!("can't write reading list {}: {}", path.display(), err)),
+        }
     }
 
-    pub fn copy_select_entries(&self, target: &str) {
+    pub fn copy_select_entries(&self, target: &str, name: &str) {
         let target_path = Path::new(target);
         if !target_path.exists() {
-            eprintln!("directory doesn't exist: {}", target);
+            self.notices.error(format!("directory doesn't exist: {}", target));
             return
         };
-        let selection: Vec<&Entry> = self.entry_list.iter().filter(|e| e.image_data.selected).collect();
+        let selection: Vec<&Entry> = self.entries_in_set(name);
         for entry in selection {
             match copy_entry(entry, target_path) {
                 Ok(_) => {},
-                Err(e) => eprintln!("err copying entry {} to {}: {}", entry.original_file_path(), target_path.display(), e),
+                Err(e) => self.notices.error(format!("err copying entry {} to {}: {}", entry.original_file_path().display(), target_path.display(), e)),
             }
         }
     }
 
-    pub fn delete_select_entries(&self) {
-        let selection: Vec<&Entry> = self.entry_list.iter().filter(|e| e.image_data.selected).collect();
+    pub fn delete_select_entries(&self, name: &str) {
+        let selection: Vec<&Entry> = self.entries_in_set(name);
         for entry in selection {
             delete_entry(entry)
         };
-        delete_selection_file()
+        delete_selection_file(&self.selection_file_path(name))
     }
 
     pub fn toggle_palette_extract(&mut self) {
@@ -701,7 +1444,7 @@ impl Repository {
     }
 
     pub fn delete_entries(&self) {
-        let selection: Vec<&Entry> = self.entry_list.iter().filter(|e| e.delete).collect();
+        let selection: Vec<&Entry> = self.entry_list.iter().chain(self.hidden.iter()).filter(|e| e.delete).collect();
         for entry in selection {
             delete_entry(entry)
         }
@@ -715,43 +1458,30 @@ mod tests {
     use std::rc::Rc;
     use std::cell::RefCell;
     use std::cell::RefMut;
-    use crate::make_entry;
+    use crate::entry::{example_entries, make_entry};
     use std::time::SystemTime;
-    use chrono::DateTime;
-
-    fn example() -> EntryList {
-        let day_a: SystemTime = DateTime::parse_from_rfc2822("Sun, 1 Jan 2023 10:52:37 GMT").unwrap().into();
-        let day_b: SystemTime = DateTime::parse_from_rfc2822("Sat, 1 Jul 2023 10:52:37 GMT").unwrap().into();
-        let day_c: SystemTime = DateTime::parse_from_rfc2822("Mon, 1 Jan 2024 10:52:37 GMT").unwrap().into();
-        let day_d: SystemTime = DateTime::parse_from_rfc2822("Mon, 1 Jan 2024 11:52:37 GMT").unwrap().into();
-        vec!(
-            make_entry(String::from("photos/foo.jpeg"), 100, 5, day_d, Rank::NoStar),
-            make_entry(String::from("photos/bar.jpeg"), 1000, 15, day_b, Rank::ThreeStars),
-            make_entry(String::from("photos/qux.jpeg"), 10, 25, day_c, Rank::TwoStars),
-            make_entry(String::from("photos/bub.jpeg"), 100, 25, day_a, Rank::OneStar))
-    }
 
     #[test]
     fn after_creation_the_current_entry_is_the_first_entry() {
-        let repository = Repository::from_entries(example().clone(), 2);
+        let repository = Repository::from_entries(example_entries(), (2, 2), None, None, None, false);
         assert_eq!(4, repository.navigator.capacity());
         assert_eq!(2, repository.navigator.cells_per_row());
         let entry: &Entry = repository.current_entry().unwrap();
-        assert_eq!(example().clone()[0], *entry);
+        assert_eq!(example_entries()[0], *entry);
     }
 
     #[test]
     fn after_moving_one_col_current_entry_is_the_second_entry() {
-        let mut repository = Repository::from_entries(example().clone(), 2);
-        repository.navigator.move_towards(Direction::Right);
+        let mut repository = Repository::from_entries(example_entries(), (2, 2), None, None, None, false);
+        repository.navigator.move_abs((1, 0));
         let entry: &Entry = repository.current_entry().unwrap();
-        assert_eq!(example().clone()[1], *entry);
+        assert_eq!(example_entries()[1], *entry);
     }
 
     #[test]
     fn after_toggle_select_current_entry_is_selected_or_unselected() {
         // to share a mutable reference on repository
-        let repository_rc = Rc::new(RefCell::new(Repository::from_entries(example().clone(), 2)));
+        let repository_rc = Rc::new(RefCell::new(Repository::from_entries(example_entries(), (2, 2), None, None, None, false)));
         {
             // first mutation occurs in this scope
             let mut repository: RefMut<'_, Repository> = repository_rc.borrow_mut();
@@ -768,10 +1498,10 @@ mod tests {
 
     #[test]
     fn after_two_select_points_a_group_of_entries_is_selected() {
-        let repository_rc = Rc::new(RefCell::new(Repository::from_entries(example().clone(), 2)));
-        { repository_rc.borrow_mut().navigator.move_towards(Direction::Down) }; // now current entry is #2 
+        let repository_rc = Rc::new(RefCell::new(Repository::from_entries(example_entries(), (2, 2), None, None, None, false)));
+        { repository_rc.borrow_mut().navigator.move_abs((0, 1)) }; // now current entry is #2
         { repository_rc.borrow_mut().select_point() };
-        { repository_rc.borrow_mut().navigator.move_towards(Direction::Up) }; // now current entry is #0
+        { repository_rc.borrow_mut().navigator.move_abs((0, 0)) }; // now current entry is #0
         { repository_rc.borrow_mut().point_select() }; // only entries 0,1,2 are selected
         let repository = repository_rc.borrow();
         for entry in &repository.entry_list[0..3] {
@@ -782,7 +1512,7 @@ mod tests {
 
     #[test]
     fn after_setting_rank_current_entries_has_a_new_rank() {
-        let repository_rc = Rc::new(RefCell::new(Repository::from_entries(example().clone(), 2)));
+        let repository_rc = Rc::new(RefCell::new(Repository::from_entries(example_entries(), (2, 2), None, None, None, false)));
         { repository_rc.borrow_mut().set_rank(Rank::ThreeStars) };
         let repository = repository_rc.borrow();
         assert_eq!(Rank::ThreeStars, repository.current_entry().unwrap().image_data.rank);
@@ -790,10 +1520,10 @@ mod tests {
 
     #[test]
     fn after_two_rank_points_a_group_on_entries_has_rank_changed() {
-        let repository_rc = Rc::new(RefCell::new(Repository::from_entries(example().clone(), 2)));
-        { repository_rc.borrow_mut().navigator.move_towards(Direction::Down) }; // now current entry is #2 
+        let repository_rc = Rc::new(RefCell::new(Repository::from_entries(example_entries(), (2, 2), None, None, None, false)));
+        { repository_rc.borrow_mut().navigator.move_abs((0, 1)) }; // now current entry is #2
         { repository_rc.borrow_mut().select_point() };
-        { repository_rc.borrow_mut().navigator.move_towards(Direction::Up) }; // now current entry is #0
+        { repository_rc.borrow_mut().navigator.move_abs((0, 0)) }; // now current entry is #0
         { repository_rc.borrow_mut().point_rank(Rank::TwoStars) }; // only entries 0,1,2 are ranked
         let repository = repository_rc.borrow();
         for entry in &repository.entry_list[0..3] {
@@ -804,7 +1534,7 @@ mod tests {
 
     #[test]
     fn sorting_entries_by_date() {
-        let repository_rc = Rc::new(RefCell::new(Repository::from_entries(example().clone(), 2)));
+        let repository_rc = Rc::new(RefCell::new(Repository::from_entries(example_entries(), (2, 2), None, None, None, false)));
         { repository_rc.borrow_mut().sort_by(Order::Date) };
         { repository_rc.borrow_mut().navigator.move_to_index(0) };
         { assert_eq!(String::from("bub.jpeg"), repository_rc.borrow().current_entry().unwrap().original_file_name()) };
@@ -818,7 +1548,7 @@ mod tests {
 
     #[test]
     fn sorting_entries_by_name() {
-        let repository_rc = Rc::new(RefCell::new(Repository::from_entries(example().clone(), 2)));
+        let repository_rc = Rc::new(RefCell::new(Repository::from_entries(example_entries(), (2, 2), None, None, None, false)));
         { repository_rc.borrow_mut().sort_by(Order::Name) };
         { repository_rc.borrow_mut().navigator.move_to_index(0) };
         { assert_eq!(String::from("bar.jpeg"), repository_rc.borrow().current_entry().unwrap().original_file_name()) };
@@ -831,7 +1561,7 @@ mod tests {
     }
     #[test]
     fn sorting_entries_by_colors_then_name() {
-        let repository_rc = Rc::new(RefCell::new(Repository::from_entries(example().clone(), 2)));
+        let repository_rc = Rc::new(RefCell::new(Repository::from_entries(example_entries(), (2, 2), None, None, None, false)));
         { repository_rc.borrow_mut().sort_by(Order::Colors) };
         { assert_eq!(String::from("foo.jpeg"), repository_rc.borrow().current_entry().unwrap().original_file_name()) };
         { repository_rc.borrow_mut().navigator.move_to_index(1) };
@@ -843,7 +1573,7 @@ mod tests {
     }
     #[test]
     fn sorting_entries_by_value_then_name() {
-        let repository_rc = Rc::new(RefCell::new(Repository::from_entries(example().clone(), 2)));
+        let repository_rc = Rc::new(RefCell::new(Repository::from_entries(example_entries(), (2, 2), None, None, None, false)));
         { repository_rc.borrow_mut().sort_by(Order::Value) };
         { repository_rc.borrow_mut().navigator.move_to_index(0) };
         { assert_eq!(String::from("bar.jpeg"), repository_rc.borrow().current_entry().unwrap().original_file_name()) };
@@ -855,24 +1585,84 @@ mod tests {
         { assert_eq!(String::from("foo.jpeg"), repository_rc.borrow().current_entry().unwrap().original_file_name()) };
     }
 
+    #[test]
+    fn hidden_pictures_follow_the_active_selection_set() {
+        let mut repository = Repository::from_entries(example_entries(), (2, 2), None, None, None, false);
+        repository.load_selection_sets(Path::new("photos"));
+        repository.selection_sets.set_members("best", [Member::Text(String::from("bar.jpeg")), Member::Text(String::from("qux.jpeg"))].into_iter().collect());
+        repository.filter_entries("foo");
+        repository.switch_selection_set("best");
+        repository.show_all_entries();
+        let selected: Vec<String> = repository.entry_list.iter().filter(|e| e.is_selected()).map(|e| e.original_file_name()).collect();
+        assert_eq!(vec![String::from("bar.jpeg"), String::from("qux.jpeg")], selected);
+        repository.filter_entries("foo");
+        repository.switch_selection_set(DEFAULT_SET);
+        repository.show_all_entries();
+        assert!(repository.entry_list.iter().all(|e| !e.is_selected()));
+        assert_eq!(2, repository.selection_sets.members("best").len());
+    }
+
+    #[test]
+    fn changing_the_current_entry_of_an_empty_list_does_nothing() {
+        let mut repository = Repository::from_entries(example_entries(), (2, 2), None, None, None, false);
+        let removed: Vec<PathBuf> = repository.entry_list.iter().map(|e| e.original_file_path()).collect();
+        repository.update_entries(&removed, EntryList::new());
+        assert!(repository.entry_list.is_empty());
+        repository.toggle_select();
+        repository.toggle_delete();
+        repository.set_rank(Rank::ThreeStars);
+        repository.field = String::from("trip");
+        repository.record_label();
+        assert!(repository.entry_list.is_empty());
+    }
+
+    #[test]
+    fn pictures_under_a_directory_removed_are_removed() {
+        let mut entries = example_entries();
+        entries.push(make_entry(String::from("photos/trips/foo.jpeg"), 100, 5, SystemTime::now(), Rank::NoStar));
+        entries.push(make_entry(String::from("photos/tripsbis/foo.jpeg"), 100, 5, SystemTime::now(), Rank::NoStar));
+        let mut repository = Repository::from_entries(entries, (2, 2), None, None, None, false);
+        repository.apply_file_changes(&[(PathBuf::from("photos/trips"), FileChange::Removed)]);
+        assert_eq!(5, repository.entry_list.len());
+        assert!(repository.entry_list.iter().all(|e| e.original_file_path() != Path::new("photos/trips/foo.jpeg")));
+    }
+
+    #[test]
+    fn pictures_changed_on_disk_keep_their_rank_label_and_selection() {
+        let mut repository = Repository::from_entries(example_entries(), (2, 2), None, None, None, false);
+        repository.load_selection_sets(Path::new("photos"));
+        repository.selection_sets.set_members("best", [Member::Text(String::from("foo.jpeg")), Member::Text(String::from("new.jpeg"))].into_iter().collect());
+        repository.switch_selection_set("best");
+        let foo = repository.entry_list.iter_mut().find(|e| e.original_file_name() == "foo.jpeg").unwrap();
+        foo.set_label(&String::from("trip"));
+        let mut changed = make_entry(String::from("photos/foo.jpeg"), 200, 7, SystemTime::now(), Rank::NoStar);
+        changed.image_data.palette = [1;9];
+        let added = make_entry(String::from("photos/new.jpeg"), 100, 5, SystemTime::now(), Rank::NoStar);
+        repository.update_entries(&[], vec![changed, added]);
+        let foo = repository.entry_list.iter().find(|e| e.original_file_name() == "foo.jpeg").unwrap();
+        assert_eq!((200, 7, [1;9]), (foo.file_size, foo.image_data.colors, foo.image_data.palette));
+        assert_eq!((Rank::NoStar, "trip", true), (foo.image_data.rank, foo.image_data.label.as_str(), foo.is_selected()));
+        let new = repository.entry_list.iter().find(|e| e.original_file_name() == "new.jpeg").unwrap();
+        assert!(new.is_selected());
+    }
+
     #[test]
     fn slicing_entries_without_limits_yields_the_whole_set() {
-        let repository_rc = Rc::new(RefCell::new(Repository::from_entries(example().clone(), 2)));
+        let repository_rc = Rc::new(RefCell::new(Repository::from_entries(example_entries(), (2, 2), None, None, None, false)));
         { repository_rc.borrow_mut().slice(None, None) };
         assert_eq!(4, repository_rc.borrow().entry_list.len());
     }
     #[test]
     fn slicing_entries_with_low_limit_yields_a_portion_of_the_set() {
-        let repository_rc = Rc::new(RefCell::new(Repository::from_entries(example().clone(), 2)));
+        let repository_rc = Rc::new(RefCell::new(Repository::from_entries(example_entries(), (2, 2), None, None, None, false)));
         { repository_rc.borrow_mut().slice(Some(2), None) };
         assert_eq!(2, repository_rc.borrow().entry_list.len());
         assert_eq!(String::from("qux.jpeg"), repository_rc.borrow().current_entry().unwrap().original_file_name());
     }
     #[test]
     fn slicing_entries_with_high_limit_yields_a_portion_of_the_set() {
-        let repository_rc = Rc::new(RefCell::new(Repository::from_entries(example().clone(), 2)));
+        let repository_rc = Rc::new(RefCell::new(Repository::from_entries(example_entries(), (2, 2), None, None, None, false)));
         { repository_rc.borrow_mut().slice(None, Some(2)) };
         assert_eq!(3, repository_rc.borrow().entry_list.len());
     }
 }
-
@@ -0,0 +1,139 @@
+use crate::direction::Direction;
+use crate::gui::entry_opacity;
+use crate::picture_io::set_thumbnail_picture_file;
+use crate::repository::Repository;
+use gtk::glib::clone;
+use gtk::prelude::*;
+use gtk::{self, Align, Orientation};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// columns when the grid shows a single picture; otherwise there are as many columns as in the grid
+const SCROLL_COLUMNS: u32 = 6;
+const THUMBNAIL_SIZE: i32 = 200;
+
+// all the pictures as thumbnails in one scrolling view; the position of an item in the model
+// is the index of its entry, and only the items on screen are bound to widgets
+pub struct ScrollView {
+    pub scrolled_window: gtk::ScrolledWindow,
+    grid_view: gtk::GridView,
+    model: gtk::StringList,
+    selection: gtk::SingleSelection,
+}
+
+fn setup_item(item: &gtk::ListItem) {
+    let picture = gtk::Picture::new();
+    picture.set_size_request(THUMBNAIL_SIZE, THUMBNAIL_SIZE);
+    picture.set_can_shrink(true);
+    let label = gtk::Label::new(None);
+    label.set_widget_name("picture_label");
+    let vbox = gtk::Box::new(Orientation::Vertical, 0);
+    vbox.set_valign(Align::Center);
+    vbox.set_halign(Align::Center);
+    vbox.append(&picture);
+    vbox.append(&label);
+    item.set_child(Some(&vbox))
+}
+
+fn bind_item(item: &gtk::ListItem, repository: &Repository) {
+    let index = item.position() as usize;
+    let vbox = match item.child().and_then(|child| child.downcast::<gtk::Box>().ok()) {
+        Some(vbox) => vbox,
+        None => return,
+    };
+    let picture = vbox.first_child().and_then(|child| child.downcast::<gtk::Picture>().ok());
+    let label = vbox.last_child().and_then(|child| child.downcast::<gtk::Label>().ok());
+    if let (Some(picture), Some(label), Some(entry)) = (picture, label, repository.entry_at_index(index)) {
+        picture.set_opacity(entry_opacity(entry));
+        if let Err(err) = set_thumbnail_picture_file(&picture, entry) {
+            eprintln!("{}", err)
+        };
+        label.set_text(&entry.label_display(index == repository.current_index(), repository.sample()))
+    }
+}
+
+impl ScrollView {
+    pub fn new(window: &gtk::ApplicationWindow, repository_rc: &Rc<RefCell<Repository>>) -> Self {
+        let model = gtk::StringList::new(&[]);
+        let selection = gtk::SingleSelection::new(Some(model.clone()));
+        let factory = gtk::SignalListItemFactory::new();
+        factory.connect_setup(|_, item| setup_item(item));
+        factory.connect_bind(clone!(@strong repository_rc => move |_, item| {
+            if let Ok(repository) = repository_rc.try_borrow() {
+                bind_item(item, &repository)
+            }
+        }));
+        let grid_view = gtk::GridView::new(Some(selection.clone()), Some(factory));
+        grid_view.set_min_columns(SCROLL_COLUMNS);
+        grid_view.set_max_columns(SCROLL_COLUMNS);
+        // keys are handled by the window, a click makes the picture current
+        grid_view.set_can_focus(false);
+        selection.connect_selected_notify(clone!(@strong repository_rc, @strong window, @strong model => move |selection| {
+            let moved = match repository_rc.try_borrow_mut() {
+                Ok(mut repository) => {
+                    let index = selection.selected() as usize;
+                    let moved = index != repository.current_index() && repository.can_move_to_index(index);
+                    if moved {
+                        repository.move_to_index(index);
+                        window.set_title(Some(&repository.title_display()))
+                    };
+                    moved
+                },
+                Err(_) => false,
+            };
+            if moved {
+                model.items_changed(0, model.n_items(), model.n_items())
+            }
+        }));
+        let scrolled_window = gtk::ScrolledWindow::builder()
+            .hscrollbar_policy(gtk::PolicyType::Never)
+            .vscrollbar_policy(gtk::PolicyType::Automatic)
+            .name("scroll")
+            .build();
+        scrolled_window.set_child(Some(&grid_view));
+        ScrollView {
+            scrolled_window: scrolled_window,
+            grid_view: grid_view,
+            model: model,
+            selection: selection,
+        }
+    }
+
+    pub fn columns(&self) -> u32 {
+        self.grid_view.max_columns()
+    }
+
+    // called with the repository borrowed, so that selecting the current picture doesn't move it
+    pub fn refresh(&self, repository: &Repository) {
+        let capacity = repository.capacity() as u32;
+        if self.model.n_items() != capacity {
+            let indexes: Vec<String> = (0..capacity).map(|index| index.to_string()).collect();
+            let indexes: Vec<&str> = indexes.iter().map(|index| index.as_str()).collect();
+            self.model.splice(0, self.model.n_items(), &indexes)
+        } else {
+            self.model.items_changed(0, capacity, capacity)
+        };
+        let columns = if repository.cells_per_row() > 1 { repository.cells_per_row() as u32 } else { SCROLL_COLUMNS };
+        if columns != self.columns() {
+            self.grid_view.set_min_columns(1);
+            self.grid_view.set_max_columns(columns);
+            self.grid_view.set_min_columns(columns)
+        };
+        if capacity > 0 {
+            let index = repository.current_index() as u32;
+            self.selection.set_selected(index);
+            let _ = self.grid_view.activate_action("list.scroll-to-item", Some(&index.to_variant()));
+        }
+    }
+}
+
+// the arrows move one picture, or one row of the view
+pub fn scroll_index(index: usize, columns: usize, capacity: usize, direction: Direction) -> Option<usize> {
+    let next = match direction {
+        Direction::Right => index.checked_add(1),
+        Direction::Left => index.checked_sub(1),
+        Direction::Down => index.checked_add(columns),
+        Direction::Up => index.checked_sub(columns),
+    }?;
+    if next < capacity { Some(next) } else { None }
+}
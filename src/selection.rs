@@ -0,0 +1,191 @@
+use crate::entry::Entry;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+// the default selection set is the one saved in each picture image data,
+// other sets are saved together in a file of the gallery directory
+pub const DEFAULT_SET: &str = "default";
+pub const SELECTION_SETS_FILE_NAME: &str = "SELECTION_SETS.json";
+
+// a member of a set is the path of the picture relative to the gallery directory, so that the
+// sets don't depend on how the directory is given; a path that isn't UTF-8 is kept as bytes
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum Member {
+    Text(String),
+    Bytes(Vec<u8>),
+}
+
+impl Member {
+    pub fn new(path: &Path, gallery: &Path) -> Self {
+        let relative = path.strip_prefix(gallery).unwrap_or(path);
+        match relative.to_str() {
+            Some(text) => Member::Text(text.to_string()),
+            None => Member::Bytes(relative.as_os_str().as_encoded_bytes().to_vec()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SetOperation {
+    Union, Intersection, Difference,
+}
+
+impl std::fmt::Display for SetOperation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            SetOperation::Union => "union",
+            SetOperation::Intersection => "intersection",
+            SetOperation::Difference => "difference",
+        })
+    }
+}
+
+impl SetOperation {
+    pub fn apply(&self, a: &BTreeSet<Member>, b: &BTreeSet<Member>) -> BTreeSet<Member> {
+        match self {
+            SetOperation::Union => a.union(b).cloned().collect(),
+            SetOperation::Intersection => a.intersection(b).cloned().collect(),
+            SetOperation::Difference => a.difference(b).cloned().collect(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct SelectionSets {
+    sets: BTreeMap<String, BTreeSet<Member>>,
+}
+
+impl SelectionSets {
+    pub fn names(&self) -> Vec<String> {
+        let mut names = vec![String::from(DEFAULT_SET)];
+        names.extend(self.sets.keys().filter(|k| k.as_str() != DEFAULT_SET).cloned());
+        names
+    }
+
+    pub fn members(&self, name: &str) -> BTreeSet<Member> {
+        self.sets.get(name).cloned().unwrap_or_default()
+    }
+
+    pub fn contains(&self, name: &str, member: &Member) -> bool {
+        self.sets.get(name).map_or(false, |set| set.contains(member))
+    }
+
+    #[cfg(test)]
+    pub fn create(&mut self, name: &str) {
+        self.sets.entry(name.to_string()).or_default();
+    }
+
+    // record the selection of the loaded entries, keeping members that are not loaded
+    pub fn record<'a>(&mut self, name: &str, entries: impl Iterator<Item = &'a Entry>, gallery: &Path) {
+        let set = self.sets.entry(name.to_string()).or_default();
+        for entry in entries {
+            let member = Member::new(&entry.original_file_path(), gallery);
+            if entry.is_selected() {
+                set.insert(member);
+            } else {
+                set.remove(&member);
+            }
+        }
+    }
+
+    #[cfg(test)]
+    pub fn set_members(&mut self, name: &str, members: BTreeSet<Member>) {
+        self.sets.insert(name.to_string(), members);
+    }
+
+    // the default set lives in the image data files
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        let mut saved = self.clone();
+        saved.sets.remove(DEFAULT_SET);
+        serde_json::to_string(&saved)
+    }
+
+    pub fn next_name(&self, name: &str, forward: bool) -> String {
+        let names = self.names();
+        let count = names.len();
+        let position = names.iter().position(|n| n == name).unwrap_or(0);
+        let next = if forward { (position + 1) % count } else { (position + count - 1) % count };
+        names[next].clone()
+    }
+}
+
+pub fn selection_file_name(name: &str) -> String {
+    if name == DEFAULT_SET {
+        String::from("selections")
+    } else {
+        format!("selections_{}", name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entry::make_entry;
+    use crate::rank::Rank;
+    use std::time::SystemTime;
+
+    fn set(paths: &[&str]) -> BTreeSet<Member> {
+        paths.iter().map(|p| Member::Text(p.to_string())).collect()
+    }
+
+    #[test]
+    fn set_operations_combine_members() {
+        let a = set(&["foo", "bar"]);
+        let b = set(&["bar", "qux"]);
+        assert_eq!(set(&["bar", "foo", "qux"]), SetOperation::Union.apply(&a, &b));
+        assert_eq!(set(&["bar"]), SetOperation::Intersection.apply(&a, &b));
+        assert_eq!(set(&["foo"]), SetOperation::Difference.apply(&a, &b));
+    }
+
+    #[test]
+    fn recording_keeps_members_that_are_not_loaded() {
+        let mut sets = SelectionSets::default();
+        sets.set_members("best", set(&["elsewhere/bar.jpeg", "foo.jpeg"]));
+        let mut entry = make_entry(String::from("photos/foo.jpeg"), 100, 5, SystemTime::now(), Rank::NoStar);
+        entry.set_select(false);
+        sets.record("best", [entry].iter(), Path::new("photos"));
+        assert_eq!(set(&["elsewhere/bar.jpeg"]), sets.members("best"));
+    }
+
+    #[test]
+    fn members_are_relative_to_the_gallery_however_it_is_given() {
+        assert_eq!(Member::Text(String::from("trips/foo.jpeg")), Member::new(Path::new("photos/trips/foo.jpeg"), Path::new("photos")));
+        assert_eq!(Member::Text(String::from("trips/foo.jpeg")), Member::new(Path::new("./photos/trips/foo.jpeg"), Path::new("./photos")));
+        assert_eq!(Member::Text(String::from("trips/foo.jpeg")), Member::new(Path::new("/home/me/photos/trips/foo.jpeg"), Path::new("/home/me/photos/")));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn members_that_are_not_utf8_are_saved_as_bytes() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+        let mut sets = SelectionSets::default();
+        let first = Member::new(Path::new(OsStr::from_bytes(b"photos/caf\xe9.jpeg")), Path::new("photos"));
+        let second = Member::new(Path::new(OsStr::from_bytes(b"photos/caf\xe8.jpeg")), Path::new("photos"));
+        assert_ne!(first, second);
+        sets.set_members("best", [first, second, Member::Text(String::from("foo.jpeg"))].into_iter().collect());
+        let json = sets.to_json().unwrap();
+        assert_eq!("{\"sets\":{\"best\":[\"foo.jpeg\",[99,97,102,232,46,106,112,101,103],[99,97,102,233,46,106,112,101,103]]}}", json);
+        assert_eq!(sets, serde_json::from_str(&json).unwrap());
+    }
+
+    #[test]
+    fn names_start_with_the_default_set_and_cycle() {
+        let mut sets = SelectionSets::default();
+        sets.create("best");
+        sets.create("print");
+        assert_eq!(vec!["default", "best", "print"], sets.names());
+        assert_eq!("best", sets.next_name(DEFAULT_SET, true));
+        assert_eq!("print", sets.next_name(DEFAULT_SET, false));
+    }
+
+    #[test]
+    fn default_set_is_not_saved_in_the_sets_file() {
+        let mut sets = SelectionSets::default();
+        sets.set_members(DEFAULT_SET, set(&["photos/foo.jpeg"]));
+        sets.create("best");
+        assert_eq!("{\"sets\":{\"best\":[]}}", sets.to_json().unwrap());
+    }
+}
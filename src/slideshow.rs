@@ -0,0 +1,210 @@
+use crate::rank::Rank;
+use std::fmt;
+
+// the gui calls tick() every TICK_MILLIS; intervals are in seconds
+pub const TICK_MILLIS: u64 = 100;
+pub const DEFAULT_INTERVAL: f64 = 5.0;
+const MIN_INTERVAL: f64 = 0.1;
+const MAX_INTERVAL: f64 = 3600.0;
+const SPEED_FACTOR: f64 = 1.5;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum State {
+    Playing, Paused, Stopped,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Tick {
+    Wait, Advance, Stop,
+}
+
+#[derive(Clone, Debug)]
+pub struct Slideshow {
+    interval: f64,
+    looping: bool,
+    // dwell time by number of stars, replacing the interval for pages with that rank
+    dwell: [Option<f64>; 4],
+    elapsed_ticks: u64,
+    state: State,
+}
+
+impl Slideshow {
+    pub fn new(interval: f64, looping: bool, dwell: &[(usize, f64)]) -> Self {
+        let mut dwell_times = [None; 4];
+        for (stars, seconds) in dwell {
+            if *stars < dwell_times.len() {
+                dwell_times[*stars] = Some(seconds.clamp(MIN_INTERVAL, MAX_INTERVAL))
+            }
+        };
+        Slideshow {
+            interval: interval.clamp(MIN_INTERVAL, MAX_INTERVAL),
+            looping: looping,
+            dwell: dwell_times,
+            elapsed_ticks: 0,
+            state: State::Playing,
+        }
+    }
+
+    #[cfg(test)]
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    #[cfg(test)]
+    pub fn interval(&self) -> f64 {
+        self.interval
+    }
+
+    // faster and slower scale the dwell times too, so that their ratio to the interval is kept
+    fn scale(&mut self, factor: f64) {
+        self.interval = (self.interval * factor).clamp(MIN_INTERVAL, MAX_INTERVAL);
+        for dwell in self.dwell.iter_mut() {
+            *dwell = dwell.map(|d| (d * factor).clamp(MIN_INTERVAL, MAX_INTERVAL))
+        }
+    }
+
+    pub fn faster(&mut self) {
+        self.scale(1.0 / SPEED_FACTOR)
+    }
+
+    pub fn slower(&mut self) {
+        self.scale(SPEED_FACTOR)
+    }
+
+    // a stopped slideshow starts again from the current page
+    pub fn toggle_pause(&mut self) {
+        self.state = match self.state {
+            State::Playing => State::Paused,
+            State::Paused | State::Stopped => State::Playing,
+        };
+        self.elapsed_ticks = 0
+    }
+
+    pub fn dwell_time(&self, rank: Rank) -> f64 {
+        self.dwell[rank.stars()].unwrap_or(self.interval)
+    }
+
+    pub fn tick(&mut self, rank: Rank, last_page: bool) -> Tick {
+        if self.state != State::Playing {
+            return Tick::Wait
+        };
+        self.elapsed_ticks += 1;
+        let dwell_ticks = (self.dwell_time(rank) * 1000.0 / TICK_MILLIS as f64).round() as u64;
+        if self.elapsed_ticks < dwell_ticks {
+            Tick::Wait
+        } else {
+            self.elapsed_ticks = 0;
+            if last_page && !self.looping {
+                self.state = State::Stopped;
+                Tick::Stop
+            } else {
+                Tick::Advance
+            }
+        }
+    }
+}
+
+impl fmt::Display for Slideshow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}s{}",
+            match self.state {
+                State::Playing => "▶",
+                State::Paused => "⏸",
+                State::Stopped => "⏹",
+            },
+            format!("{:.1}", self.interval).trim_end_matches(".0"),
+            if self.looping { "" } else { "|" })
+    }
+}
+
+// parses a number of seconds, as in --timer 2.5
+pub fn parse_seconds(s: &str) -> Result<f64, String> {
+    let seconds = s.trim().parse::<f64>().map_err(|e| e.to_string())?;
+    if !seconds.is_finite() || seconds <= 0.0 {
+        return Err(String::from("seconds must be a positive number"))
+    };
+    Ok(seconds)
+}
+
+// parses STARS=SECONDS, as in --dwell 3=10
+pub fn parse_dwell(s: &str) -> Result<(usize, f64), String> {
+    match s.split_once('=') {
+        Some((stars, seconds)) => {
+            let stars = stars.trim().parse::<usize>().map_err(|e| e.to_string())?;
+            if stars > 3 {
+                return Err(String::from("stars must be between 0 and 3"))
+            };
+            Ok((stars, parse_seconds(seconds)?))
+        },
+        None => Err(String::from("expected STARS=SECONDS")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ticks_until_advance(slideshow: &mut Slideshow, rank: Rank) -> usize {
+        let mut ticks = 1;
+        while slideshow.tick(rank, false) == Tick::Wait {
+            ticks += 1
+        };
+        ticks
+    }
+
+    #[test]
+    fn fractional_intervals_and_dwell_times_are_counted_in_ticks() {
+        let mut slideshow = Slideshow::new(0.5, true, &[(3, 2.0)]);
+        assert_eq!(5, ticks_until_advance(&mut slideshow, Rank::NoStar));
+        assert_eq!(20, ticks_until_advance(&mut slideshow, Rank::ThreeStars));
+    }
+
+    #[test]
+    fn paused_slideshow_never_advances() {
+        let mut slideshow = Slideshow::new(0.1, true, &[]);
+        slideshow.toggle_pause();
+        for _ in 0..10 {
+            assert_eq!(Tick::Wait, slideshow.tick(Rank::NoStar, false))
+        };
+        slideshow.toggle_pause();
+        assert_eq!(Tick::Advance, slideshow.tick(Rank::NoStar, false));
+    }
+
+    #[test]
+    fn slideshow_stops_at_the_last_page_unless_looping() {
+        let mut looping = Slideshow::new(0.1, true, &[]);
+        assert_eq!(Tick::Advance, looping.tick(Rank::NoStar, true));
+        let mut once = Slideshow::new(0.1, false, &[]);
+        assert_eq!(Tick::Stop, once.tick(Rank::NoStar, true));
+        assert_eq!(State::Stopped, once.state());
+        assert_eq!(Tick::Wait, once.tick(Rank::NoStar, true));
+    }
+
+    #[test]
+    fn faster_and_slower_keep_the_interval_in_range() {
+        let mut slideshow = Slideshow::new(0.15, true, &[(2, 3.0)]);
+        slideshow.faster();
+        slideshow.faster();
+        assert_eq!(MIN_INTERVAL, slideshow.interval());
+        slideshow.slower();
+        assert!(slideshow.dwell_time(Rank::TwoStars) < 3.0);
+        assert_eq!("▶0.1s", Slideshow::new(0.1, true, &[]).to_string());
+        assert_eq!("▶2s|", Slideshow::new(2.0, false, &[]).to_string());
+    }
+
+    #[test]
+    fn dwell_is_parsed_as_stars_and_seconds() {
+        assert_eq!(Ok((3, 2.5)), parse_dwell("3=2.5"));
+        assert!(parse_dwell("4=1").is_err());
+        assert!(parse_dwell("3").is_err());
+        assert!(parse_dwell("3=nan").is_err());
+    }
+
+    #[test]
+    fn seconds_must_be_a_positive_number() {
+        assert_eq!(Ok(0.5), parse_seconds("0.5"));
+        assert!(parse_seconds("0").is_err());
+        assert!(parse_seconds("nan").is_err());
+        assert!(parse_seconds("inf").is_err());
+    }
+}
@@ -0,0 +1,218 @@
+use crate::entry::{Entry, EntryList};
+use crate::rank::Rank;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+const SIZE_BUCKETS: [(u64, &str); 4] = [
+    (100_000, "< 100 KB"),
+    (1_000_000, "< 1 MB"),
+    (10_000_000, "< 10 MB"),
+    (u64::MAX, ">= 10 MB"),
+];
+
+const COLORS_BUCKETS: [(usize, &str); 4] = [
+    (256, "< 256 colors"),
+    (4_096, "< 4096 colors"),
+    (65_536, "< 65536 colors"),
+    (usize::MAX, ">= 65536 colors"),
+];
+
+const NO_LABEL: &str = "(no label)";
+
+// counts of a group of entries
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct Group {
+    pub name: String,
+    pub count: usize,
+    pub size: u64,
+    pub selected: usize,
+    pub ranks: [usize; 4],
+}
+
+impl Group {
+    fn new(name: &str) -> Self {
+        Group { name: name.to_string(), ..Default::default() }
+    }
+
+    fn add(&mut self, entry: &Entry) {
+        self.count += 1;
+        self.size += entry.file_size;
+        if entry.is_selected() {
+            self.selected += 1
+        };
+        self.ranks[entry.image_data.rank.stars()] += 1
+    }
+
+    pub fn show(&self) -> String {
+        format!("{:<24} {:>6} {:>10}  selected {:>5}  ☆☆☆ {:>5}  ☆☆ {:>5}  ☆ {:>5}  _ {:>5}",
+            self.name,
+            self.count,
+            human_size(self.size),
+            self.selected,
+            self.ranks[3], self.ranks[2], self.ranks[1], self.ranks[0])
+    }
+}
+
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct Statistics {
+    pub total: Group,
+    pub by_rank: Vec<Group>,
+    pub by_label: Vec<Group>,
+    pub by_selection: Vec<Group>,
+    pub by_directory: Vec<Group>,
+    pub by_size: Vec<Group>,
+    pub by_colors: Vec<Group>,
+}
+
+pub fn human_size(size: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = size as f64;
+    let mut unit = 0;
+    while value >= 1000.0 && unit < UNITS.len() - 1 {
+        value /= 1000.0;
+        unit += 1
+    };
+    if unit == 0 {
+        format!("{} {}", size, UNITS[0])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+fn groups_by<F: Fn(&Entry) -> String>(entry_list: &EntryList, key: F) -> Vec<Group> {
+    let mut groups: BTreeMap<String, Group> = BTreeMap::new();
+    for entry in entry_list {
+        let name = key(entry);
+        groups.entry(name.clone()).or_insert(Group::new(&name)).add(entry)
+    };
+    groups.into_values().collect()
+}
+
+fn groups_by_bucket<T: Copy + PartialOrd, F: Fn(&Entry) -> T>(entry_list: &EntryList, buckets: &[(T, &str)], value: F) -> Vec<Group> {
+    let mut groups: Vec<Group> = buckets.iter().map(|(_, name)| Group::new(name)).collect();
+    for entry in entry_list {
+        let v = value(entry);
+        if let Some(i) = buckets.iter().position(|(limit, _)| v < *limit) {
+            groups[i].add(entry)
+        } else {
+            groups[buckets.len()-1].add(entry)
+        }
+    };
+    groups.into_iter().filter(|g| g.count > 0).collect()
+}
+
+// the directory of a picture relative to the gallery, so that directories with the same name stay apart
+fn relative_directory(entry: &Entry, gallery: &Path) -> String {
+    let path = entry.original_file_path();
+    let parent = path.parent().unwrap_or(Path::new(""));
+    let relative = parent.strip_prefix(gallery).unwrap_or(parent);
+    if relative.as_os_str().is_empty() {
+        String::from(".")
+    } else {
+        relative.to_string_lossy().to_string()
+    }
+}
+
+impl Statistics {
+    pub fn from_entries(entry_list: &EntryList, gallery: &Path) -> Self {
+        let mut total = Group::new("total");
+        entry_list.iter().for_each(|entry| total.add(entry));
+        let by_rank = [Rank::ThreeStars, Rank::TwoStars, Rank::OneStar, Rank::NoStar].iter()
+            .map(|rank| {
+                let mut group = Group::new(&rank.to_string());
+                entry_list.iter().filter(|e| e.image_data.rank == *rank).for_each(|e| group.add(e));
+                group
+            })
+            .filter(|g| g.count > 0)
+            .collect();
+        Statistics {
+            total: total,
+            by_rank: by_rank,
+            by_label: groups_by(entry_list, |e| e.image_data.label().unwrap_or(String::from(NO_LABEL))),
+            by_selection: groups_by(entry_list, |e| String::from(if e.is_selected() { "selected" } else { "unselected" })),
+            by_directory: groups_by(entry_list, |e| relative_directory(e, gallery)),
+            by_size: groups_by_bucket(entry_list, &SIZE_BUCKETS, |e| e.file_size),
+            by_colors: groups_by_bucket(entry_list, &COLORS_BUCKETS, |e| e.image_data.colors),
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut lines: Vec<String> = vec![self.total.show()];
+        for (title, groups) in [
+            ("rank", &self.by_rank),
+            ("label", &self.by_label),
+            ("selection", &self.by_selection),
+            ("directory", &self.by_directory),
+            ("size", &self.by_size),
+            ("colors", &self.by_colors)] {
+            lines.push(String::new());
+            lines.push(format!("by {}", title));
+            groups.iter().for_each(|g| lines.push(g.show()))
+        };
+        lines.join("\n")
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entry::{example_entries, make_entry};
+
+    // the statistics of the example pictures, one of them selected, one labelled and one big
+    fn example_statistics() -> Statistics {
+        let mut entries = example_entries();
+        entries[1].set_select(true);
+        entries[2].set_label(&String::from("print"));
+        entries[3].file_size = 2_000_000;
+        entries[3].image_data.colors = 70000;
+        Statistics::from_entries(&entries, Path::new("photos"))
+    }
+
+    #[test]
+    fn total_counts_all_entries() {
+        let statistics = example_statistics();
+        assert_eq!(4, statistics.total.count);
+        assert_eq!(2_001_110, statistics.total.size);
+        assert_eq!(1, statistics.total.selected);
+        assert_eq!([1, 1, 1, 1], statistics.total.ranks);
+    }
+
+    #[test]
+    fn groups_split_entries_by_rank_label_and_directory() {
+        let statistics = example_statistics();
+        assert_eq!(vec!["☆☆☆", "☆☆", "☆", "_"], statistics.by_rank.iter().map(|g| g.name.as_str()).collect::<Vec<_>>());
+        assert_eq!(vec!["(no label)", "print"], statistics.by_label.iter().map(|g| g.name.as_str()).collect::<Vec<_>>());
+        let photos = &statistics.by_directory[0];
+        assert_eq!((".", 4, 1), (photos.name.as_str(), photos.count, photos.ranks[3]));
+    }
+
+    #[test]
+    fn directories_are_grouped_by_their_path_in_the_gallery() {
+        let day = std::time::SystemTime::now();
+        let entries = vec!(
+            make_entry(String::from("photos/2023/trips/foo.jpeg"), 100, 5, day, Rank::NoStar),
+            make_entry(String::from("photos/2024/trips/bar.jpeg"), 100, 5, day, Rank::NoStar),
+            make_entry(String::from("photos/2024/trips/qux.jpeg"), 100, 5, day, Rank::NoStar));
+        let statistics = Statistics::from_entries(&entries, Path::new("photos"));
+        let directories: Vec<(&str, usize)> = statistics.by_directory.iter().map(|g| (g.name.as_str(), g.count)).collect();
+        assert_eq!(vec![("2023/trips", 1), ("2024/trips", 2)], directories);
+    }
+
+    #[test]
+    fn buckets_are_given_in_increasing_order_and_empty_ones_are_left_out() {
+        let statistics = example_statistics();
+        assert_eq!(vec!["< 100 KB", "< 10 MB"], statistics.by_size.iter().map(|g| g.name.as_str()).collect::<Vec<_>>());
+        assert_eq!(vec!["< 256 colors", ">= 65536 colors"], statistics.by_colors.iter().map(|g| g.name.as_str()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn sizes_are_shown_with_units() {
+        assert_eq!("512 B", human_size(512));
+        assert_eq!("2.0 MB", human_size(2_000_000));
+    }
+}
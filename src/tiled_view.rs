@@ -0,0 +1,143 @@
+use crate::entry::Entry;
+use crate::tiles::{TilePyramid, are_tiles_complete, build_tiles, finest_level, large_picture_pyramid, tile_file_path, tiles_directory};
+use gtk::cairo::Context;
+use gtk::gdk::prelude::GdkCairoContextExt;
+use gtk::gdk_pixbuf::Pixbuf;
+use gtk::glib;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::thread;
+
+// tiles kept in memory before the cache is emptied
+const MAX_LOADED_TILES: usize = 256;
+
+pub enum TilesMessage {
+    Level,
+    Done(PathBuf),
+    Failed(PathBuf, String),
+}
+
+pub struct TiledPicture {
+    pub file_path: PathBuf,
+    pub pyramid: TilePyramid,
+    // levels more detailed than that aren't built
+    finest_level: usize,
+    directory: PathBuf,
+    placeholder: Option<Pixbuf>,
+    loaded: HashMap<(usize, u32, u32), Pixbuf>,
+}
+
+// the tiled picture shown, and the pictures whose tiles are being built
+#[derive(Default)]
+pub struct TiledState {
+    pub current: Option<TiledPicture>,
+    building: HashSet<PathBuf>,
+}
+
+impl TiledPicture {
+    fn tile(&mut self, level: usize, col: u32, row: u32) -> Option<Pixbuf> {
+        if let Some(pixbuf) = self.loaded.get(&(level, col, row)) {
+            return Some(pixbuf.clone())
+        };
+        let pixbuf = Pixbuf::from_file(tile_file_path(&self.directory, level, col, row)).ok()?;
+        if self.loaded.len() >= MAX_LOADED_TILES {
+            self.loaded.clear()
+        };
+        self.loaded.insert((level, col, row), pixbuf.clone());
+        Some(pixbuf)
+    }
+}
+
+impl TiledState {
+    pub fn is_shown(&self, entry: &Entry) -> bool {
+        self.current.as_ref().map(|t| t.file_path == entry.original_file_path()).unwrap_or(false)
+    }
+
+    // sets the entry as the tiled picture if it's large enough, building its tiles in the background if needed
+    pub fn show(&mut self, entry: &Entry, sender: &glib::Sender<TilesMessage>) -> bool {
+        if self.is_shown(entry) {
+            return true
+        };
+        match large_picture_pyramid(entry) {
+            Some(pyramid) => {
+                let directory = tiles_directory(entry);
+                if !are_tiles_complete(&directory) && !self.building.contains(&directory) {
+                    self.building.insert(directory.clone());
+                    start_building(entry.original_file_path(), directory.clone(), sender.clone())
+                };
+                self.current = Some(TiledPicture {
+                    file_path: entry.original_file_path(),
+                    pyramid: pyramid,
+                    finest_level: finest_level(&entry.original_file_path(), &pyramid),
+                    directory: directory,
+                    placeholder: Pixbuf::from_file(entry.thumbnail_file_path()).ok(),
+                    loaded: HashMap::new(),
+                });
+                true
+            },
+            None => {
+                self.current = None;
+                false
+            },
+        }
+    }
+
+    pub fn built(&mut self, directory: &PathBuf) {
+        self.building.remove(directory);
+    }
+}
+
+fn start_building(file_path: PathBuf, directory: PathBuf, sender: glib::Sender<TilesMessage>) {
+    eprintln!("building tiles for {} in {}", file_path.display(), directory.display());
+    thread::spawn(move || {
+        let result = build_tiles(&file_path, &directory, |_| {
+            let _ = sender.send(TilesMessage::Level);
+        });
+        let _ = sender.send(match result {
+            Ok(()) => TilesMessage::Done(directory),
+            Err(err) => TilesMessage::Failed(directory, format!("can't build tiles for {}: {}", file_path.display(), err)),
+        });
+    });
+}
+
+// draws the part of the picture visible in the area: (x, y, width, height) in area coordinates;
+// the picture is fitted in the area, which is as large as the zoomed picture
+pub fn draw_tiled_picture(ctx: &Context, tiled: &mut TiledPicture, area: (f64, f64), visible: (f64, f64, f64, f64)) {
+    let (width, height) = area;
+    let pyramid = tiled.pyramid;
+    let scale = (width / pyramid.width as f64).min(height / pyramid.height as f64);
+    if scale <= 0.0 {
+        return
+    };
+    let origin_x = (width - pyramid.width as f64 * scale) / 2.0;
+    let origin_y = (height - pyramid.height as f64 * scale) / 2.0;
+    ctx.set_source_rgb(0.0, 0.0, 0.0);
+    let _ = ctx.paint();
+    if let Some(placeholder) = &tiled.placeholder {
+        draw_pixbuf(ctx, placeholder, origin_x, origin_y, pyramid.width as f64 * scale / placeholder.width() as f64);
+    };
+    let (visible_x, visible_y, visible_width, visible_height) = visible;
+    let level = pyramid.level_for_scale(scale).max(tiled.finest_level);
+    let tiles = pyramid.visible_tiles(level,
+        (visible_x - origin_x) / scale,
+        (visible_y - origin_y) / scale,
+        visible_width / scale,
+        visible_height / scale);
+    for (col, row) in tiles {
+        if let Some(pixbuf) = tiled.tile(level, col, row) {
+            let (x, y, tile_width, _) = pyramid.tile_rect(level, col, row);
+            draw_pixbuf(ctx, &pixbuf, origin_x + x * scale, origin_y + y * scale, tile_width * scale / pixbuf.width() as f64)
+        }
+    }
+}
+
+fn draw_pixbuf(ctx: &Context, pixbuf: &Pixbuf, x: f64, y: f64, scale: f64) {
+    if ctx.save().is_err() {
+        return
+    };
+    ctx.translate(x, y);
+    ctx.scale(scale, scale);
+    ctx.set_source_pixbuf(pixbuf, 0.0, 0.0);
+    let _ = ctx.paint();
+    let _ = ctx.restore();
+}
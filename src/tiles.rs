@@ -0,0 +1,357 @@
+use crate::entry::Entry;
+use crate::paths::cache_path;
+use image::{ImageFormat, ImageReader, RgbImage, image_dimensions};
+use std::collections::hash_map::DefaultHasher;
+use std::fs::{File, create_dir_all, write};
+use std::hash::{Hash, Hasher};
+use std::io::{BufReader, Result, Error, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+// pictures with at least that many pixels are shown from a tile pyramid in view mode
+pub const LARGE_PICTURE_PIXELS: u64 = 40_000_000;
+pub const TILE_SIZE: u32 = 512;
+// jpeg pictures larger than that are decoded at a reduced scale, the finer levels are left out
+const MAX_DECODED_PIXELS: u64 = 64_000_000;
+
+const TILES_DIR_NAME: &str = "tiles";
+const COMPLETE_MARKER: &str = "complete";
+
+// level 0 is the picture at full size, each level halves the previous one,
+// the last level fits in a single tile
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TilePyramid {
+    pub width: u32,
+    pub height: u32,
+}
+
+fn ceil_div(value: u32, divisor: u32) -> u32 {
+    (value + divisor - 1) / divisor
+}
+
+impl TilePyramid {
+    pub fn levels(&self) -> usize {
+        let mut levels = 1;
+        let (mut width, mut height) = (self.width, self.height);
+        while width > TILE_SIZE || height > TILE_SIZE {
+            width = ceil_div(width, 2);
+            height = ceil_div(height, 2);
+            levels += 1
+        };
+        levels
+    }
+
+    pub fn level_size(&self, level: usize) -> (u32, u32) {
+        let factor = 1 << level;
+        (ceil_div(self.width, factor), ceil_div(self.height, factor))
+    }
+
+    pub fn tiles(&self, level: usize) -> (u32, u32) {
+        let (width, height) = self.level_size(level);
+        (ceil_div(width, TILE_SIZE), ceil_div(height, TILE_SIZE))
+    }
+
+    // the smallest level still at least as detailed as the scale shown
+    pub fn level_for_scale(&self, scale: f64) -> usize {
+        if scale >= 1.0 || scale <= 0.0 {
+            return 0
+        };
+        let level = (1.0 / scale).log2().floor() as usize;
+        level.min(self.levels() - 1)
+    }
+
+    // tile rectangle in full size picture coordinates: x, y, width, height
+    pub fn tile_rect(&self, level: usize, col: u32, row: u32) -> (f64, f64, f64, f64) {
+        let (level_width, level_height) = self.level_size(level);
+        let factor = (1 << level) as f64;
+        let x = col * TILE_SIZE;
+        let y = row * TILE_SIZE;
+        let width = TILE_SIZE.min(level_width - x);
+        let height = TILE_SIZE.min(level_height - y);
+        (x as f64 * factor, y as f64 * factor, width as f64 * factor, height as f64 * factor)
+    }
+
+    // tiles of a level intersecting a rectangle given in full size picture coordinates
+    pub fn visible_tiles(&self, level: usize, x: f64, y: f64, width: f64, height: f64) -> Vec<(u32, u32)> {
+        let (cols, rows) = self.tiles(level);
+        let span = (TILE_SIZE as f64) * (1 << level) as f64;
+        let first_col = (x.max(0.0) / span).floor() as u32;
+        let first_row = (y.max(0.0) / span).floor() as u32;
+        let last_col = (((x + width) / span).ceil() as u32).min(cols);
+        let last_row = (((y + height) / span).ceil() as u32).min(rows);
+        let mut tiles = Vec::new();
+        for row in first_row..last_row {
+            for col in first_col..last_col {
+                tiles.push((col, row))
+            }
+        };
+        tiles
+    }
+}
+
+// only reads the picture header
+pub fn large_picture_pyramid(entry: &Entry) -> Option<TilePyramid> {
+    match image_dimensions(entry.original_file_path()) {
+        Ok((width, height)) if width as u64 * height as u64 >= LARGE_PICTURE_PIXELS => Some(TilePyramid { width: width, height: height }),
+        _ => None,
+    }
+}
+
+// tiles are cached by path, size and date, so that a changed picture gets new tiles
+pub fn tiles_directory(entry: &Entry) -> PathBuf {
+    let mut hasher = DefaultHasher::new();
+    entry.original_file_path().hash(&mut hasher);
+    entry.file_size.hash(&mut hasher);
+    entry.modified_time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0).hash(&mut hasher);
+    cache_path().join(TILES_DIR_NAME).join(format!("{:016x}", hasher.finish()))
+}
+
+pub fn tile_file_path(directory: &Path, level: usize, col: u32, row: u32) -> PathBuf {
+    directory.join(level.to_string()).join(format!("{}_{}.jpeg", col, row))
+}
+
+pub fn are_tiles_complete(directory: &Path) -> bool {
+    directory.join(COMPLETE_MARKER).exists()
+}
+
+fn format_error<E: std::error::Error + Send + Sync + 'static>(err: E) -> Error {
+    Error::new(ErrorKind::Other, err)
+}
+
+fn picture_format(file_path: &Path) -> Option<ImageFormat> {
+    ImageReader::open(file_path).and_then(|reader| reader.with_guessed_format()).ok().and_then(|reader| reader.format())
+}
+
+// the most detailed level built for the picture
+pub fn finest_level(file_path: &Path, pyramid: &TilePyramid) -> usize {
+    if picture_format(file_path) != Some(ImageFormat::Jpeg) {
+        return 0
+    };
+    (0..pyramid.levels())
+        .find(|&level| {
+            let (width, height) = pyramid.level_size(level);
+            width as u64 * height as u64 <= MAX_DECODED_PIXELS
+        })
+        .unwrap_or(pyramid.levels() - 1)
+}
+
+// the rows of a level not yet written as a row of tiles
+struct Band {
+    rows: Vec<u8>,
+    count: u32,
+    tile_row: u32,
+    // a row waiting for the next one to be halved into the next level
+    pending: Option<Vec<u8>>,
+}
+
+// writes the tiles of every level from the rows of the picture, given from top to bottom;
+// only a band of TILE_SIZE rows by level is kept in memory
+struct PyramidWriter<'a, F: FnMut(usize)> {
+    pyramid: TilePyramid,
+    directory: &'a Path,
+    bands: Vec<Band>,
+    tiles_done: F,
+}
+
+// averages two rows of rgb pixels by pairs of pixels
+fn halve_rows(upper: &[u8], lower: &[u8]) -> Vec<u8> {
+    let width = upper.len() / 3;
+    let mut halved = Vec::with_capacity((width + 1) / 2 * 3);
+    for x in (0..width).step_by(2) {
+        let right = (x + 1).min(width - 1);
+        for channel in 0..3 {
+            let sum = upper[x*3+channel] as u32 + upper[right*3+channel] as u32
+                + lower[x*3+channel] as u32 + lower[right*3+channel] as u32;
+            halved.push(((sum + 2) / 4) as u8)
+        }
+    };
+    halved
+}
+
+impl<'a, F: FnMut(usize)> PyramidWriter<'a, F> {
+    fn new(pyramid: TilePyramid, directory: &'a Path, tiles_done: F) -> Result<Self> {
+        for level in 0..pyramid.levels() {
+            create_dir_all(directory.join(level.to_string()))?
+        };
+        let bands = (0..pyramid.levels()).map(|_| Band { rows: Vec::new(), count: 0, tile_row: 0, pending: None }).collect();
+        Ok(PyramidWriter { pyramid: pyramid, directory: directory, bands: bands, tiles_done: tiles_done })
+    }
+
+    fn push_row(&mut self, level: usize, row: Vec<u8>) -> Result<()> {
+        if level + 1 < self.pyramid.levels() {
+            match self.bands[level].pending.take() {
+                Some(upper) => self.push_row(level + 1, halve_rows(&upper, &row))?,
+                None => self.bands[level].pending = Some(row.clone()),
+            }
+        };
+        let band = &mut self.bands[level];
+        band.rows.extend_from_slice(&row);
+        band.count += 1;
+        if band.count == TILE_SIZE {
+            self.write_band(level)?
+        };
+        Ok(())
+    }
+
+    fn write_band(&mut self, level: usize) -> Result<()> {
+        let (width, _) = self.pyramid.level_size(level);
+        let (cols, _) = self.pyramid.tiles(level);
+        let band = &mut self.bands[level];
+        for col in 0..cols {
+            let x = (col * TILE_SIZE) as usize;
+            let tile_width = TILE_SIZE.min(width - col * TILE_SIZE) as usize;
+            let mut pixels = Vec::with_capacity(tile_width * band.count as usize * 3);
+            for line in band.rows.chunks_exact(width as usize * 3) {
+                pixels.extend_from_slice(&line[x*3..(x + tile_width)*3])
+            };
+            RgbImage::from_raw(tile_width as u32, band.count, pixels)
+                .ok_or(Error::new(ErrorKind::Other, "tile size mismatch"))?
+                .save_with_format(tile_file_path(self.directory, level, col, band.tile_row), ImageFormat::Jpeg)
+                .map_err(format_error)?
+        };
+        band.rows.clear();
+        band.count = 0;
+        band.tile_row += 1;
+        (self.tiles_done)(level);
+        Ok(())
+    }
+
+    // a last odd row is halved with itself, then the partial bands are written
+    fn finish(mut self) -> Result<()> {
+        for level in 0..self.pyramid.levels() {
+            if let Some(upper) = self.bands[level].pending.take() {
+                if level + 1 < self.pyramid.levels() {
+                    self.push_row(level + 1, halve_rows(&upper, &upper))?
+                }
+            };
+            if self.bands[level].count > 0 {
+                self.write_band(level)?
+            }
+        };
+        Ok(())
+    }
+}
+
+fn push_rgb_rows<F: FnMut(usize)>(writer: &mut PyramidWriter<F>, level: usize, picture: &RgbImage) -> Result<()> {
+    for line in picture.as_raw().chunks_exact(picture.width() as usize * 3) {
+        writer.push_row(level, line.to_vec())?
+    };
+    Ok(())
+}
+
+// png rows are read one at a time
+fn push_png_rows<F: FnMut(usize)>(writer: &mut PyramidWriter<F>, file_path: &Path) -> Result<bool> {
+    let mut decoder = png::Decoder::new(BufReader::new(File::open(file_path)?));
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    decoder.set_limits(png::Limits { bytes: usize::MAX });
+    let mut reader = decoder.read_info().map_err(format_error)?;
+    if reader.info().interlaced {
+        return Ok(false)
+    };
+    let (color_type, _) = reader.output_color_type();
+    while let Some(row) = reader.next_row().map_err(format_error)? {
+        let rgb: Vec<u8> = match color_type {
+            png::ColorType::Grayscale => row.data().iter().flat_map(|&l| [l, l, l]).collect(),
+            png::ColorType::GrayscaleAlpha => row.data().chunks_exact(2).flat_map(|p| [p[0], p[0], p[0]]).collect(),
+            png::ColorType::Rgba => row.data().chunks_exact(4).flat_map(|p| [p[0], p[1], p[2]]).collect(),
+            _ => row.data().to_vec(),
+        };
+        writer.push_row(0, rgb)?
+    };
+    Ok(true)
+}
+
+// jpeg pictures are scaled down while decoding, to the finest level built
+fn decode_jpeg(file_path: &Path, pyramid: &TilePyramid, level: usize) -> Result<RgbImage> {
+    let mut decoder = jpeg_decoder::Decoder::new(BufReader::new(File::open(file_path)?));
+    let (width, height) = pyramid.level_size(level);
+    decoder.scale(width.min(u16::MAX as u32) as u16, height.min(u16::MAX as u32) as u16).map_err(format_error)?;
+    let pixels = decoder.decode().map_err(format_error)?;
+    let info = decoder.info().ok_or(Error::new(ErrorKind::Other, "no jpeg header"))?;
+    let rgb: Vec<u8> = match info.pixel_format {
+        jpeg_decoder::PixelFormat::L8 => pixels.iter().flat_map(|&l| [l, l, l]).collect(),
+        jpeg_decoder::PixelFormat::L16 => pixels.chunks_exact(2).map(|l| u16::from_ne_bytes([l[0], l[1]]) >> 8).flat_map(|l| [l as u8, l as u8, l as u8]).collect(),
+        jpeg_decoder::PixelFormat::CMYK32 => pixels.chunks_exact(4).flat_map(|p| {
+            let k = 255 - p[3] as u16;
+            [(255 - p[0] as u16) * k / 255, (255 - p[1] as u16) * k / 255, (255 - p[2] as u16) * k / 255].map(|c| c as u8)
+        }).collect(),
+        jpeg_decoder::PixelFormat::RGB24 => pixels,
+    };
+    let picture = RgbImage::from_raw(info.width as u32, info.height as u32, rgb)
+        .ok_or(Error::new(ErrorKind::Other, "jpeg size mismatch"))?;
+    if picture.dimensions() == (width, height) {
+        Ok(picture)
+    } else {
+        Ok(image::imageops::thumbnail(&picture, width, height))
+    }
+}
+
+// reads the picture once and writes every level, calling tiles_done after each row of tiles;
+// png pictures are read by rows, jpeg pictures decoded at the finest level built,
+// other formats are decoded whole; slow, meant to run in a background thread
+pub fn build_tiles<F: FnMut(usize)>(file_path: &Path, directory: &Path, tiles_done: F) -> Result<()> {
+    let (width, height) = image_dimensions(file_path).map_err(format_error)?;
+    let pyramid = TilePyramid { width: width, height: height };
+    let mut writer = PyramidWriter::new(pyramid, directory, tiles_done)?;
+    let format = picture_format(file_path);
+    if format == Some(ImageFormat::Jpeg) {
+        let level = finest_level(file_path, &pyramid);
+        push_rgb_rows(&mut writer, level, &decode_jpeg(file_path, &pyramid, level)?)?
+    } else if format != Some(ImageFormat::Png) || !push_png_rows(&mut writer, file_path)? {
+        let mut reader = ImageReader::open(file_path)?.with_guessed_format()?;
+        reader.no_limits();
+        push_rgb_rows(&mut writer, 0, &reader.decode().map_err(format_error)?.into_rgb8())?
+    };
+    writer.finish()?;
+    write(directory.join(COMPLETE_MARKER), "")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::paths::test_directory;
+
+    const PYRAMID: TilePyramid = TilePyramid { width: 2000, height: 1000 };
+
+    #[test]
+    fn levels_halve_the_picture_until_it_fits_in_a_tile() {
+        assert_eq!(3, PYRAMID.levels());
+        assert_eq!((1000, 500), PYRAMID.level_size(1));
+        assert_eq!((500, 250), PYRAMID.level_size(2));
+        assert_eq!((4, 2), PYRAMID.tiles(0));
+        assert_eq!((1, 1), PYRAMID.tiles(2));
+    }
+
+    #[test]
+    fn level_is_chosen_from_the_scale_shown() {
+        assert_eq!(0, PYRAMID.level_for_scale(2.0));
+        assert_eq!(0, PYRAMID.level_for_scale(0.6));
+        assert_eq!(1, PYRAMID.level_for_scale(0.5));
+        assert_eq!(2, PYRAMID.level_for_scale(0.01));
+    }
+
+    #[test]
+    fn only_tiles_in_the_visible_rectangle_are_listed() {
+        assert_eq!(vec![(1, 0), (2, 0)], PYRAMID.visible_tiles(0, 600.0, 100.0, 800.0, 200.0));
+        assert_eq!(vec![(0, 0)], PYRAMID.visible_tiles(2, 0.0, 0.0, 2000.0, 1000.0));
+        assert_eq!((1536.0, 512.0, 464.0, 488.0), PYRAMID.tile_rect(0, 3, 1));
+    }
+
+    #[test]
+    fn tiles_of_every_level_are_written_from_the_rows() {
+        let picture = RgbImage::from_fn(1100, 600, |x, y| image::Rgb([(x % 256) as u8, (y % 256) as u8, 128]));
+        let test_directory = test_directory("tiles");
+        let source = test_directory.join("source.png");
+        picture.save(&source).unwrap();
+        let directory = test_directory.join("tiles");
+        let mut bands = 0;
+        build_tiles(&source, &directory, |_| bands += 1).unwrap();
+        assert!(are_tiles_complete(&directory));
+        assert_eq!(4, bands);
+        assert_eq!((76, 88), image_dimensions(tile_file_path(&directory, 0, 2, 1)).unwrap());
+        assert_eq!((38, 300), image_dimensions(tile_file_path(&directory, 1, 1, 0)).unwrap());
+        assert_eq!(0, finest_level(&source, &TilePyramid { width: 1100, height: 600 }));
+        let _ = std::fs::remove_dir_all(&test_directory);
+    }
+}
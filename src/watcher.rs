@@ -0,0 +1,197 @@
+use crate::picture_io::is_picture_file;
+use notify::event::{ModifyKind, RenameMode};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use regex::Regex;
+use std::collections::HashMap;
+use std::io::{Result, Error, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver};
+use std::time::{Duration, Instant};
+use walkdir::WalkDir;
+
+// a file is reported once no event came for it for that long, so that files being copied are complete
+const SETTLE_MILLIS: u64 = 500;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FileChange {
+    Added, Removed, Modified,
+}
+
+// what two changes in a row amount to; none when the file came and went
+pub fn merge_changes(previous: FileChange, next: FileChange) -> Option<FileChange> {
+    match (previous, next) {
+        (FileChange::Added, FileChange::Removed) => None,
+        (FileChange::Added, _) => Some(FileChange::Added),
+        (_, FileChange::Removed) => Some(FileChange::Removed),
+        // replaced, as editors do when saving
+        (FileChange::Removed, _) => Some(FileChange::Modified),
+        (FileChange::Modified, _) => Some(FileChange::Modified),
+    }
+}
+
+fn event_changes(event: Event) -> Vec<(PathBuf, FileChange)> {
+    let change = match event.kind {
+        EventKind::Create(_) => FileChange::Added,
+        EventKind::Remove(_) => FileChange::Removed,
+        EventKind::Modify(ModifyKind::Name(RenameMode::From)) => FileChange::Removed,
+        EventKind::Modify(ModifyKind::Name(RenameMode::To)) => FileChange::Added,
+        EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if event.paths.len() == 2 => {
+            return vec![(event.paths[0].clone(), FileChange::Removed), (event.paths[1].clone(), FileChange::Added)]
+        },
+        EventKind::Modify(ModifyKind::Name(_)) => {
+            return event.paths.into_iter()
+                .map(|path| { let change = if path.exists() { FileChange::Added } else { FileChange::Removed }; (path, change) })
+                .collect()
+        },
+        EventKind::Modify(ModifyKind::Data(_)) | EventKind::Modify(ModifyKind::Any) => FileChange::Modified,
+        _ => return vec![],
+    };
+    event.paths.into_iter().map(|path| (path, change)).collect()
+}
+
+fn add_pending(pending: &mut HashMap<PathBuf, (FileChange, Instant)>, path: PathBuf, change: FileChange, now: Instant) {
+    let merged = match pending.get(&path) {
+        Some((previous, _)) => merge_changes(*previous, change),
+        None => Some(change),
+    };
+    match merged {
+        Some(change) => { pending.insert(path, (change, now)); },
+        None => { pending.remove(&path); },
+    }
+}
+
+fn settled_changes(pending: &mut HashMap<PathBuf, (FileChange, Instant)>, now: Instant) -> Vec<(PathBuf, FileChange)> {
+    let settled: Vec<PathBuf> = pending.iter()
+        .filter(|(_, (_, time))| now.duration_since(*time) >= Duration::from_millis(SETTLE_MILLIS))
+        .map(|(path, _)| path.clone())
+        .collect();
+    let mut changes: Vec<(PathBuf, FileChange)> = settled.into_iter()
+        .filter_map(|path| pending.remove(&path).map(|(change, _)| (path, change)))
+        .collect();
+    changes.sort();
+    changes
+}
+
+fn is_watched(path: &Path, pattern: Option<&Regex>) -> bool {
+    is_picture_file(path) && match pattern {
+        Some(pattern) => pattern.is_match(&path.to_string_lossy()),
+        None => true,
+    }
+}
+
+// a directory moved in brings its pictures; a path removed that isn't a picture may be a directory,
+// and the pictures under it go with it
+fn watched_changes(path: PathBuf, change: FileChange, pattern: Option<&Regex>) -> Vec<(PathBuf, FileChange)> {
+    if change == FileChange::Added && path.is_dir() {
+        WalkDir::new(&path).into_iter()
+            .filter_map(|e| e.ok())
+            .map(|e| e.into_path())
+            .filter(|path| is_watched(path, pattern))
+            .map(|path| (path, FileChange::Added))
+            .collect()
+    } else if is_watched(&path, pattern) || (change == FileChange::Removed && !is_picture_file(&path)) {
+        vec![(path, change)]
+    } else {
+        vec![]
+    }
+}
+
+// pictures added to, removed from or changed in the gallery directory and its subdirectories
+pub struct DirectoryWatcher {
+    _watcher: RecommendedWatcher,
+    directory: PathBuf,
+    absolute: PathBuf,
+    receiver: Receiver<notify::Result<Event>>,
+    pattern: Option<Regex>,
+    pending: HashMap<PathBuf, (FileChange, Instant)>,
+}
+
+impl DirectoryWatcher {
+    pub fn new(directory: &Path, pattern: Option<String>) -> Result<Self> {
+        let pattern = match pattern {
+            Some(pattern) => Some(Regex::new(&pattern).map_err(|err| Error::new(ErrorKind::Other, err))?),
+            None => None,
+        };
+        let (sender, receiver) = channel();
+        let mut watcher = notify::recommended_watcher(sender)
+            .map_err(|err| Error::new(ErrorKind::Other, format!("can't watch {}: {}", directory.display(), err)))?;
+        watcher.watch(directory, RecursiveMode::Recursive)
+            .map_err(|err| Error::new(ErrorKind::Other, format!("can't watch {}: {}", directory.display(), err)))?;
+        let absolute = if directory.is_absolute() { directory.to_path_buf() } else { std::env::current_dir()?.join(directory) };
+        Ok(DirectoryWatcher {
+            _watcher: watcher,
+            directory: directory.to_path_buf(),
+            absolute: absolute,
+            receiver: receiver,
+            pattern: pattern,
+            pending: HashMap::new(),
+        })
+    }
+
+    // events come with absolute paths, entries have paths under the directory as given
+    fn gallery_path(&self, path: PathBuf) -> PathBuf {
+        match path.strip_prefix(&self.absolute) {
+            Ok(relative) => self.directory.join(relative),
+            Err(_) => path,
+        }
+    }
+
+    // the changes settled since the last call, one per file
+    pub fn changes(&mut self) -> Vec<(PathBuf, FileChange)> {
+        let now = Instant::now();
+        while let Ok(result) = self.receiver.try_recv() {
+            match result {
+                Ok(event) => for (path, change) in event_changes(event) {
+                    let path = self.gallery_path(path);
+                    for (path, change) in watched_changes(path, change, self.pattern.as_ref()) {
+                        add_pending(&mut self.pending, path, change, now)
+                    }
+                },
+                Err(err) => eprintln!("watch error: {}", err),
+            }
+        };
+        settled_changes(&mut self.pending, now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::paths::test_directory;
+
+    #[test]
+    fn changes_in_a_row_are_merged() {
+        assert_eq!(None, merge_changes(FileChange::Added, FileChange::Removed));
+        assert_eq!(Some(FileChange::Added), merge_changes(FileChange::Added, FileChange::Modified));
+        assert_eq!(Some(FileChange::Modified), merge_changes(FileChange::Removed, FileChange::Added));
+        assert_eq!(Some(FileChange::Removed), merge_changes(FileChange::Modified, FileChange::Removed));
+    }
+
+    #[test]
+    fn changes_are_reported_once_settled() {
+        let mut pending = HashMap::new();
+        let start = Instant::now();
+        add_pending(&mut pending, PathBuf::from("photos/foo.jpeg"), FileChange::Added, start);
+        add_pending(&mut pending, PathBuf::from("photos/foo.jpeg"), FileChange::Modified, start);
+        add_pending(&mut pending, PathBuf::from("photos/bar.jpeg"), FileChange::Added, start);
+        add_pending(&mut pending, PathBuf::from("photos/bar.jpeg"), FileChange::Removed, start);
+        assert!(settled_changes(&mut pending, start).is_empty());
+        let later = start + Duration::from_millis(SETTLE_MILLIS);
+        assert_eq!(vec![(PathBuf::from("photos/foo.jpeg"), FileChange::Added)], settled_changes(&mut pending, later));
+        assert!(pending.is_empty());
+    }
+
+    #[test]
+    fn a_directory_moved_in_brings_its_pictures_and_one_removed_takes_them() {
+        let directory = test_directory("watched_directory");
+        let trips = directory.join("trips");
+        std::fs::create_dir_all(trips.join("2024")).unwrap();
+        std::fs::write(trips.join("2024").join("foo.jpeg"), b"").unwrap();
+        std::fs::write(trips.join("2024").join("fooTHUMB.jpeg"), b"").unwrap();
+        std::fs::write(trips.join("notes.txt"), b"").unwrap();
+        assert_eq!(vec![(trips.join("2024").join("foo.jpeg"), FileChange::Added)], watched_changes(trips.clone(), FileChange::Added, None));
+        assert_eq!(vec![(trips.clone(), FileChange::Removed)], watched_changes(trips.clone(), FileChange::Removed, None));
+        assert!(watched_changes(trips.clone(), FileChange::Modified, None).is_empty());
+        let _ = std::fs::remove_dir_all(&directory);
+    }
+}
@@ -0,0 +1,159 @@
+use crate::entry::{Entry, EntryList};
+use rand::Rng;
+use rand::distributions::{Distribution, WeightedIndex};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+// weights by number of stars: no star, ☆, ☆☆, ☆☆☆
+pub const DEFAULT_WEIGHTS: [f64; 4] = [1.0, 2.0, 4.0, 8.0];
+
+// a picture shown less than this number of displays ago has its weight reduced in proportion
+const RECENCY_WINDOW: u64 = 50;
+
+#[derive(Clone, Debug)]
+pub struct Weighting {
+    weights: [f64; 4],
+    clock: u64,
+    last_shown: HashMap<PathBuf, u64>,
+}
+
+impl Default for Weighting {
+    fn default() -> Self {
+        Weighting::new(DEFAULT_WEIGHTS)
+    }
+}
+
+impl Weighting {
+    pub fn new(weights: [f64; 4]) -> Self {
+        Weighting {
+            weights: weights,
+            clock: 0,
+            last_shown: HashMap::new(),
+        }
+    }
+
+    fn recency_factor(&self, entry: &Entry) -> f64 {
+        match self.last_shown.get(&entry.original_file_path()) {
+            Some(shown) => {
+                let age = self.clock - shown;
+                (age as f64 / RECENCY_WINDOW as f64).min(1.0)
+            },
+            None => 1.0,
+        }
+    }
+
+    pub fn weight(&self, entry: &Entry) -> f64 {
+        self.weights[entry.image_data.rank.stars()] * self.recency_factor(entry)
+    }
+
+    // one display: every entry of the page counts as shown
+    pub fn record_shown(&mut self, entries: &[Entry]) {
+        self.clock += 1;
+        for entry in entries {
+            self.last_shown.insert(entry.original_file_path(), self.clock);
+        }
+    }
+
+    // falls back to a uniform pick when all the weights are zero
+    pub fn pick<R: Rng>(&self, entry_list: &EntryList, rng: &mut R) -> Option<usize> {
+        if entry_list.is_empty() {
+            return None
+        };
+        let weights: Vec<f64> = entry_list.iter().map(|e| self.weight(e)).collect();
+        match WeightedIndex::new(&weights) {
+            Ok(distribution) => Some(distribution.sample(rng)),
+            Err(_) => Some(rng.gen_range(0..entry_list.len())),
+        }
+    }
+
+    // weighted random order: each entry gets a key drawn from an exponential distribution of rate its weight,
+    // so that heavier entries tend to come first; zero weight entries come last
+    pub fn shuffle<R: Rng>(&self, entry_list: &mut EntryList, rng: &mut R) {
+        let mut keyed: Vec<(f64, Entry)> = entry_list.drain(..)
+            .map(|e| {
+                let weight = self.weight(&e);
+                let u: f64 = rng.gen_range(f64::EPSILON..1.0);
+                let key = if weight > 0.0 { -u.ln() / weight } else { f64::INFINITY };
+                (key, e)
+            })
+            .collect();
+        keyed.sort_by(|a, b| a.0.total_cmp(&b.0));
+        entry_list.extend(keyed.into_iter().map(|(_, e)| e));
+    }
+}
+
+// parses four comma separated weights, for no star up to three stars, as in --weights 1,2,4,8
+pub fn parse_weights(s: &str) -> Result<[f64; 4], String> {
+    let values: Vec<f64> = s.split(',')
+        .map(|v| v.trim().parse::<f64>().map_err(|e| e.to_string()))
+        .collect::<Result<Vec<f64>, String>>()?;
+    if values.len() != 4 {
+        return Err(String::from("expected four weights, for 0 to 3 stars"))
+    };
+    if values.iter().any(|v| *v < 0.0 || !v.is_finite()) {
+        return Err(String::from("weights can't be negative"))
+    };
+    if values.iter().all(|v| *v == 0.0) {
+        return Err(String::from("at least one weight must be positive"))
+    };
+    Ok([values[0], values[1], values[2], values[3]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entry::example_entries;
+    use crate::rank::Rank;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn weight_depends_on_rank() {
+        let weighting = Weighting::default();
+        let entries = example_entries();
+        assert_eq!(1.0, weighting.weight(&entries[0]));
+        assert_eq!(8.0, weighting.weight(&entries[1]));
+    }
+
+    #[test]
+    fn recently_shown_pictures_weigh_less() {
+        let mut weighting = Weighting::default();
+        let entries = example_entries();
+        weighting.record_shown(&entries[1..]);
+        assert_eq!(0.0, weighting.weight(&entries[1]));
+        for _ in 0..RECENCY_WINDOW / 2 {
+            weighting.record_shown(&[])
+        };
+        assert_eq!(4.0, weighting.weight(&entries[1]));
+    }
+
+    #[test]
+    fn zero_weight_pictures_are_never_picked_unless_all_are() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let entries = example_entries();
+        let weighting = Weighting::new([0.0, 0.0, 0.0, 1.0]);
+        for _ in 0..100 {
+            assert_eq!(Some(1), weighting.pick(&entries, &mut rng))
+        };
+        let mut weighting = Weighting::new([0.0, 0.0, 0.0, 1.0]);
+        weighting.record_shown(&entries);
+        assert!(weighting.pick(&entries, &mut rng).is_some());
+    }
+
+    #[test]
+    fn weighted_shuffle_puts_zero_weights_last() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut entries = example_entries();
+        Weighting::new([0.0, 0.0, 0.0, 1.0]).shuffle(&mut entries, &mut rng);
+        assert_eq!(Rank::ThreeStars, entries[0].image_data.rank);
+        assert_eq!(4, entries.len());
+    }
+
+    #[test]
+    fn weights_are_parsed_by_stars() {
+        assert_eq!(Ok([1.0, 2.0, 4.0, 8.0]), parse_weights("1, 2,4,8"));
+        assert!(parse_weights("1,2,4").is_err());
+        assert!(parse_weights("0,0,0,0").is_err());
+        assert!(parse_weights("1,-2,4,8").is_err());
+    }
+}
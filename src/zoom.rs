@@ -0,0 +1,104 @@
+use std::fmt;
+
+// zoom steps, as scale factors from 25% to 800%
+pub const LEVELS: [f64; 12] = [0.25, 0.33, 0.5, 0.67, 0.75, 1.0, 1.5, 2.0, 3.0, 4.0, 6.0, 8.0];
+
+pub type Size = (f64, f64);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Zoom {
+    Fit, FitWidth, FitHeight, Scale(f64),
+}
+
+impl Zoom {
+    // scale factor of the picture shown in the viewport
+    pub fn scale(&self, picture: Size, viewport: Size) -> f64 {
+        let (picture_width, picture_height) = picture;
+        let (viewport_width, viewport_height) = viewport;
+        if picture_width <= 0.0 || picture_height <= 0.0 {
+            return 1.0
+        };
+        match self {
+            Zoom::Fit => (viewport_width / picture_width).min(viewport_height / picture_height),
+            Zoom::FitWidth => viewport_width / picture_width,
+            Zoom::FitHeight => viewport_height / picture_height,
+            Zoom::Scale(factor) => *factor,
+        }
+    }
+
+    pub fn size(&self, picture: Size, viewport: Size) -> Size {
+        let scale = self.scale(picture, viewport);
+        (picture.0 * scale, picture.1 * scale)
+    }
+
+    // next level from the scale actually shown, so that zooming in from fit goes on from there
+    pub fn zoom_in(&self, picture: Size, viewport: Size) -> Zoom {
+        let scale = self.scale(picture, viewport);
+        Zoom::Scale(*LEVELS.iter().find(|l| **l > scale + 0.001).unwrap_or(&LEVELS[LEVELS.len()-1]))
+    }
+
+    pub fn zoom_out(&self, picture: Size, viewport: Size) -> Zoom {
+        let scale = self.scale(picture, viewport);
+        Zoom::Scale(*LEVELS.iter().rev().find(|l| **l < scale - 0.001).unwrap_or(&LEVELS[0]))
+    }
+
+    pub fn next_fit(&self) -> Zoom {
+        match self {
+            Zoom::Fit => Zoom::FitWidth,
+            Zoom::FitWidth => Zoom::FitHeight,
+            Zoom::FitHeight | Zoom::Scale(_) => Zoom::Fit,
+        }
+    }
+}
+
+impl fmt::Display for Zoom {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Zoom::Fit => write!(f, "fit"),
+            Zoom::FitWidth => write!(f, "fit width"),
+            Zoom::FitHeight => write!(f, "fit height"),
+            Zoom::Scale(factor) => write!(f, "{:.0}%", factor * 100.0),
+        }
+    }
+}
+
+// scroll offset keeping the point under the anchor (pointer or viewport centre) in place after a scale change
+pub fn anchored_offset(offset: f64, anchor: f64, old_scale: f64, new_scale: f64) -> f64 {
+    if old_scale <= 0.0 {
+        return offset
+    };
+    ((offset + anchor) * new_scale / old_scale - anchor).max(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PICTURE: Size = (2000.0, 1000.0);
+    const VIEWPORT: Size = (1000.0, 1000.0);
+
+    #[test]
+    fn fit_modes_scale_to_the_viewport() {
+        assert_eq!(0.5, Zoom::Fit.scale(PICTURE, VIEWPORT));
+        assert_eq!((1000.0, 500.0), Zoom::FitWidth.size(PICTURE, VIEWPORT));
+        assert_eq!((2000.0, 1000.0), Zoom::FitHeight.size(PICTURE, VIEWPORT));
+        assert_eq!((4000.0, 2000.0), Zoom::Scale(2.0).size(PICTURE, VIEWPORT));
+    }
+
+    #[test]
+    fn zoom_steps_from_the_shown_scale_and_stays_in_range() {
+        assert_eq!(Zoom::Scale(0.67), Zoom::Fit.zoom_in(PICTURE, VIEWPORT));
+        assert_eq!(Zoom::Scale(0.33), Zoom::Fit.zoom_out(PICTURE, VIEWPORT));
+        assert_eq!(Zoom::Scale(8.0), Zoom::Scale(8.0).zoom_in(PICTURE, VIEWPORT));
+        assert_eq!(Zoom::Scale(0.25), Zoom::Scale(0.25).zoom_out(PICTURE, VIEWPORT));
+        assert_eq!("150%", Zoom::Scale(1.5).to_string());
+    }
+
+    #[test]
+    fn point_under_the_anchor_stays_in_place() {
+        // picture point at 300 px (scale 1) is under the anchor at 100 px with offset 200
+        let offset = anchored_offset(200.0, 100.0, 1.0, 2.0);
+        assert_eq!(500.0, offset);
+        assert_eq!(0.0, anchored_offset(0.0, 100.0, 2.0, 1.0));
+    }
+}